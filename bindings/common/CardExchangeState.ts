@@ -1,4 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { Card } from "./Card";
 
-export interface CardExchangeState { playerExchangeCards: Record<string, boolean>, yourExchangeCards: Array<Card>, }
\ No newline at end of file
+export interface CardExchangeState { playerExchangeCards: Record<string, boolean>, yourExchangeCards: Array<Card>, confirmedPlayers: Array<string>, }
\ No newline at end of file
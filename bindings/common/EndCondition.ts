@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type EndCondition = { "type": "maxScore", maxScore: number, } | { "type": "maxRounds", maxRounds: number, };
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MoveAnalysis } from "./MoveAnalysis";
+
+export interface GameAnalysis { player: string, moves: Array<MoveAnalysis>, pointsLost: number, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Card } from "./Card";
+import type { HintReason } from "./HintReason";
+
+export interface Hint { cards: Array<Card>, reason: HintReason, }
\ No newline at end of file
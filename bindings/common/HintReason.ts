@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type HintReason = "voidSuit" | "passHighCards" | "dumpHighSpade" | "dumpPoints" | "dumpHighCard" | "duckTrick" | "takeTrickLast" | "leadLow" | "playLow";
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChallengeResult } from "./ChallengeResult";
+
+export interface Leaderboard { date: string, results: Array<ChallengeResult>, }
\ No newline at end of file
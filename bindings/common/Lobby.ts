@@ -1,3 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EndCondition } from "./EndCondition";
+import type { TimeControl } from "./TimeControl";
+import type { TimeoutPolicy } from "./TimeoutPolicy";
 
-export interface Lobby { maxPlayers: number, maxScore: number, players: Array<string>, }
\ No newline at end of file
+export interface Lobby { maxPlayers: number, endCondition: EndCondition, takeBackCompletedTricks: boolean, timeControl: TimeControl | null, timeoutPolicy: TimeoutPolicy, confirmCardExchange: boolean, ruleScript: string | null, hints: boolean, tables: number, players: Array<string>, }
\ No newline at end of file
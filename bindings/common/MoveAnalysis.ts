@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Blunder } from "./Blunder";
+import type { Card } from "./Card";
+
+export interface MoveAnalysis { round: number, trick: number, card: Card, bestCard: Card | null, pointsLost: number | null, blunder: Blunder | null, }
\ No newline at end of file
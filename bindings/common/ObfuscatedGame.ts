@@ -1,5 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { Card } from "./Card";
 import type { GameSettings } from "./GameSettings";
+import type { PlayerClocks } from "./PlayerClocks";
+import type { TakeBackRequest } from "./TakeBackRequest";
 
-export interface ObfuscatedGame<S> { settings: GameSettings, players: Array<string>, scores: Record<string, number>, isFinished: boolean, playerDecks: Record<string, number>, yourCards: Array<Card>, state: S, }
\ No newline at end of file
+export interface ObfuscatedGame<S> { settings: GameSettings, players: Array<string>, round: number, dealer: string, scores: Record<string, number>, isFinished: boolean, playerDecks: Record<string, number>, yourCards: Array<Card>, leftPlayers: Array<string>, endVotes: Array<string>, takeBackRequest: TakeBackRequest | null, clocks: PlayerClocks | null, hintsUsed: Record<string, number>, state: S, }
\ No newline at end of file
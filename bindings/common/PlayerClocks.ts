@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface PlayerClocks { remainingMillis: Record<string, number>, runningPlayers: Array<string>, updatedAt: number | null, forfeitedPlayer: string | null, }
\ No newline at end of file
@@ -1,3 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EndCondition } from "../common/EndCondition";
+import type { InputCard } from "../common/InputCard";
+import type { TimeControl } from "../common/TimeControl";
+import type { TimeoutPolicy } from "../common/TimeoutPolicy";
 
-export interface CreateLobbyPayload { maxPlayers: number, maxScore: number, }
\ No newline at end of file
+export interface CreateLobbyPayload { maxPlayers: number, endCondition: EndCondition, takeBackCompletedTricks: boolean, timeControl: TimeControl | null, timeoutPolicy: TimeoutPolicy, confirmCardExchange: boolean, ruleScript: string | null, hints: boolean, tables: number | null, debugDeal: Array<Array<InputCard>> | null, }
\ No newline at end of file
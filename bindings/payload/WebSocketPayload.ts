@@ -1,8 +1,10 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AnswerTakeBackPayload } from "./AnswerTakeBackPayload";
 import type { CardExchangePayload } from "./CardExchangePayload";
 import type { ClaimReadinessPayload } from "./ClaimReadinessPayload";
 import type { CreateLobbyPayload } from "./CreateLobbyPayload";
 import type { IdPayload } from "./IdPayload";
 import type { PlaceCardPayload } from "./PlaceCardPayload";
+import type { StartTutorialPayload } from "./StartTutorialPayload";
 
-export type WebSocketPayload = { "action": "listLobbies" } | { "action": "getLobbyDetails" } & IdPayload | { "action": "createLobby" } & CreateLobbyPayload | { "action": "joinLobby" } & IdPayload | { "action": "quitLobby" } & IdPayload | { "action": "listGames" } | { "action": "getGameDetails" } & IdPayload | { "action": "cardExchangeMove" } & CardExchangePayload | { "action": "placeCardMove" } & PlaceCardPayload | { "action": "claimReadinessMove" } & ClaimReadinessPayload | { "action": "quitGame" } & IdPayload;
\ No newline at end of file
+export type WebSocketPayload = { "action": "listLobbies" } | { "action": "getLobbyDetails" } & IdPayload | { "action": "createLobby" } & CreateLobbyPayload | { "action": "joinLobby" } & IdPayload | { "action": "quitLobby" } & IdPayload | { "action": "listGames" } | { "action": "getGameDetails" } & IdPayload | { "action": "cardExchangeMove" } & CardExchangePayload | { "action": "placeCardMove" } & PlaceCardPayload | { "action": "claimReadinessMove" } & ClaimReadinessPayload | { "action": "quitGame" } & IdPayload | { "action": "voteEndGame" } & IdPayload | { "action": "requestTakeBack" } & IdPayload | { "action": "answerTakeBack" } & AnswerTakeBackPayload | { "action": "withdrawExchangeCards" } & IdPayload | { "action": "confirmExchangeCards" } & IdPayload | { "action": "startDailyChallenge" } | { "action": "getDailyLeaderboard" } | { "action": "getTournament" } & IdPayload | { "action": "getHint" } & IdPayload | { "action": "getGameAnalysis" } & IdPayload | { "action": "listLessons" } | { "action": "startTutorial" } & StartTutorialPayload | { "action": "continueTutorial" } & IdPayload;
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GameAnalysis } from "../common/GameAnalysis";
+
+export interface GameAnalysisResponse { id: string, analysis: GameAnalysis, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ListedLesson } from "../common/ListedLesson";
+
+export interface LessonListResponse { lessons: Array<ListedLesson>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TournamentStanding } from "../common/TournamentStanding";
+
+export interface TournamentResponse { id: string, tables: Array<Array<string>>, isFinished: boolean, standings: Array<TournamentStanding>, }
\ No newline at end of file
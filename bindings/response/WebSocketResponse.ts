@@ -9,5 +9,12 @@ import {CardExchangeState} from "../common/CardExchangeState";
 import {RoundInProgressState} from "../common/RoundInProgressState";
 import {RoundFinishedState} from "../common/RoundFinishedState";
 import {ErrorResponse} from "./ErrorResponse";
+import {Leaderboard} from "../common/Leaderboard";
+import {TournamentResponse} from "./TournamentResponse";
+import {HintResponse} from "./HintResponse";
+import {GameAnalysisResponse} from "./GameAnalysisResponse";
+import {LessonListResponse} from "./LessonListResponse";
+import {TutorialResponse} from "./TutorialResponse";
+import {PlayerConnectionResponse} from "./PlayerConnectionResponse";
 
-export type WebSocketResponse = { "type": "lobbyList" } & LobbyListResponse | { "type": "lobbyDetails" } & LobbyDetailsResponse | { "type": "LobbyDeleted" } & IdResponse | { "type": "gameList" } & GameListResponse | { "type": "gameDetailsCardExchange" } & GameDetailsResponse<CardExchangeState> | { "type": "gameDetailsRoundInProgress" } & GameDetailsResponse<RoundInProgressState> | { "type": "gameDetailsCardExchange" } & GameDetailsResponse<RoundFinishedState> | { "type": "gameDeleted" } & IdResponse | { "type": "error" } & ErrorResponse;
\ No newline at end of file
+export type WebSocketResponse = { "type": "lobbyList" } & LobbyListResponse | { "type": "lobbyDetails" } & LobbyDetailsResponse | { "type": "LobbyDeleted" } & IdResponse | { "type": "gameList" } & GameListResponse | { "type": "gameDetailsCardExchange" } & GameDetailsResponse<CardExchangeState> | { "type": "gameDetailsRoundInProgress" } & GameDetailsResponse<RoundInProgressState> | { "type": "gameDetailsCardExchange" } & GameDetailsResponse<RoundFinishedState> | { "type": "gameDeleted" } & IdResponse | { "type": "dailyLeaderboard" } & Leaderboard | { "type": "tournamentStandings" } & TournamentResponse | { "type": "hint" } & HintResponse | { "type": "gameAnalysis" } & GameAnalysisResponse | { "type": "lessonList" } & LessonListResponse | { "type": "tutorial" } & TutorialResponse | { "type": "playerReconnecting" } & PlayerConnectionResponse | { "type": "playerReconnected" } & PlayerConnectionResponse | { "type": "error" } & ErrorResponse;
\ No newline at end of file
@@ -0,0 +1,206 @@
+use crate::card::{Card, CardSuit};
+use crate::error::{GameError, GameResult};
+use crate::history::GameHistory;
+use crate::payload::PlaceCardPayload;
+use crate::solver::Solver;
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Points lost by a single card which make it a blunder.
+const BLUNDER_POINTS: usize = 7;
+
+/// Card play of a player compared with the double dummy solver, round by round.
+///
+/// The solver sees all hands, so the points lost are an upper bound of what the player could
+/// have saved with the information they had. Moves too early in a round for the solver to
+/// finish within the node limit are not rated. Passes and house rules are not analysed.
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+pub struct GameAnalysis {
+    pub player: String,
+    pub moves: Vec<MoveAnalysis>,
+    /// Summed over all rated moves.
+    #[serde(rename = "pointsLost")]
+    pub points_lost: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+pub struct MoveAnalysis {
+    /// Starting from 1, same as `trick`.
+    pub round: usize,
+    pub trick: usize,
+    pub card: Card,
+    /// `None` when the move was not rated.
+    #[serde(rename = "bestCard")]
+    pub best_card: Option<Card>,
+    #[serde(rename = "pointsLost")]
+    pub points_lost: Option<usize>,
+    pub blunder: Option<Blunder>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+pub enum Blunder {
+    /// The player took the trick with the queen of spades when they did not have to.
+    #[serde(rename = "queenOfSpadesToYourself")]
+    QueenOfSpadesToYourself,
+    #[serde(rename = "pointsThrownAway")]
+    PointsThrownAway,
+}
+
+impl GameAnalysis {
+    /// `node_limit` bounds the solver search of every single move.
+    pub fn new(history: &GameHistory, player: &str, node_limit: usize) -> GameResult<GameAnalysis> {
+        if !history.players.iter().any(|p| p == player) {
+            Err(GameError(format!("Player {} is not in the game", player)))?
+        }
+
+        let queen_of_spades = Card::new(CardSuit::Spade, 12).unwrap();
+        let mut moves = Vec::new();
+        for (index, round) in history.rounds.iter().enumerate() {
+            let mut step = history.replay_round_start(index, round)?;
+            let plays = round.tricks.iter().enumerate().flat_map(|(trick, plays)| {
+                plays
+                    .plays
+                    .iter()
+                    .map(move |(player, card)| (trick, player, *card))
+            });
+
+            // the starting card is placed when the round starts
+            for (trick, trick_player, card) in plays.skip(1) {
+                if trick_player == player {
+                    let evaluations = Solver::with_node_limit(node_limit).evaluate_cards(&step);
+                    let best = evaluations
+                        .as_ref()
+                        .and_then(|evaluations| evaluations.iter().min_by_key(|&&(_, p)| p));
+                    let played = evaluations.as_ref().and_then(|evaluations| {
+                        evaluations.iter().find(|(other, _)| other == &card)
+                    });
+                    let points_lost = best
+                        .zip(played)
+                        .map(|((_, best_points), (_, points))| points - best_points);
+
+                    let took_queen = round.tricks[trick].winner() == Some(player)
+                        && round.tricks[trick]
+                            .plays
+                            .iter()
+                            .any(|(_, card)| card == &queen_of_spades);
+                    moves.push(MoveAnalysis {
+                        round: index + 1,
+                        trick: trick + 1,
+                        card,
+                        best_card: best.map(|&(card, _)| card),
+                        points_lost,
+                        blunder: get_blunder(points_lost, took_queen),
+                    });
+                }
+                step.handle_payload(&PlaceCardPayload { card }, trick_player)?;
+            }
+        }
+
+        Ok(GameAnalysis {
+            player: player.to_string(),
+            points_lost: moves
+                .iter()
+                .filter_map(|analysis| analysis.points_lost)
+                .sum(),
+            moves,
+        })
+    }
+
+    pub fn blunders(&self) -> impl Iterator<Item = &MoveAnalysis> {
+        self.moves
+            .iter()
+            .filter(|analysis| analysis.blunder.is_some())
+    }
+}
+
+fn get_blunder(points_lost: Option<usize>, took_queen_of_spades: bool) -> Option<Blunder> {
+    match points_lost? {
+        0 => None,
+        _ if took_queen_of_spades => Some(Blunder::QueenOfSpadesToYourself),
+        points if points >= BLUNDER_POINTS => Some(Blunder::PointsThrownAway),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::LowestCardBot;
+    use crate::game::{EndCondition, Game, GameSettings};
+
+    fn get_history(seed: u64) -> GameHistory {
+        let players = vec!["1".to_string(), "2".to_string(), "3".to_string()];
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 1 },
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&players, settings, seed).unwrap();
+        for player in &players {
+            game.replace_with_bot(player).unwrap();
+        }
+        game.play_bots(&LowestCardBot);
+        game.history().clone()
+    }
+
+    #[test]
+    fn every_card_play_of_the_player_is_analysed() {
+        let history = get_history(7);
+        let analysis = GameAnalysis::new(&history, "2", 2_000).unwrap();
+
+        let played: Vec<Card> = history.rounds[0]
+            .tricks
+            .iter()
+            .flat_map(|trick| trick.plays.iter())
+            .skip(1)
+            .filter(|(player, _)| player == "2")
+            .map(|(_, card)| *card)
+            .collect();
+        let analysed: Vec<Card> = analysis
+            .moves
+            .iter()
+            .map(|analysis| analysis.card)
+            .collect();
+        assert_eq!(analysed, played);
+
+        // the end of the round is always within reach of the solver, with a single card left
+        let last_move = analysis.moves.last().unwrap();
+        assert_eq!(last_move.trick, 17);
+        assert_eq!(last_move.points_lost, Some(0));
+        assert_eq!(last_move.best_card, Some(last_move.card));
+        assert!(analysis.moves[0].points_lost.is_none());
+        assert_eq!(
+            analysis.points_lost,
+            analysis
+                .moves
+                .iter()
+                .filter_map(|m| m.points_lost)
+                .sum::<usize>()
+        );
+        assert!(GameAnalysis::new(&history, "4", 2_000).is_err());
+    }
+
+    #[test]
+    fn blunders_are_moves_losing_points() {
+        let history = get_history(7);
+        let analysis = GameAnalysis::new(&history, "2", 2_000).unwrap();
+        let blunders: Vec<&MoveAnalysis> = analysis.blunders().collect();
+
+        assert!(!blunders.is_empty());
+        for blunder in blunders {
+            assert!(blunder.points_lost.unwrap() > 0);
+            assert_ne!(blunder.best_card, Some(blunder.card));
+        }
+    }
+
+    #[test]
+    fn taking_the_queen_of_spades_is_the_worse_blunder() {
+        assert_eq!(get_blunder(None, true), None);
+        assert_eq!(get_blunder(Some(0), true), None);
+        assert_eq!(
+            get_blunder(Some(1), true),
+            Some(Blunder::QueenOfSpadesToYourself)
+        );
+        assert_eq!(get_blunder(Some(6), false), None);
+        assert_eq!(get_blunder(Some(7), false), Some(Blunder::PointsThrownAway));
+    }
+}
@@ -0,0 +1,50 @@
+//! Writes self-play records as JSON lines to stdout, see `game::dataset` for the schema.
+//!
+//! `$ cargo run -p game --bin export_dataset -- <games> [first seed] [players] [rounds] > data.jsonl`
+//!
+//! Game `n` is played from seed `first seed + n`.
+
+use game::dataset::{self_play, write_jsonl};
+use game::SimpleBot;
+use std::io::{BufWriter, Write};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let arg = |index: usize, default: u64| match args.get(index) {
+        Some(arg) => arg
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid argument {}", arg)),
+        None => Ok(default),
+    };
+    let parsed = (|| {
+        let rounds = arg(3, 1)?;
+        if rounds == 0 {
+            Err("At least one round has to be played".to_string())?
+        }
+        Ok::<_, String>((arg(0, 1)?, arg(1, 0)?, arg(2, 4)?, rounds))
+    })();
+    let (games, first_seed, players, rounds) = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut output = BufWriter::new(std::io::stdout().lock());
+    for seed in first_seed..first_seed + games {
+        let result = self_play(seed, players as usize, rounds as usize, &SimpleBot)
+            .map_err(|error| error.to_string())
+            .and_then(|records| write_jsonl(&records, &mut output).map_err(|e| e.to_string()));
+        if let Err(error) = result {
+            eprintln!("Game {}: {}", seed, error);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    match output.flush() {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
+    }
+}
@@ -0,0 +1,178 @@
+use crate::card::Card;
+use crate::view::{PlayerView, StateView};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+/// Decides the moves of a seat which is not controlled by a person.
+pub trait Bot {
+    fn choose_exchange_cards(&self, view: &PlayerView) -> HashSet<Card>;
+
+    /// `None` when it is not the bot's turn.
+    fn choose_card(&self, view: &PlayerView) -> Option<Card>;
+}
+
+/// Passes its most dangerous cards and ducks tricks whenever it can.
+pub struct SimpleBot;
+
+impl Bot for SimpleBot {
+    fn choose_exchange_cards(&self, view: &PlayerView) -> HashSet<Card> {
+        let mut cards: Vec<Card> = view.your_cards.iter().copied().collect();
+        cards.sort_by_key(|card| Reverse((card.score, card.value, card.suit)));
+        cards.into_iter().take(3).collect()
+    }
+
+    fn choose_card(&self, view: &PlayerView) -> Option<Card> {
+        let StateView::RoundInProgress(state) = &view.state else {
+            return None;
+        };
+        let legal_cards = view.legal_cards.clone();
+        let table_suit = match state.table_suit {
+            Some(table_suit) => table_suit,
+            None => {
+                return legal_cards
+                    .into_iter()
+                    .min_by_key(|card| (card.value, card.score))
+            }
+        };
+
+        if legal_cards.iter().all(|card| card.suit != table_suit) {
+            return legal_cards
+                .into_iter()
+                .max_by_key(|card| (card.score, card.value));
+        }
+
+        let winning_value = state
+            .cards_on_table
+            .values()
+            .filter(|card| card.suit == table_suit)
+            .map(|card| card.value)
+            .max()
+            .unwrap_or(0);
+        let ducking_card = legal_cards
+            .iter()
+            .filter(|card| card.value < winning_value)
+            .max_by_key(|card| card.value);
+        if let Some(&card) = ducking_card {
+            return Some(card);
+        }
+
+        // the trick is taken anyway when playing last, so get rid of the highest card without points
+        let is_last = state.cards_on_table.len() + 1 == view.players.len();
+        match is_last {
+            true => legal_cards
+                .into_iter()
+                .max_by_key(|card| (Reverse(card.score), card.value)),
+            false => legal_cards.into_iter().min_by_key(|card| card.value),
+        }
+    }
+}
+
+/// Always passes and plays its lowest cards, used for players who ran out of time.
+pub struct LowestCardBot;
+
+impl Bot for LowestCardBot {
+    fn choose_exchange_cards(&self, view: &PlayerView) -> HashSet<Card> {
+        let mut cards: Vec<Card> = view.your_cards.iter().copied().collect();
+        cards.sort_by_key(|card| (card.value, card.suit));
+        cards.into_iter().take(3).collect()
+    }
+
+    fn choose_card(&self, view: &PlayerView) -> Option<Card> {
+        view.legal_cards
+            .iter()
+            .copied()
+            .min_by_key(|card| (card.value, card.suit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameSettings;
+    use crate::step::round_in_progress::get_legal_cards_of_hand;
+    use crate::view::RoundInProgressView;
+    use std::collections::HashMap;
+
+    fn card(notation: &str) -> Card {
+        notation.parse().unwrap()
+    }
+
+    fn get_view(hand: &[&str], cards_on_table: &[(&str, &str)]) -> PlayerView {
+        let cards_on_table: HashMap<String, Card> = cards_on_table
+            .iter()
+            .map(|(player, notation)| (player.to_string(), card(notation)))
+            .collect();
+        let your_cards: HashSet<Card> = hand.iter().map(|notation| card(notation)).collect();
+        let table_suit = cards_on_table.values().next().map(|card| card.suit);
+
+        PlayerView {
+            player: "1".to_string(),
+            settings: GameSettings::default(),
+            players: vec!["1".to_string(), "2".to_string(), "3".to_string()],
+            round: 1,
+            dealer: "1".to_string(),
+            scores: HashMap::new(),
+            is_finished: false,
+            player_decks: HashMap::new(),
+            legal_cards: get_legal_cards_of_hand(&your_cards, table_suit),
+            your_cards,
+            left_players: Vec::new(),
+            end_votes: HashSet::new(),
+            take_back_request: None,
+            clocks: None,
+            hints_used: HashMap::new(),
+            state: StateView::RoundInProgress(RoundInProgressView {
+                current_player: "1".to_string(),
+                table_suit,
+                cards_on_table,
+            }),
+        }
+    }
+
+    #[test]
+    fn bot_passes_high_spades_and_hearts() {
+        let view = get_view(&["QS", "AS", "2H", "AH", "KD"], &[]);
+
+        assert_eq!(
+            SimpleBot.choose_exchange_cards(&view),
+            HashSet::from([card("QS"), card("AS"), card("AH")])
+        );
+    }
+
+    #[test]
+    fn bot_leads_lowest_card() {
+        let view = get_view(&["QS", "4D", "3C", "2H"], &[]);
+
+        assert_eq!(SimpleBot.choose_card(&view), Some(card("3C")));
+    }
+
+    #[test]
+    fn bot_ducks_under_winning_card() {
+        let view = get_view(&["2D", "9D", "KD"], &[("3", "10D")]);
+
+        assert_eq!(SimpleBot.choose_card(&view), Some(card("9D")));
+    }
+
+    #[test]
+    fn bot_dumps_queen_of_spades_when_void() {
+        let view = get_view(&["QS", "AC", "5H"], &[("3", "10D")]);
+
+        assert_eq!(SimpleBot.choose_card(&view), Some(card("QS")));
+    }
+
+    #[test]
+    fn lowest_card_bot_plays_lowest_legal_card() {
+        let view = get_view(&["QS", "4D", "9D", "2C"], &[("3", "10D")]);
+
+        assert_eq!(LowestCardBot.choose_card(&view), Some(card("4D")));
+    }
+
+    #[test]
+    fn bot_does_not_move_out_of_turn() {
+        let mut view = get_view(&["2D"], &[]);
+        view.player = "2".to_string();
+        view.legal_cards.clear();
+
+        assert_eq!(SimpleBot.choose_card(&view), None);
+    }
+}
@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use ts_rs::TS;
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Deserialize, Serialize, TS)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Deserialize, Serialize, TS)]
 pub enum CardSuit {
     #[serde(rename = "SPADE")]
     Spade,
@@ -27,6 +28,51 @@ impl Display for CardSuit {
     }
 }
 
+impl CardSuit {
+    fn letter(&self) -> char {
+        match self {
+            CardSuit::Spade => 'S',
+            CardSuit::Club => 'C',
+            CardSuit::Heart => 'H',
+            CardSuit::Diamond => 'D',
+        }
+    }
+
+    fn glyph(&self) -> char {
+        match self {
+            CardSuit::Spade => '♠',
+            CardSuit::Club => '♣',
+            CardSuit::Heart => '♥',
+            CardSuit::Diamond => '♦',
+        }
+    }
+}
+
+impl FromStr for CardSuit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<CardSuit, String> {
+        match s.to_uppercase().as_str() {
+            "SPADE" | "S" | "♠" | "♤" => Ok(CardSuit::Spade),
+            "CLUB" | "C" | "♣" | "♧" => Ok(CardSuit::Club),
+            "HEART" | "H" | "♥" | "♡" => Ok(CardSuit::Heart),
+            "DIAMOND" | "D" | "♦" | "♢" => Ok(CardSuit::Diamond),
+            _ => Err(format!("Invalid card suit: {}", s)),
+        }
+    }
+}
+
+/// Text notations supported by [`Card::to_notation`] and accepted by `Card::from_str`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CardNotation {
+    /// `SPADE_12`, same as `Display`
+    Long,
+    /// `QS`, `10H`, `2C`
+    Short,
+    /// `Q♠`, `10♥`, `2♣`
+    Unicode,
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Deserialize, Serialize, TS)]
 pub struct Card {
     pub suit: CardSuit,
@@ -36,7 +82,7 @@ pub struct Card {
 
 impl Display for Card {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "{}_{}", &self.suit, self.value.to_string())
+        write!(f, "{}_{}", &self.suit, self.value)
     }
 }
 
@@ -65,6 +111,96 @@ impl Card {
 
         Ok(Card { suit, value, score })
     }
+
+    pub fn to_notation(&self, notation: CardNotation) -> String {
+        match notation {
+            CardNotation::Long => self.to_string(),
+            CardNotation::Short => format!("{}{}", self.rank(), self.suit.letter()),
+            CardNotation::Unicode => format!("{}{}", self.rank(), self.suit.glyph()),
+        }
+    }
+
+    fn rank(&self) -> String {
+        match self.value {
+            11 => "J".to_string(),
+            12 => "Q".to_string(),
+            13 => "K".to_string(),
+            14 => "A".to_string(),
+            value => value.to_string(),
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = String;
+
+    /// Parses both the long (`SPADE_12`) and the short (`QS`, `10H`, `Q♠`) notation.
+    fn from_str(s: &str) -> Result<Card, String> {
+        let s = s.trim();
+        let (rank, suit) = match s.split_once('_') {
+            Some((suit, rank)) => (rank, suit),
+            None => {
+                let suit_start = s
+                    .char_indices()
+                    .last()
+                    .map(|(i, _)| i)
+                    .ok_or("Card notation cannot be empty".to_string())?;
+                s.split_at(suit_start)
+            }
+        };
+
+        let suit = suit.parse::<CardSuit>()?;
+        let value = match rank.to_uppercase().as_str() {
+            "J" => 11,
+            "Q" => 12,
+            "K" => 13,
+            "A" => 14,
+            "T" => 10,
+            rank => rank
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid card rank: {}", rank))?,
+        };
+
+        if value < 2 {
+            Err(format!("Invalid card rank: {}", rank))?
+        }
+
+        Card::new(suit, value)
+    }
+}
+
+/// Formats a set of cards sorted by suit, then by rank, e.g. `4S QS 2C 10H`.
+pub struct Hand<'a> {
+    cards: Vec<&'a Card>,
+    notation: CardNotation,
+}
+
+impl<'a> Hand<'a> {
+    pub fn new<I: IntoIterator<Item = &'a Card>>(cards: I) -> Hand<'a> {
+        let mut cards: Vec<&Card> = cards.into_iter().collect();
+        cards.sort_by_key(|card| (card.suit, card.value));
+
+        Hand {
+            cards,
+            notation: CardNotation::Short,
+        }
+    }
+
+    pub fn with_notation(mut self, notation: CardNotation) -> Hand<'a> {
+        self.notation = notation;
+        self
+    }
+}
+
+impl Display for Hand<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let cards: Vec<String> = self
+            .cards
+            .iter()
+            .map(|card| card.to_notation(self.notation))
+            .collect();
+        write!(f, "{}", cards.join(" "))
+    }
 }
 
 #[cfg(test)]
@@ -92,4 +228,80 @@ mod tests {
     fn cannot_create_card_with_value_greater_than_14() {
         assert!(Card::new(CardSuit::Spade, 15).is_err());
     }
+
+    #[test]
+    fn card_to_notation() {
+        let queen_of_spades = Card::new(CardSuit::Spade, 12).unwrap();
+        let ten_of_hearts = Card::new(CardSuit::Heart, 10).unwrap();
+
+        assert_eq!(queen_of_spades.to_notation(CardNotation::Long), "SPADE_12");
+        assert_eq!(queen_of_spades.to_notation(CardNotation::Short), "QS");
+        assert_eq!(queen_of_spades.to_notation(CardNotation::Unicode), "Q♠");
+        assert_eq!(ten_of_hearts.to_notation(CardNotation::Short), "10H");
+        assert_eq!(ten_of_hearts.to_notation(CardNotation::Unicode), "10♥");
+    }
+
+    #[test]
+    fn parse_card_from_every_notation() {
+        let queen_of_spades = Card::new(CardSuit::Spade, 12).unwrap();
+
+        for notation in ["SPADE_12", "QS", "qs", "Q♠", "12S", " QS "] {
+            assert_eq!(notation.parse::<Card>(), Ok(queen_of_spades));
+        }
+        assert_eq!("TH".parse::<Card>(), Card::new(CardSuit::Heart, 10));
+        assert_eq!("2C".parse::<Card>(), Card::new(CardSuit::Club, 2));
+    }
+
+    #[test]
+    fn card_notation_round_trips_for_whole_deck() {
+        for suit in [
+            CardSuit::Spade,
+            CardSuit::Club,
+            CardSuit::Heart,
+            CardSuit::Diamond,
+        ] {
+            for value in 2..=14 {
+                let card = Card::new(suit, value).unwrap();
+                for notation in [
+                    CardNotation::Long,
+                    CardNotation::Short,
+                    CardNotation::Unicode,
+                ] {
+                    assert_eq!(card.to_notation(notation).parse::<Card>(), Ok(card));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid_card() {
+        assert_eq!(
+            "QX".parse::<Card>(),
+            Err("Invalid card suit: X".to_string())
+        );
+        assert_eq!(
+            "1S".parse::<Card>(),
+            Err("Invalid card rank: 1".to_string())
+        );
+        assert_eq!(
+            "ZS".parse::<Card>(),
+            Err("Invalid card rank: Z".to_string())
+        );
+        assert!("15S".parse::<Card>().is_err());
+        assert!("".parse::<Card>().is_err());
+    }
+
+    #[test]
+    fn hand_is_sorted_by_suit_then_rank() {
+        let cards =
+            ["10H", "2C", "QS", "4S", "AD"].map(|notation| notation.parse::<Card>().unwrap());
+
+        assert_eq!(Hand::new(&cards).to_string(), "4S QS 2C 10H AD");
+        assert_eq!(
+            Hand::new(&cards)
+                .with_notation(CardNotation::Unicode)
+                .to_string(),
+            "4♠ Q♠ 2♣ 10♥ A♦"
+        );
+    }
 }
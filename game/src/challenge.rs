@@ -0,0 +1,287 @@
+use crate::clock::Clock;
+use crate::error::{GameError, GameResult};
+use crate::game::GameState::RoundFinished;
+use crate::game::{EndCondition, Game, GameSettings};
+use crate::step::ALL_POINTS;
+use serde::Serialize;
+use ts_rs::TS;
+
+const BOTS: [&str; 3] = ["Bot 1", "Bot 2", "Bot 3"];
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// A single round played by everyone from the same deal, against bots in the same seats.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyChallenge {
+    /// `YYYY-MM-DD` in UTC.
+    pub date: String,
+    pub seed: u64,
+}
+
+impl DailyChallenge {
+    pub fn for_date(date: &str) -> DailyChallenge {
+        DailyChallenge {
+            date: date.to_string(),
+            seed: get_seed_from_date(date),
+        }
+    }
+
+    pub fn today(clock: &impl Clock) -> DailyChallenge {
+        DailyChallenge::for_date(&get_date(clock.now() / MILLIS_PER_DAY))
+    }
+
+    /// The player always sits first, followed by the bots.
+    pub fn players(&self, player: &str) -> Vec<String> {
+        let mut players = vec![player.to_string()];
+        players.extend(BOTS.map(String::from));
+        players
+    }
+
+    pub fn start(&self, player: &str) -> GameResult<Game> {
+        if BOTS.contains(&player) {
+            Err(GameError(format!(
+                "Player {} cannot play the daily challenge",
+                player
+            )))?
+        }
+
+        // the bots accept every take back, the player could try every line of the shared deal
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 1 },
+            take_backs: false,
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&self.players(player), settings, self.seed)?;
+        for bot in BOTS {
+            game.replace_with_bot(bot)?;
+        }
+        Ok(game)
+    }
+
+    /// Only a round played to the end by the player counts.
+    pub fn result(&self, game: &Game, player: &str) -> Option<ChallengeResult> {
+        let finished = game.is_finished && matches!(game.state, RoundFinished(_));
+        if !finished || game.left_players.iter().any(|p| p == player) {
+            return None;
+        }
+
+        Some(ChallengeResult {
+            player: player.to_string(),
+            score: game.scores()[player],
+        })
+    }
+
+    /// Recorded for a player who quit, so that they cannot start the deal over. No round
+    /// scores worse.
+    pub fn forfeit(&self, player: &str) -> ChallengeResult {
+        ChallengeResult {
+            player: player.to_string(),
+            score: ALL_POINTS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+pub struct ChallengeResult {
+    pub player: String,
+    pub score: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, TS)]
+pub struct Leaderboard {
+    pub date: String,
+    /// Lowest score first, ties keep the order in which results were added.
+    pub results: Vec<ChallengeResult>,
+}
+
+impl Leaderboard {
+    pub fn new(date: &str) -> Leaderboard {
+        Leaderboard {
+            date: date.to_string(),
+            results: Vec::new(),
+        }
+    }
+
+    pub fn has_played(&self, player: &str) -> bool {
+        self.results.iter().any(|result| result.player == player)
+    }
+
+    /// Only the first result of a player is kept.
+    pub fn add(&mut self, result: ChallengeResult) -> GameResult<()> {
+        if self.has_played(&result.player) {
+            Err(GameError(format!(
+                "Player {} has already played the challenge of {}",
+                result.player, self.date
+            )))?
+        }
+
+        let index = self
+            .results
+            .partition_point(|other| other.score <= result.score);
+        self.results.insert(index, result);
+        Ok(())
+    }
+}
+
+// FNV-1a, the seed of a date has to stay the same across builds
+fn get_seed_from_date(date: &str) -> u64 {
+    date.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// civil date from days since the unix epoch, see http://howardhinnant.github.io/date_algorithms.html
+fn get_date(days: u64) -> String {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{Bot, SimpleBot};
+    use crate::clock::SystemClock;
+    use crate::game::GameState::CardExchange;
+    use crate::payload::{CardExchangePayload, PlaceCardPayload};
+
+    #[test]
+    fn date_is_taken_from_clock() {
+        struct FixedClock(u64);
+        impl Clock for FixedClock {
+            fn now(&self) -> u64 {
+                self.0
+            }
+        }
+
+        assert_eq!(DailyChallenge::today(&FixedClock(0)).date, "1970-01-01");
+        let millis = 1_709_251_199_000; // 2024-02-29 23:59:59
+        assert_eq!(
+            DailyChallenge::today(&FixedClock(millis)).date,
+            "2024-02-29"
+        );
+        assert_eq!(
+            DailyChallenge::today(&FixedClock(millis + 1000)).date,
+            "2024-03-01"
+        );
+        assert!(DailyChallenge::today(&SystemClock).date.as_str() > "2024-01-01");
+    }
+
+    #[test]
+    fn everyone_gets_the_same_deal() {
+        let challenge = DailyChallenge::for_date("2024-03-01");
+        let first_game = challenge.start("alice").unwrap();
+        let second_game = challenge.start("bob").unwrap();
+        let other_day_game = DailyChallenge::for_date("2024-03-02")
+            .start("alice")
+            .unwrap();
+
+        let hands =
+            |game: &Game, player: &str| game.history().rounds[0].dealt_hands[player].clone();
+        assert_eq!(hands(&first_game, "alice"), hands(&second_game, "bob"));
+        assert_eq!(hands(&first_game, "Bot 2"), hands(&second_game, "Bot 2"));
+        assert_ne!(hands(&first_game, "alice"), hands(&other_day_game, "alice"));
+    }
+
+    fn play_as(game: &mut Game, player: &str) {
+        game.play_bots(&SimpleBot);
+        while !game.is_finished {
+            let view = game.view_for(player).unwrap();
+            match &game.state {
+                CardExchange(_) => {
+                    let cards_to_exchange = SimpleBot.choose_exchange_cards(&view);
+                    game.exchange_cards(player, &CardExchangePayload { cards_to_exchange })
+                }
+                _ => {
+                    let card = SimpleBot.choose_card(&view).unwrap();
+                    game.place_card(player, &PlaceCardPayload { card })
+                }
+            }
+            .unwrap();
+            game.play_bots(&SimpleBot);
+        }
+    }
+
+    #[test]
+    fn result_is_the_score_of_finished_round() {
+        let challenge = DailyChallenge::for_date("2024-03-01");
+        let mut game = challenge.start("alice").unwrap();
+        play_as(&mut game, "alice");
+
+        let result = challenge.result(&game, "alice").unwrap();
+        assert_eq!(result.score, game.history().rounds[0].scores["alice"]);
+    }
+
+    #[test]
+    fn result_counts_only_when_player_finished_the_round() {
+        let challenge = DailyChallenge::for_date("2024-03-01");
+        let mut game = challenge.start("alice").unwrap();
+        game.play_bots(&SimpleBot);
+        assert_eq!(challenge.result(&game, "alice"), None);
+
+        game.replace_with_bot("alice").unwrap();
+        game.play_bots(&SimpleBot);
+        assert!(game.is_finished);
+        assert_eq!(challenge.result(&game, "alice"), None);
+    }
+
+    #[test]
+    fn cards_cannot_be_taken_back() {
+        let challenge = DailyChallenge::for_date("2024-03-01");
+        let mut game = challenge.start("alice").unwrap();
+        game.play_bots(&SimpleBot);
+        let view = game.view_for("alice").unwrap();
+        let cards_to_exchange = SimpleBot.choose_exchange_cards(&view);
+        game.exchange_cards("alice", &CardExchangePayload { cards_to_exchange })
+            .unwrap();
+        game.play_bots(&SimpleBot);
+        let view = game.view_for("alice").unwrap();
+        let card = SimpleBot.choose_card(&view).unwrap();
+        game.place_card("alice", &PlaceCardPayload { card })
+            .unwrap();
+
+        assert_eq!(
+            game.request_take_back("alice"),
+            Err(GameError(
+                "Cards cannot be taken back in this game".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn leaderboard_is_ordered_by_score() {
+        let mut leaderboard = Leaderboard::new("2024-03-01");
+        for (player, score) in [("alice", 10), ("bob", 3), ("carol", 10)] {
+            leaderboard
+                .add(ChallengeResult {
+                    player: player.to_string(),
+                    score,
+                })
+                .unwrap();
+        }
+        let result = ChallengeResult {
+            player: "bob".to_string(),
+            score: 0,
+        };
+
+        assert!(leaderboard.add(result).is_err());
+        let players: Vec<&str> = leaderboard
+            .results
+            .iter()
+            .map(|result| result.player.as_str())
+            .collect();
+        assert_eq!(players, vec!["bob", "alice", "carol"]);
+    }
+}
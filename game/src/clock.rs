@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use ts_rs::TS;
+
+/// Source of the current time in milliseconds, passed in so that clocks can be tested without waiting.
+pub trait Clock {
+    fn now(&self) -> u64;
+}
+
+/// Milliseconds since the unix epoch.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[serde(tag = "type")]
+pub enum TimeControl {
+    /// Time for the whole game, extended by the increment after every move.
+    #[serde(rename = "increment")]
+    Increment {
+        #[serde(rename = "baseMillis")]
+        #[ts(type = "number")]
+        base_millis: u64,
+        #[serde(rename = "incrementMillis")]
+        #[ts(type = "number")]
+        increment_millis: u64,
+    },
+    /// The same time for every move, unused time is not carried over.
+    #[serde(rename = "perMove")]
+    PerMove {
+        #[serde(rename = "moveMillis")]
+        #[ts(type = "number")]
+        move_millis: u64,
+    },
+}
+
+/// What happens to a player who runs out of time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+pub enum TimeoutPolicy {
+    /// The lowest legal card is played for them.
+    #[default]
+    #[serde(rename = "autoPlay")]
+    AutoPlay,
+    /// The game ends.
+    #[serde(rename = "forfeit")]
+    Forfeit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+pub struct PlayerClocks {
+    #[serde(rename = "remainingMillis")]
+    #[ts(type = "Record<string, number>")]
+    pub remaining_millis: HashMap<String, u64>,
+    /// Players whose time is running since `updated_at`.
+    #[serde(rename = "runningPlayers")]
+    pub running_players: Vec<String>,
+    #[serde(rename = "updatedAt")]
+    #[ts(type = "number | null")]
+    pub updated_at: Option<u64>,
+    #[serde(rename = "forfeitedPlayer")]
+    pub forfeited_player: Option<String>,
+}
+
+impl PlayerClocks {
+    pub fn new(players: &[String], time_control: TimeControl) -> PlayerClocks {
+        let millis = match time_control {
+            TimeControl::Increment { base_millis, .. } => base_millis,
+            TimeControl::PerMove { move_millis } => move_millis,
+        };
+
+        PlayerClocks {
+            remaining_millis: players
+                .iter()
+                .map(|player| (player.clone(), millis))
+                .collect(),
+            running_players: Vec::new(),
+            updated_at: None,
+            forfeited_player: None,
+        }
+    }
+
+    /// Charges the time since the last update to the running players.
+    pub(crate) fn run_until(&mut self, now: u64) {
+        let elapsed = now.saturating_sub(self.updated_at.unwrap_or(now));
+        for player in &self.running_players {
+            let remaining = self.remaining_millis.get_mut(player).unwrap();
+            *remaining = remaining.saturating_sub(elapsed);
+        }
+        self.updated_at = Some(now);
+    }
+
+    pub(crate) fn record_move(&mut self, player: &str, time_control: TimeControl) {
+        let remaining = self.remaining_millis.get_mut(player).unwrap();
+        match time_control {
+            TimeControl::Increment {
+                increment_millis, ..
+            } => *remaining += increment_millis,
+            TimeControl::PerMove { move_millis } => *remaining = move_millis,
+        }
+    }
+
+    pub(crate) fn take_back_move(&mut self, player: &str, time_control: TimeControl) {
+        let remaining = self.remaining_millis.get_mut(player).unwrap();
+        match time_control {
+            TimeControl::Increment {
+                increment_millis, ..
+            } => *remaining = remaining.saturating_sub(increment_millis),
+            // the move is made again with the full time
+            TimeControl::PerMove { move_millis } => *remaining = move_millis,
+        }
+    }
+
+    pub(crate) fn timed_out_player(&self) -> Option<&String> {
+        self.running_players
+            .iter()
+            .find(|&player| self.remaining_millis[player] == 0)
+    }
+
+    /// Milliseconds until the first of the running players runs out of time.
+    pub fn next_timeout(&self, now: u64) -> Option<u64> {
+        let elapsed = now.saturating_sub(self.updated_at?);
+        self.running_players
+            .iter()
+            .map(|player| self.remaining_millis[player].saturating_sub(elapsed))
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::SimpleBot;
+    use crate::card::Card;
+    use crate::game::GameState::{CardExchange, RoundInProgress};
+    use crate::game::{Game, GameSettings};
+    use crate::payload::CardExchangePayload;
+    use std::cell::Cell;
+    use std::collections::HashSet;
+
+    struct TestClock(Cell<u64>);
+
+    impl TestClock {
+        fn advance(&self, millis: u64) {
+            self.0.set(self.0.get() + millis);
+        }
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    fn get_players() -> Vec<String> {
+        vec!["1".to_string(), "2".to_string(), "3".to_string()]
+    }
+
+    fn get_game(time_control: TimeControl, timeout_policy: TimeoutPolicy) -> Game {
+        let settings = GameSettings {
+            time_control: Some(time_control),
+            timeout_policy,
+            ..GameSettings::default()
+        };
+        Game::from_seed(&get_players(), settings, 7).unwrap()
+    }
+
+    fn exchange_cards(game: &mut Game, player: &str) {
+        let CardExchange(step) = &mut game.state else {
+            panic!("Game should be in card exchange")
+        };
+        let cards_to_exchange: HashSet<Card> =
+            step.player_decks[player].iter().copied().take(3).collect();
+        step.handle_payload(&CardExchangePayload { cards_to_exchange }, player)
+            .unwrap();
+        game.record_move(player);
+    }
+
+    #[test]
+    fn increment_is_added_after_move() {
+        let clock = TestClock(Cell::new(1000));
+        let increment = TimeControl::Increment {
+            base_millis: 10_000,
+            increment_millis: 2_000,
+        };
+        let mut game = get_game(increment, TimeoutPolicy::AutoPlay);
+        game.advance(&clock, &SimpleBot);
+
+        clock.advance(3_000);
+        game.advance(&clock, &SimpleBot);
+        exchange_cards(&mut game, "1");
+        game.advance(&clock, &SimpleBot);
+
+        let clocks = game.clocks.as_ref().unwrap();
+        assert_eq!(clocks.remaining_millis["1"], 9_000);
+        assert_eq!(clocks.remaining_millis["2"], 7_000);
+        assert_eq!(
+            clocks.running_players,
+            vec!["2".to_string(), "3".to_string()]
+        );
+        assert_eq!(clocks.next_timeout(clock.now() + 1_000), Some(6_000));
+    }
+
+    #[test]
+    fn per_move_time_is_reset_after_move() {
+        let clock = TestClock(Cell::new(0));
+        let per_move = TimeControl::PerMove { move_millis: 5_000 };
+        let mut game = get_game(per_move, TimeoutPolicy::AutoPlay);
+        game.advance(&clock, &SimpleBot);
+
+        clock.advance(4_000);
+        game.advance(&clock, &SimpleBot);
+        exchange_cards(&mut game, "1");
+
+        let clocks = game.clocks.as_ref().unwrap();
+        assert_eq!(clocks.remaining_millis["1"], 5_000);
+        assert_eq!(clocks.remaining_millis["2"], 1_000);
+    }
+
+    #[test]
+    fn lowest_cards_are_played_for_player_out_of_time() {
+        let clock = TestClock(Cell::new(0));
+        let per_move = TimeControl::PerMove { move_millis: 5_000 };
+        let mut game = get_game(per_move, TimeoutPolicy::AutoPlay);
+        game.advance(&clock, &SimpleBot);
+        for player in ["1", "2"] {
+            exchange_cards(&mut game, player);
+        }
+
+        clock.advance(5_000);
+        game.advance(&clock, &SimpleBot);
+
+        let RoundInProgress(step) = &game.state else {
+            panic!("Cards should be exchanged for player 3")
+        };
+        let mut cards: Vec<Card> = step.history.rounds[0].dealt_hands["3"]
+            .iter()
+            .copied()
+            .collect();
+        cards.sort_by_key(|card| (card.value, card.suit));
+        let lowest_cards = HashSet::from_iter(cards.into_iter().take(3));
+        assert_eq!(step.history.rounds[0].passes["3"], lowest_cards);
+        assert!(!game.is_finished);
+    }
+
+    #[test]
+    fn game_ends_when_player_forfeits_on_time() {
+        let clock = TestClock(Cell::new(0));
+        let per_move = TimeControl::PerMove { move_millis: 5_000 };
+        let mut game = get_game(per_move, TimeoutPolicy::Forfeit);
+        game.advance(&clock, &SimpleBot);
+        for player in ["1", "3"] {
+            exchange_cards(&mut game, player);
+        }
+
+        clock.advance(6_000);
+        game.advance(&clock, &SimpleBot);
+
+        let clocks = game.clocks.as_ref().unwrap();
+        assert!(game.is_finished);
+        assert_eq!(clocks.forfeited_player, Some("2".to_string()));
+        assert_eq!(clocks.next_timeout(clock.now()), None);
+    }
+}
@@ -0,0 +1,228 @@
+//! Training data from seeded self-play, one record per decision.
+//!
+//! Records are written as JSON lines with the following fields:
+//!
+//! - `game`: seed the game was played from, the same seed gives the same records
+//! - `round`: starting from 1
+//! - `player`: who made the decision
+//! - `decision`: `"exchange"` (choosing the cards to pass) or `"play"` (placing a card)
+//! - `view`: [`PlayerView`] of the player right before the decision, the same one the server
+//!   sends to them, so it holds nothing the player could not see
+//! - `legalMoves`: cards which could be chosen, any three of them for `"exchange"`
+//! - `action`: chosen cards, three for `"exchange"` and one for `"play"`
+//! - `roundPoints`: points the player ended up taking in the round, after shooting the moon
+//!
+//! Cards are `{"suit": "SPADE", "value": 12, "score": 13}` everywhere.
+//! The starting card is played automatically, so it is not a decision.
+
+use crate::bot::Bot;
+use crate::card::Card;
+use crate::error::{GameError, GameResult};
+use crate::game::{EndCondition, Game, GameSettings};
+use crate::payload::{CardExchangePayload, ClaimReadinessPayload, PlaceCardPayload};
+use crate::view::{PlayerView, StateView};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Exchange,
+    Play,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatasetRecord {
+    pub game: u64,
+    pub round: usize,
+    pub player: String,
+    pub decision: Decision,
+    pub view: PlayerView,
+    #[serde(rename = "legalMoves")]
+    pub legal_moves: Vec<Card>,
+    pub action: Vec<Card>,
+    #[serde(rename = "roundPoints")]
+    pub round_points: usize,
+}
+
+/// Plays a whole game of `rounds` deals with `bot` in every seat.
+pub fn self_play(
+    seed: u64,
+    players: usize,
+    rounds: usize,
+    bot: &impl Bot,
+) -> GameResult<Vec<DatasetRecord>> {
+    if rounds == 0 {
+        Err(GameError("At least one round has to be played".to_string()))?
+    }
+    let players: Vec<String> = (1..=players).map(|seat| seat.to_string()).collect();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let settings = GameSettings {
+        end_condition: EndCondition::MaxRounds { max_rounds: rounds },
+        deal_seeds: (0..rounds).map(|_| rng.next_u64()).collect(),
+        ..GameSettings::default()
+    };
+    let mut game = Game::from_seed(&players, settings.clone(), settings.deal_seeds[0])?;
+
+    let mut records = Vec::new();
+    while !game.is_finished {
+        for player in &players {
+            let view = game.view_for(player)?;
+            if let Some(record) = play_decision(&mut game, seed, view, bot)? {
+                records.push(record);
+            }
+        }
+    }
+
+    let history = game.history();
+    for record in &mut records {
+        let index = record.round - 1;
+        let scores_before = history.scores_before_round(index);
+        let before = scores_before.get(&record.player).copied().unwrap_or(0);
+        record.round_points = history.rounds[index].scores[&record.player] - before;
+    }
+    Ok(records)
+}
+
+// makes the move of the player if they have one, readiness for the next round is not a decision
+fn play_decision(
+    game: &mut Game,
+    seed: u64,
+    view: PlayerView,
+    bot: &impl Bot,
+) -> GameResult<Option<DatasetRecord>> {
+    let player = view.player.clone();
+    let (decision, legal_moves, action) = match &view.state {
+        StateView::CardExchange(state) if state.your_exchange_cards.is_empty() => {
+            let cards_to_exchange = bot.choose_exchange_cards(&view);
+            let mut legal_moves: Vec<Card> = view.your_cards.iter().copied().collect();
+            legal_moves.sort_by_key(|card| (card.suit, card.value));
+            let mut action: Vec<Card> = cards_to_exchange.iter().copied().collect();
+            action.sort_by_key(|card| (card.suit, card.value));
+            game.exchange_cards(&player, &CardExchangePayload { cards_to_exchange })?;
+            (Decision::Exchange, legal_moves, action)
+        }
+        StateView::RoundInProgress(state) if state.current_player == player => {
+            let legal_moves = view.legal_cards.clone();
+            let card = bot
+                .choose_card(&view)
+                .filter(|card| legal_moves.contains(card))
+                .unwrap_or(legal_moves[0]);
+            game.place_card(&player, &PlaceCardPayload { card })?;
+            (Decision::Play, legal_moves, vec![card])
+        }
+        StateView::RoundFinished(state) if state.players_ready.get(&player) != Some(&true) => {
+            game.claim_readiness(&player, &ClaimReadinessPayload { ready: true })?;
+            return Ok(None);
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(DatasetRecord {
+        game: seed,
+        round: view.round,
+        player,
+        decision,
+        view,
+        legal_moves,
+        action,
+        round_points: 0,
+    }))
+}
+
+/// One JSON object per line.
+pub fn write_jsonl(records: &[DatasetRecord], mut writer: impl Write) -> std::io::Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::SimpleBot;
+
+    #[test]
+    fn self_play_records_every_decision() {
+        let records = self_play(7, 3, 2, &SimpleBot).unwrap();
+
+        let count = |decision: Decision| {
+            records
+                .iter()
+                .filter(|record| record.decision == decision)
+                .count()
+        };
+        assert_eq!(count(Decision::Exchange), 2 * 3);
+        assert_eq!(count(Decision::Play), 2 * 50);
+        assert_eq!(records, self_play(7, 3, 2, &SimpleBot).unwrap());
+    }
+
+    #[test]
+    fn records_hold_only_what_the_player_sees() {
+        for record in self_play(7, 4, 1, &SimpleBot).unwrap() {
+            assert_eq!(record.view.player, record.player);
+            assert!(record
+                .action
+                .iter()
+                .all(|card| record.legal_moves.contains(card)));
+            assert!(record
+                .legal_moves
+                .iter()
+                .all(|card| record.view.your_cards.contains(card)));
+            assert!(!record.view.player_decks.contains_key(&record.player));
+        }
+    }
+
+    #[test]
+    fn round_points_add_up_to_final_scores() {
+        let records = self_play(11, 4, 2, &SimpleBot).unwrap();
+
+        // bots in every seat play the same game as long as they see the same views
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 2 },
+            deal_seeds: (0..2).map(|_| rng.next_u64()).collect(),
+            ..GameSettings::default()
+        };
+        let players = records[0].view.players.clone();
+        let mut game = Game::from_seed(&players, settings.clone(), settings.deal_seeds[0]).unwrap();
+        for player in &players {
+            game.replace_with_bot(player).unwrap();
+        }
+        game.play_bots(&SimpleBot);
+
+        for player in &players {
+            let total: usize = records
+                .iter()
+                .filter(|record| &record.player == player && record.decision == Decision::Exchange)
+                .map(|record| record.round_points)
+                .sum();
+            assert_eq!(total, game.scores()[player]);
+        }
+    }
+
+    #[test]
+    fn self_play_needs_a_round() {
+        assert_eq!(
+            self_play(7, 4, 0, &SimpleBot),
+            Err(GameError("At least one round has to be played".to_string()))
+        );
+    }
+
+    #[test]
+    fn records_are_written_as_json_lines() {
+        let records = self_play(7, 3, 1, &SimpleBot).unwrap();
+        let mut output = Vec::new();
+        write_jsonl(&records, &mut output).unwrap();
+
+        let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert_eq!(lines.len(), records.len());
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["decision"], "exchange");
+        assert_eq!(first["action"].as_array().unwrap().len(), 3);
+    }
+}
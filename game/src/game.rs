@@ -1,10 +1,24 @@
+use crate::bot::{Bot, LowestCardBot};
+use crate::card::Card;
+use crate::clock::{Clock, PlayerClocks, TimeControl, TimeoutPolicy};
 use crate::error::{GameError, GameResult};
-use crate::game::GameState::CardExchange;
+use crate::game::GameState::{CardExchange, RoundFinished, RoundInProgress};
+use crate::helper::get_full_deck;
+#[cfg(feature = "thread-rng")]
+use crate::helper::get_random_seed;
+use crate::hint::Hint;
+use crate::history::GameHistory;
+use crate::observer::{GameObserver, GameObservers};
+use crate::payload::{CardExchangePayload, ClaimReadinessPayload, PlaceCardPayload};
+use crate::rules::HouseRules;
 use crate::step::card_exchange::CardExchangeState;
 use crate::step::round_finished::RoundFinishedState;
 use crate::step::round_in_progress::RoundInProgressState;
 use crate::step::GameStep;
-use serde::Serialize;
+use crate::view::{PlayerView, SpectatorView};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use ts_rs::TS;
 
 #[derive(Debug, Clone)]
@@ -13,28 +27,743 @@ pub struct Game {
     pub players: Vec<String>,
     pub state: GameState,
     pub is_finished: bool,
+    pub left_players: Vec<String>,
+    pub end_votes: HashSet<String>,
+    pub take_back_request: Option<TakeBackRequest>,
+    pub clocks: Option<PlayerClocks>,
+    /// Number of hints every player asked for.
+    pub hints_used: HashMap<String, usize>,
+    observers: GameObservers,
 }
 
 impl Game {
+    #[cfg(feature = "thread-rng")]
     pub fn from_players(players: &[String], settings: GameSettings) -> GameResult<Game> {
-        let number_of_players = players.len();
-        if number_of_players < 3 || number_of_players > 4 {
-            Err(GameError("Invalid number of players".to_string()))?
+        let seed = settings
+            .deal_seeds
+            .first()
+            .copied()
+            .unwrap_or_else(get_random_seed);
+        Game::from_seed(players, settings, seed)
+    }
+
+    /// Same as `from_players`, but the first deal is shuffled from the given seed,
+    /// unless the settings hold a preset deal.
+    pub fn from_seed(players: &[String], settings: GameSettings, seed: u64) -> GameResult<Game> {
+        validate_number_of_players(players)?;
+        settings.validate_deal(players.len())?;
+        match &settings.deal {
+            Some(deal) => {
+                let player_decks = players
+                    .iter()
+                    .cloned()
+                    .zip(deal.iter().map(|hand| hand.iter().copied().collect()))
+                    .collect();
+                Game::from_deal(players, settings, player_decks)
+            }
+            None => Game::from_step(GameStep::initialize_from_seed(players, seed), settings),
         }
+    }
+
+    /// Same as `from_seed`, but the first round starts with the given hands.
+    pub(crate) fn from_deal(
+        players: &[String],
+        settings: GameSettings,
+        player_decks: HashMap<String, HashSet<Card>>,
+    ) -> GameResult<Game> {
+        validate_number_of_players(players)?;
+        Game::from_step(
+            GameStep::initialize_from_decks(players, player_decks),
+            settings,
+        )
+    }
+
+    pub(crate) fn from_step(
+        mut step: GameStep<CardExchangeState>,
+        settings: GameSettings,
+    ) -> GameResult<Game> {
+        let players = step.players.clone();
+        step.house_rules = settings
+            .rule_script
+            .as_deref()
+            .map(HouseRules::compile)
+            .transpose()?;
 
         Ok(Game {
+            clocks: settings
+                .time_control
+                .map(|time_control| PlayerClocks::new(&players, time_control)),
             settings,
-            players: players.to_vec(),
-            state: GameState::get_initial_state(players),
+            players,
+            state: CardExchange(step),
             is_finished: false,
+            left_players: Vec::new(),
+            end_votes: HashSet::new(),
+            take_back_request: None,
+            hints_used: HashMap::new(),
+            observers: GameObservers::default(),
         })
     }
+
+    /// The deal of the current round is reported right away when cards are not exchanged yet,
+    /// so observers added at the start of the game see every round.
+    pub fn add_observer(&mut self, observer: Arc<dyn GameObserver>) {
+        if let CardExchange(step) = &self.state {
+            if let Some(round) = step.history.rounds.last() {
+                observer.on_deal(step.history.rounds.len(), &round.dealt_hands);
+            }
+        }
+        self.observers.add(observer);
+    }
+
+    pub fn history(&self) -> &GameHistory {
+        match &self.state {
+            CardExchange(step) => &step.history,
+            RoundInProgress(step) => &step.history,
+            RoundFinished(step) => &step.history,
+        }
+    }
+
+    pub fn scores(&self) -> &HashMap<String, usize> {
+        match &self.state {
+            CardExchange(step) => &step.scores,
+            RoundInProgress(step) => &step.scores,
+            RoundFinished(step) => &step.scores,
+        }
+    }
+
+    /// Number of the current round, starting from 1.
+    pub fn round(&self) -> usize {
+        self.history().rounds.len()
+    }
+
+    /// The deal moves to the next player every round.
+    pub fn dealer(&self) -> &str {
+        let round = self.round().max(1);
+        &self.players[(round - 1) % self.players.len()]
+    }
+
+    /// Finishes the game when a round is over and the end condition from settings is met.
+    pub fn finish_if_ended(&mut self) {
+        let RoundFinished(step) = &self.state else {
+            return;
+        };
+
+        let ended = match self.settings.end_condition {
+            EndCondition::MaxScore { max_score } => step.game_finished(max_score),
+            EndCondition::MaxRounds { max_rounds } => step.history.rounds.len() >= max_rounds,
+        };
+        if ended {
+            self.finish();
+        }
+    }
+
+    fn finish(&mut self) {
+        if !self.is_finished {
+            self.is_finished = true;
+            self.observers
+                .notify(|observer| observer.on_game_over(self.scores()));
+        }
+    }
+
+    pub fn view_for(&self, player: &str) -> GameResult<PlayerView> {
+        PlayerView::new(self, player)
+    }
+
+    pub fn spectator_view(&self) -> SpectatorView {
+        SpectatorView::new(self)
+    }
+
+    /// Suggests the current move of the player from their view, counted in `hints_used`.
+    pub fn hint(&mut self, player: &str, bot: &impl Bot) -> GameResult<Hint> {
+        if !self.settings.hints {
+            Err(GameError("Hints are disabled in this game".to_string()))?
+        }
+        self.validate_active_player(player)?;
+
+        let view = self.view_for(player)?;
+        let hint = Hint::for_view(&view, bot)
+            .ok_or(GameError(format!("Player {} has no move to make", player)))?;
+        *self.hints_used.entry(player.to_string()).or_insert(0) += 1;
+        Ok(hint)
+    }
+
+    /// Players who did not leave the game, bots play for the others.
+    pub fn active_players(&self) -> Vec<String> {
+        self.players
+            .iter()
+            .filter(|&player| !self.left_players.contains(player))
+            .cloned()
+            .collect()
+    }
+
+    fn validate_active_player(&self, player: &str) -> GameResult<()> {
+        if !self.active_players().iter().any(|p| p == player) {
+            Err(GameError(format!(
+                "Player {} does not participate in the game",
+                player
+            )))?
+        }
+
+        Ok(())
+    }
+
+    /// The seat of a leaving player is taken over by a bot, keeping its cards and score.
+    pub fn replace_with_bot(&mut self, player: &str) -> GameResult<()> {
+        self.validate_active_player(player)?;
+        self.left_players.push(player.to_string());
+        self.end_votes.remove(player);
+        self.finish_if_everyone_voted();
+
+        Ok(())
+    }
+
+    pub fn vote_to_end(&mut self, player: &str) -> GameResult<()> {
+        self.validate_active_player(player)?;
+        if self.is_finished {
+            Err(GameError("Game is already finished".to_string()))?
+        }
+
+        self.end_votes.insert(player.to_string());
+        self.finish_if_everyone_voted();
+
+        Ok(())
+    }
+
+    fn finish_if_everyone_voted(&mut self) {
+        let active_players = self.active_players();
+        if !self.end_votes.is_empty()
+            && active_players
+                .iter()
+                .all(|player| self.end_votes.contains(player))
+        {
+            self.finish();
+        }
+    }
+
+    /// Asks the other active players to let the player take back the last card they placed.
+    /// Cards which bot seats placed after it are taken back as well.
+    pub fn request_take_back(&mut self, player: &str) -> GameResult<()> {
+        self.validate_active_player(player)?;
+        if !self.settings.take_backs {
+            Err(GameError(
+                "Cards cannot be taken back in this game".to_string(),
+            ))?
+        }
+        if self.take_back_request.is_some() {
+            Err(GameError("Take back was already requested".to_string()))?
+        }
+
+        let RoundInProgress(step) = &self.state else {
+            Err(GameError(
+                "Cards can only be taken back during a round".to_string(),
+            ))?
+        };
+        let plays = self.take_back_plays(&mut step.clone(), player)?;
+        let card = plays.last().unwrap().1;
+
+        self.take_back_request = Some(TakeBackRequest {
+            player: player.to_string(),
+            card,
+            accepted: HashSet::new(),
+        });
+        self.take_back_if_everyone_accepted()
+    }
+
+    /// A single rejection cancels the request, the card is taken back once everyone accepted.
+    pub fn answer_take_back(&mut self, player: &str, accept: bool) -> GameResult<()> {
+        self.validate_active_player(player)?;
+        let request = self
+            .take_back_request
+            .as_mut()
+            .ok_or(GameError("Take back was not requested".to_string()))?;
+        if request.player == player {
+            Err(GameError(
+                "Cannot answer your own take back request".to_string(),
+            ))?
+        }
+
+        match accept {
+            true => {
+                request.accepted.insert(player.to_string());
+                self.take_back_if_everyone_accepted()
+            }
+            false => {
+                self.take_back_request = None;
+                Ok(())
+            }
+        }
+    }
+
+    fn take_back_if_everyone_accepted(&mut self) -> GameResult<()> {
+        let request = self.take_back_request.as_ref().unwrap();
+        let everyone_accepted = self
+            .active_players()
+            .iter()
+            .all(|player| player == &request.player || request.accepted.contains(player));
+        if !everyone_accepted {
+            return Ok(());
+        }
+
+        let request = self.take_back_request.take().unwrap();
+        let RoundInProgress(step) = &self.state else {
+            Err(GameError("Take back request is outdated".to_string()))?
+        };
+
+        // the request fails if another player placed a card after it
+        let mut step = step.clone();
+        let plays = match self.take_back_plays(&mut step, &request.player) {
+            Ok(plays) if plays.last().unwrap().1 == request.card => plays,
+            _ => Err(GameError("Take back request is outdated".to_string()))?,
+        };
+        self.state = RoundInProgress(step);
+        for (player, card) in &plays {
+            self.take_back_move(player);
+            self.observers
+                .notify(|observer| observer.on_card_taken_back(player, *card));
+        }
+        Ok(())
+    }
+
+    // bot seats move right after the player, so their cards are taken back first, last one first
+    fn take_back_plays(
+        &self,
+        step: &mut GameStep<RoundInProgressState>,
+        player: &str,
+    ) -> GameResult<Vec<(String, Card)>> {
+        let mut plays = Vec::new();
+        loop {
+            let last_player = match step.history.last_play() {
+                Some((last_player, _)) if self.left_players.contains(last_player) => {
+                    last_player.clone()
+                }
+                _ => player.to_string(),
+            };
+            let card =
+                step.take_back_last_card(&last_player, self.settings.take_back_completed_tricks)?;
+            plays.push((last_player.clone(), card));
+            if last_player == player {
+                return Ok(plays);
+            }
+        }
+    }
+
+    /// Adds the increment, or resets the per move time, after the player made a move.
+    pub fn record_move(&mut self, player: &str) {
+        if let (Some(clocks), Some(time_control)) = (&mut self.clocks, self.settings.time_control) {
+            clocks.record_move(player, time_control);
+        }
+    }
+
+    /// Takes away the time added for a move which was taken back.
+    fn take_back_move(&mut self, player: &str) {
+        if let (Some(clocks), Some(time_control)) = (&mut self.clocks, self.settings.time_control) {
+            clocks.take_back_move(player, time_control);
+        }
+    }
+
+    /// Charges the time since the last call to the players who have to move and applies
+    /// the timeout policy to those who ran out of it. Bot seats move right away.
+    pub fn advance(&mut self, clock: &impl Clock, bot: &impl Bot) {
+        if let Some(clocks) = &mut self.clocks {
+            clocks.run_until(clock.now());
+        }
+
+        loop {
+            self.play_bots(bot);
+            let players_on_clock = match self.is_finished {
+                true => Vec::new(),
+                false => self.players_on_clock(),
+            };
+            let Some(clocks) = &mut self.clocks else {
+                return;
+            };
+            clocks.running_players = players_on_clock;
+
+            let Some(player) = clocks.timed_out_player().cloned() else {
+                return;
+            };
+            match self.settings.timeout_policy {
+                TimeoutPolicy::AutoPlay => {
+                    if !self.play_move_for(&player, &LowestCardBot) {
+                        return;
+                    }
+                }
+                TimeoutPolicy::Forfeit => {
+                    clocks.forfeited_player = Some(player);
+                    clocks.running_players.clear();
+                    self.finish();
+                }
+            }
+        }
+    }
+
+    /// Milliseconds until the next player runs out of time, `None` when no clock is running.
+    pub fn next_clock_timeout(&self, clock: &impl Clock) -> Option<u64> {
+        self.clocks.as_ref()?.next_timeout(clock.now())
+    }
+
+    // players are not timed while getting ready for the next round
+    fn players_on_clock(&self) -> Vec<String> {
+        match &self.state {
+            CardExchange(step) => self
+                .players
+                .iter()
+                .filter(|&player| !self.has_committed_exchange(step, player))
+                .cloned()
+                .collect(),
+            RoundInProgress(step) => vec![step.state.current_player.clone()],
+            RoundFinished(_) => Vec::new(),
+        }
+    }
+
+    /// Plays the moves of all bot seats until one of the active players has to move.
+    pub fn play_bots(&mut self, bot: &impl Bot) {
+        while !self.is_finished && self.play_bot_move(bot) {}
+    }
+
+    fn play_bot_move(&mut self, bot: &impl Bot) -> bool {
+        match self.get_bot_to_move() {
+            Some(player) => self.play_move_for(&player, bot),
+            None => false,
+        }
+    }
+
+    fn play_move_for(&mut self, player: &str, bot: &impl Bot) -> bool {
+        let player = player.to_string();
+        let view = self.view_for(&player).unwrap();
+
+        match &self.state {
+            CardExchange(step) => {
+                // a selection which is only waiting for confirmation is kept
+                if !step.state.cards_to_exchange.contains_key(&player) {
+                    let payload = CardExchangePayload {
+                        cards_to_exchange: bot.choose_exchange_cards(&view),
+                    };
+                    if self.exchange_cards(&player, &payload).is_err() {
+                        return false;
+                    }
+                }
+                if self.settings.confirm_card_exchange
+                    && self.confirm_exchange_cards(&player).is_err()
+                {
+                    return false;
+                }
+            }
+            RoundInProgress(_) => {
+                // the first legal card is played instead of an illegal choice of the bot
+                let Some(card) = bot
+                    .choose_card(&view)
+                    .filter(|card| view.legal_cards.contains(card))
+                    .or(view.legal_cards.first().copied())
+                else {
+                    return false;
+                };
+                if self
+                    .place_card(&player, &PlaceCardPayload { card })
+                    .is_err()
+                {
+                    return false;
+                }
+            }
+            RoundFinished(_) => {
+                let payload = ClaimReadinessPayload { ready: true };
+                if self.claim_readiness(&player, &payload).is_err() {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    pub fn exchange_cards(
+        &mut self,
+        player: &str,
+        payload: &CardExchangePayload,
+    ) -> GameResult<()> {
+        let CardExchange(step) = &mut self.state else {
+            Err(GameError("Cards are not being exchanged".to_string()))?
+        };
+        step.handle_payload(payload, player)?;
+
+        self.switch_state();
+        Ok(())
+    }
+
+    pub fn withdraw_exchange_cards(&mut self, player: &str) -> GameResult<()> {
+        let CardExchange(step) = &mut self.state else {
+            Err(GameError("Cards are not being exchanged".to_string()))?
+        };
+        step.withdraw_exchange_cards(player)
+    }
+
+    pub fn confirm_exchange_cards(&mut self, player: &str) -> GameResult<()> {
+        let CardExchange(step) = &mut self.state else {
+            Err(GameError("Cards are not being exchanged".to_string()))?
+        };
+        step.confirm_exchange_cards(player)?;
+
+        self.switch_state();
+        self.record_move(player);
+        Ok(())
+    }
+
+    pub fn place_card(&mut self, player: &str, payload: &PlaceCardPayload) -> GameResult<()> {
+        let RoundInProgress(step) = &mut self.state else {
+            Err(GameError("Round is not in progress".to_string()))?
+        };
+        step.handle_payload(payload, player)?;
+        self.take_back_request = None;
+
+        self.observers
+            .notify(|observer| observer.on_card_played(player, payload.card));
+        let trick = step
+            .history
+            .rounds
+            .last()
+            .and_then(|round| round.tricks.last());
+        if let Some(trick) = trick.filter(|trick| trick.plays.len() == step.players.len()) {
+            // house rules can pick a different winner than the trick itself
+            let winner = step.state.trick_winners.last().unwrap();
+            self.observers
+                .notify(|observer| observer.on_trick_won(winner, trick));
+        }
+
+        self.switch_state();
+        self.record_move(player);
+        Ok(())
+    }
+
+    pub fn claim_readiness(
+        &mut self,
+        player: &str,
+        payload: &ClaimReadinessPayload,
+    ) -> GameResult<()> {
+        let RoundFinished(step) = &mut self.state else {
+            Err(GameError("Round is not finished".to_string()))?
+        };
+        step.handle_payload(payload, player);
+
+        self.switch_state();
+        Ok(())
+    }
+
+    // moves the game to the next step once everyone is done with the current one
+    fn switch_state(&mut self) {
+        match &self.state {
+            CardExchange(step) => {
+                let confirmed =
+                    !self.settings.confirm_card_exchange || step.is_confirmed_by_everyone();
+                if step.should_switch() && confirmed {
+                    let step = step.clone().to_round_in_progress();
+                    let passes = &step.history.rounds.last().unwrap().passes;
+                    for from_player in &step.players {
+                        let to_player = &step.player_to_player_map[from_player];
+                        self.observers.notify(|observer| {
+                            observer.on_pass(from_player, to_player, &passes[from_player])
+                        });
+                    }
+                    let (player, card) = step.history.last_play().unwrap();
+                    self.observers
+                        .notify(|observer| observer.on_card_played(player, *card));
+                    let players = step.players.clone();
+                    self.state = RoundInProgress(step);
+                    // selections can be declared again until the exchange is made, so the move
+                    // counts once it is made, unless it counted on confirm
+                    if !self.settings.confirm_card_exchange {
+                        for player in &players {
+                            self.record_move(player);
+                        }
+                    }
+                }
+            }
+            RoundInProgress(step) => {
+                if step.should_switch() {
+                    let moon_shooter = step.state.moon_shooter().cloned();
+                    let step = step.clone().to_round_finished();
+                    if let Some(player) = moon_shooter {
+                        self.observers.notify(|observer| observer.on_moon(&player));
+                    }
+                    let round = step.history.rounds.len();
+                    self.observers
+                        .notify(|observer| observer.on_round_scored(round, &step.scores));
+                    self.state = RoundFinished(step);
+                    self.finish_if_ended();
+                }
+            }
+            RoundFinished(step) => {
+                if !step.should_switch() || self.is_finished {
+                    return;
+                }
+                // without thread-rng the caller has to deal the next round from its own seed
+                let Some(seed) = self.next_deal_seed() else {
+                    return;
+                };
+                let step = step.clone().to_card_exchange_from_seed(seed);
+                let round = step.history.rounds.len();
+                let hands = &step.history.rounds.last().unwrap().dealt_hands;
+                self.observers
+                    .notify(|observer| observer.on_deal(round, hands));
+                self.state = CardExchange(step);
+            }
+        }
+    }
+
+    fn next_deal_seed(&self) -> Option<u64> {
+        let seed = self.settings.deal_seeds.get(self.round()).copied();
+        #[cfg(feature = "thread-rng")]
+        let seed = seed.or_else(|| Some(get_random_seed()));
+        seed
+    }
+
+    // the selection can still be changed until it is confirmed, when confirmation is required
+    fn has_committed_exchange(&self, step: &GameStep<CardExchangeState>, player: &str) -> bool {
+        step.state.cards_to_exchange.contains_key(player)
+            && (!self.settings.confirm_card_exchange
+                || step.state.confirmed_players.contains(player))
+    }
+
+    fn get_bot_to_move(&self) -> Option<String> {
+        let mut left_players = self.left_players.iter();
+        match &self.state {
+            CardExchange(step) => {
+                left_players.find(|&player| !self.has_committed_exchange(step, player))
+            }
+            RoundInProgress(step) => {
+                left_players.find(|&player| player == &step.state.current_player)
+            }
+            RoundFinished(step) => {
+                left_players.find(|&player| !step.state.players_ready.contains_key(player))
+            }
+        }
+        .cloned()
+    }
+}
+
+pub(crate) fn validate_number_of_players(players: &[String]) -> GameResult<()> {
+    if !(3..=4).contains(&players.len()) {
+        Err(GameError("Invalid number of players".to_string()))?
+    }
+    Ok(())
 }
 
-#[derive(Clone, Debug, Serialize, TS)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TS)]
 pub struct GameSettings {
+    #[serde(rename = "endCondition")]
+    pub end_condition: EndCondition,
+    /// Whether players can ask to take back the last card they placed.
+    #[serde(rename = "takeBacks")]
+    pub take_backs: bool,
+    /// Whether a card which completed a trick can be taken back.
+    #[serde(rename = "takeBackCompletedTricks")]
+    pub take_back_completed_tricks: bool,
+    /// Games without time control are not timed at all.
+    #[serde(rename = "timeControl")]
+    pub time_control: Option<TimeControl>,
+    #[serde(rename = "timeoutPolicy")]
+    pub timeout_policy: TimeoutPolicy,
+    /// Whether the exchange waits for every player to confirm their selection,
+    /// otherwise it happens as soon as the last player declares cards.
+    #[serde(rename = "confirmCardExchange")]
+    pub confirm_card_exchange: bool,
+    /// Source of a house rules script, see the `rules` module.
+    #[serde(rename = "ruleScript")]
+    pub rule_script: Option<String>,
+    /// Whether players can ask for a hint for their current move.
+    pub hints: bool,
+    /// Seeds of the deals of consecutive rounds, further rounds are dealt at random.
+    /// Kept from players, as the seed gives away all hands.
+    #[serde(skip)]
+    #[ts(skip)]
+    pub deal_seeds: Vec<u64>,
+    /// Hands of the first round in seating order, e.g. to reproduce a bug. Kept from players
+    /// like the seeds.
+    #[serde(skip)]
+    #[ts(skip)]
+    pub deal: Option<Vec<Vec<Card>>>,
+}
+
+impl GameSettings {
+    /// Checks that the preset deal, if any, has a hand for every player and deals the whole
+    /// deck evenly, every card once.
+    pub fn validate_deal(&self, number_of_players: usize) -> GameResult<()> {
+        let Some(deal) = &self.deal else {
+            return Ok(());
+        };
+        if deal.len() != number_of_players {
+            Err(GameError(format!(
+                "Deal has {} hands for {} players",
+                deal.len(),
+                number_of_players
+            )))?
+        }
+
+        let full_deck = get_full_deck(number_of_players);
+        let mut dealt_cards = HashSet::new();
+        for card in deal.iter().flatten() {
+            if !full_deck.contains(card) {
+                Err(GameError(format!("Card {} is not in the deck", card)))?
+            }
+            if !dealt_cards.insert(*card) {
+                Err(GameError(format!("Card {} is dealt more than once", card)))?
+            }
+        }
+        if let Some(card) = full_deck.iter().find(|card| !dealt_cards.contains(card)) {
+            Err(GameError(format!("Card {} is not dealt", card)))?
+        }
+
+        let hand_size = full_deck.len() / number_of_players;
+        if deal.iter().any(|hand| hand.len() != hand_size) {
+            Err(GameError(format!(
+                "Every hand has to have {} cards",
+                hand_size
+            )))?
+        }
+        Ok(())
+    }
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            end_condition: EndCondition::MaxScore { max_score: 100 },
+            take_backs: true,
+            take_back_completed_tricks: false,
+            time_control: None,
+            timeout_policy: TimeoutPolicy::default(),
+            confirm_card_exchange: false,
+            rule_script: None,
+            hints: false,
+            deal_seeds: Vec::new(),
+            deal: None,
+        }
+    }
+}
+
+/// Checked after every round, the game is finished once it is met.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[serde(tag = "type")]
+pub enum EndCondition {
+    /// One of the players reached the score.
     #[serde(rename = "maxScore")]
-    pub max_score: usize,
+    MaxScore {
+        #[serde(rename = "maxScore")]
+        max_score: usize,
+    },
+    /// The given number of rounds was played.
+    #[serde(rename = "maxRounds")]
+    MaxRounds {
+        #[serde(rename = "maxRounds")]
+        max_rounds: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+pub struct TakeBackRequest {
+    pub player: String,
+    pub card: Card,
+    pub accepted: HashSet<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -44,8 +773,391 @@ pub enum GameState {
     RoundFinished(GameStep<RoundFinishedState>),
 }
 
-impl GameState {
-    fn get_initial_state(players: &[String]) -> GameState {
-        CardExchange(GameStep::initialize_from_players(players))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::SimpleBot;
+    use std::collections::HashMap;
+
+    fn get_players() -> Vec<String> {
+        vec!["1".to_string(), "2".to_string(), "3".to_string()]
+    }
+
+    fn get_game() -> Game {
+        Game::from_seed(&get_players(), GameSettings::default(), 7).unwrap()
+    }
+
+    fn get_deal() -> Vec<Vec<Card>> {
+        get_full_deck(3)
+            .chunks(17)
+            .map(|hand| hand.to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn preset_deal_is_dealt_in_seating_order() {
+        let deal = get_deal();
+        let settings = GameSettings {
+            deal: Some(deal.clone()),
+            ..GameSettings::default()
+        };
+        let game = Game::from_seed(&get_players(), settings, 7).unwrap();
+
+        let dealt_hands = &game.history().rounds[0].dealt_hands;
+        for (player, hand) in get_players().iter().zip(deal) {
+            assert_eq!(dealt_hands[player], hand.into_iter().collect());
+        }
+        assert_eq!(game.history().rounds[0].seed, None);
+    }
+
+    #[test]
+    fn invalid_preset_deals_are_rejected() {
+        let two_of_clubs: Card = "2C".parse().unwrap();
+        let mut duplicate = get_deal();
+        duplicate[0][0] = duplicate[1][0];
+        let mut missing = get_deal();
+        missing[2].pop();
+        let mut outside_deck = get_deal();
+        outside_deck[0][0] = two_of_clubs;
+        let mut uneven = get_deal();
+        let card = uneven[0].pop().unwrap();
+        uneven[1].push(card);
+
+        let cases = [
+            (get_deal()[..2].to_vec(), "Deal has 2 hands for 3 players"),
+            (duplicate, "Card HEART_6 is dealt more than once"),
+            (missing, "Card DIAMOND_14 is not dealt"),
+            (outside_deck, "Card CLUB_2 is not in the deck"),
+            (uneven, "Every hand has to have 17 cards"),
+        ];
+        for (deal, error) in cases {
+            let settings = GameSettings {
+                deal: Some(deal),
+                ..GameSettings::default()
+            };
+            assert_eq!(
+                Game::from_seed(&get_players(), settings, 7).err(),
+                Some(GameError(error.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn hints_are_counted_when_enabled() {
+        let mut game = get_game();
+        let error = game.hint("1", &SimpleBot).unwrap_err();
+        assert_eq!(error.0, "Hints are disabled in this game");
+
+        game.settings.hints = true;
+        let hint = game.hint("1", &SimpleBot).unwrap();
+        let payload = CardExchangePayload {
+            cards_to_exchange: hint.cards.iter().copied().collect(),
+        };
+        game.exchange_cards("1", &payload).unwrap();
+
+        assert!(game.hint("1", &SimpleBot).is_err());
+        assert_eq!(game.hints_used, HashMap::from([("1".to_string(), 1)]));
+        assert_eq!(game.view_for("2").unwrap().hints_used["1"], 1);
+    }
+
+    #[test]
+    fn bots_play_for_players_who_left() {
+        let mut game = get_game();
+        let cards_before = game.view_for("2").unwrap().your_cards;
+        game.replace_with_bot("2").unwrap();
+        game.replace_with_bot("3").unwrap();
+        game.play_bots(&SimpleBot);
+
+        assert_eq!(game.active_players(), vec!["1".to_string()]);
+        let CardExchange(step) = &game.state else {
+            panic!("Game should wait for player 1 to exchange cards")
+        };
+        assert_eq!(step.player_decks["2"], cards_before);
+        assert_eq!(step.state.cards_to_exchange.len(), 2);
+
+        // player 1 follows the same strategy, so the whole round is played
+        while !matches!(game.state, RoundFinished(_)) {
+            let view = game.view_for("1").unwrap();
+            match &mut game.state {
+                CardExchange(step) => {
+                    let payload = CardExchangePayload {
+                        cards_to_exchange: SimpleBot.choose_exchange_cards(&view),
+                    };
+                    step.handle_payload(&payload, "1").unwrap();
+                    game.state = RoundInProgress(step.clone().to_round_in_progress());
+                }
+                RoundInProgress(step) => {
+                    let card = SimpleBot.choose_card(&view).unwrap();
+                    step.handle_payload(&PlaceCardPayload { card }, "1")
+                        .unwrap();
+                    if step.should_switch() {
+                        game.state = RoundFinished(step.clone().to_round_finished());
+                    }
+                }
+                RoundFinished(_) => unreachable!(),
+            }
+            game.play_bots(&SimpleBot);
+        }
+
+        let RoundFinished(step) = &game.state else {
+            unreachable!()
+        };
+        assert_eq!(step.state.players_ready.len(), 2);
+        assert_eq!(game.history().rounds[0].tricks.len(), 17);
+    }
+
+    #[test]
+    fn cards_are_exchanged_after_everyone_confirmed() {
+        let settings = GameSettings {
+            confirm_card_exchange: true,
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&get_players(), settings, 7).unwrap();
+        game.replace_with_bot("3").unwrap();
+        game.play_bots(&SimpleBot);
+
+        for player in ["1", "2"] {
+            let view = game.view_for(player).unwrap();
+            let payload = CardExchangePayload {
+                cards_to_exchange: SimpleBot.choose_exchange_cards(&view),
+            };
+            game.exchange_cards(player, &payload).unwrap();
+        }
+        assert!(matches!(game.state, CardExchange(_)));
+
+        game.confirm_exchange_cards("1").unwrap();
+        game.withdraw_exchange_cards("2").unwrap();
+        assert!(matches!(game.state, CardExchange(_)));
+
+        game.play_move_for("2", &SimpleBot);
+        assert!(matches!(game.state, RoundInProgress(_)));
+    }
+
+    #[test]
+    fn declaring_exchange_again_does_not_add_time() {
+        let time_control = TimeControl::Increment {
+            base_millis: 10_000,
+            increment_millis: 2_000,
+        };
+        let settings = GameSettings {
+            time_control: Some(time_control),
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&get_players(), settings, 7).unwrap();
+        game.replace_with_bot("3").unwrap();
+        game.play_bots(&SimpleBot);
+
+        let view = game.view_for("1").unwrap();
+        let payload = CardExchangePayload {
+            cards_to_exchange: SimpleBot.choose_exchange_cards(&view),
+        };
+        for _ in 0..3 {
+            game.exchange_cards("1", &payload).unwrap();
+            game.withdraw_exchange_cards("1").unwrap();
+        }
+        game.exchange_cards("1", &payload).unwrap();
+        assert_eq!(game.clocks.as_ref().unwrap().remaining_millis["1"], 10_000);
+
+        game.play_move_for("2", &SimpleBot);
+        assert!(matches!(game.state, RoundInProgress(_)));
+        let clocks = game.clocks.as_ref().unwrap();
+        assert!(clocks
+            .remaining_millis
+            .values()
+            .all(|&remaining| remaining == 12_000));
+    }
+
+    #[test]
+    fn player_cannot_leave_twice() {
+        let mut game = get_game();
+        game.replace_with_bot("2").unwrap();
+
+        assert_eq!(
+            game.replace_with_bot("2"),
+            Err(GameError(
+                "Player 2 does not participate in the game".to_string()
+            ))
+        );
+        assert!(game.vote_to_end("2").is_err());
+        assert_eq!(game.left_players, vec!["2".to_string()]);
+    }
+
+    #[test]
+    fn game_ends_when_all_active_players_vote() {
+        let mut game = get_game();
+        game.vote_to_end("1").unwrap();
+        game.vote_to_end("2").unwrap();
+        assert!(!game.is_finished);
+
+        game.replace_with_bot("3").unwrap();
+        assert!(game.is_finished);
+    }
+
+    fn get_bot_game(end_condition: EndCondition) -> Game {
+        let settings = GameSettings {
+            end_condition,
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&get_players(), settings, 7).unwrap();
+        for player in get_players() {
+            game.replace_with_bot(&player).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn game_ends_after_max_rounds() {
+        let mut game = get_bot_game(EndCondition::MaxRounds { max_rounds: 2 });
+        assert_eq!((game.round(), game.dealer()), (1, "1"));
+
+        game.play_bots(&SimpleBot);
+
+        assert!(game.is_finished);
+        assert!(matches!(game.state, RoundFinished(_)));
+        assert_eq!((game.round(), game.dealer()), (2, "2"));
+    }
+
+    #[test]
+    fn game_ends_when_player_reaches_max_score() {
+        let mut game = get_bot_game(EndCondition::MaxScore { max_score: 1 });
+
+        game.play_bots(&SimpleBot);
+
+        assert!(game.is_finished);
+        assert_eq!(game.round(), 1);
+    }
+
+    fn place_card(game: &mut Game) -> String {
+        let RoundInProgress(step) = &mut game.state else {
+            panic!("Round should be in progress")
+        };
+        let player = step.state.current_player.clone();
+        let card = step.legal_cards(&player)[0];
+        step.handle_payload(&PlaceCardPayload { card }, &player)
+            .unwrap();
+        player
+    }
+
+    fn get_game_in_progress() -> Game {
+        let mut game = get_game();
+        let CardExchange(step) = &mut game.state else {
+            unreachable!()
+        };
+        step.state.cards_to_exchange = HashMap::from_iter(
+            get_players()
+                .into_iter()
+                .map(|player| (player, HashSet::new())),
+        );
+        game.state = RoundInProgress(step.clone().to_round_in_progress());
+        game
+    }
+
+    #[test]
+    fn take_back_after_everyone_accepted() {
+        let mut game = get_game_in_progress();
+        let player = place_card(&mut game);
+        let others: Vec<String> = get_players().into_iter().filter(|p| p != &player).collect();
+
+        assert!(game.request_take_back(&others[0]).is_err());
+        game.request_take_back(&player).unwrap();
+        assert_eq!(
+            game.request_take_back(&player),
+            Err(GameError("Take back was already requested".to_string()))
+        );
+
+        game.answer_take_back(&others[0], true).unwrap();
+        assert!(game.take_back_request.is_some());
+        game.answer_take_back(&others[1], true).unwrap();
+        assert!(game.take_back_request.is_none());
+
+        let RoundInProgress(step) = &game.state else {
+            unreachable!()
+        };
+        assert_eq!(step.state.current_player, player);
+        assert_eq!(step.state.cards_on_table.len(), 1);
+    }
+
+    #[test]
+    fn take_back_rejected() {
+        let mut game = get_game_in_progress();
+        let player = place_card(&mut game);
+        let other = get_players().into_iter().find(|p| p != &player).unwrap();
+
+        game.request_take_back(&player).unwrap();
+        game.answer_take_back(&other, false).unwrap();
+
+        assert!(game.take_back_request.is_none());
+        let RoundInProgress(step) = &game.state else {
+            unreachable!()
+        };
+        assert_eq!(step.state.cards_on_table.len(), 2);
+    }
+
+    #[test]
+    fn take_back_returns_cards_bots_placed_after_it() {
+        let time_control = TimeControl::Increment {
+            base_millis: 10_000,
+            increment_millis: 2_000,
+        };
+        let mut game = get_game_in_progress();
+        game.settings.time_control = Some(time_control);
+        game.clocks = Some(PlayerClocks::new(&get_players(), time_control));
+        let RoundInProgress(step) = &game.state else {
+            unreachable!()
+        };
+        let player = step.state.current_player.clone();
+        let card = step.legal_cards(&player)[0];
+        let decks = step.player_decks.clone();
+        for other in get_players().iter().filter(|&p| p != &player) {
+            game.replace_with_bot(other).unwrap();
+        }
+
+        game.place_card(&player, &PlaceCardPayload { card })
+            .unwrap();
+        game.play_bots(&SimpleBot);
+        let RoundInProgress(step) = &game.state else {
+            unreachable!()
+        };
+        assert_eq!(step.history.rounds[0].tricks.len(), 2);
+        assert_eq!(
+            game.request_take_back(&player),
+            Err(GameError(
+                "Cannot take back a card from a completed trick".to_string()
+            ))
+        );
+
+        game.settings.take_back_completed_tricks = true;
+        game.request_take_back(&player).unwrap();
+
+        assert!(game.take_back_request.is_none());
+        let RoundInProgress(step) = &game.state else {
+            unreachable!()
+        };
+        assert_eq!(step.state.current_player, player);
+        assert_eq!(step.state.cards_on_table.len(), 1);
+        assert_eq!(step.player_decks, decks);
+        assert!(step.scores.values().all(|&score| score == 0));
+        let clocks = game.clocks.as_ref().unwrap();
+        assert!(clocks
+            .remaining_millis
+            .values()
+            .all(|&remaining| remaining == 10_000));
+    }
+
+    #[test]
+    fn take_back_is_immediate_when_only_bots_are_left() {
+        let mut game = get_game_in_progress();
+        let player = place_card(&mut game);
+        for other in get_players().iter().filter(|&p| p != &player) {
+            game.replace_with_bot(other).unwrap();
+        }
+
+        game.request_take_back(&player).unwrap();
+
+        assert!(game.take_back_request.is_none());
+        let RoundInProgress(step) = &game.state else {
+            unreachable!()
+        };
+        assert_eq!(step.state.current_player, player);
     }
 }
@@ -2,7 +2,8 @@ use crate::card::Card;
 use crate::card::CardSuit::{Club, Diamond, Heart, Spade};
 use crate::CardSuit;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use std::collections::{HashMap, HashSet};
 
 pub fn pick_player_with_starting_card(
@@ -34,22 +35,39 @@ pub fn get_player_to_player_map(players: &[String]) -> HashMap<String, String> {
     )
 }
 
+#[cfg(feature = "thread-rng")]
+pub fn get_random_seed() -> u64 {
+    use rand::Rng;
+    rand::thread_rng().gen()
+}
+
+#[cfg(feature = "thread-rng")]
 pub fn get_starting_player_decks(players: &[String]) -> HashMap<String, HashSet<Card>> {
-    let mut player_decks = HashMap::new();
-    let mut all_cards = HashSet::new();
+    get_seeded_player_decks(players, get_random_seed())
+}
+
+pub fn get_full_deck(number_of_players: usize) -> Vec<Card> {
+    let mut all_cards = Vec::new();
 
     for card_suit in [Spade, Heart, Club, Diamond] {
         for value in 2..=14 {
-            all_cards.insert(Card::new(card_suit, value).unwrap());
+            all_cards.push(Card::new(card_suit, value).unwrap());
         }
     }
 
-    if players.len() == 3 {
-        all_cards.remove(&Card::new(Club, 2).unwrap());
+    if number_of_players == 3 {
+        all_cards.retain(|&card| card != Card::new(Club, 2).unwrap());
     }
-    let mut all_cards = Vec::from_iter(all_cards.iter().cloned());
 
-    let mut rng = thread_rng();
+    all_cards
+}
+
+// ChaCha8Rng is used directly, so the same seed always produces the same deal
+pub fn get_seeded_player_decks(players: &[String], seed: u64) -> HashMap<String, HashSet<Card>> {
+    let mut player_decks = HashMap::new();
+    let mut all_cards = get_full_deck(players.len());
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
     all_cards.shuffle(&mut rng);
 
     for (i, &card) in all_cards.iter().enumerate() {
@@ -186,6 +204,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "thread-rng")]
     fn get_starting_player_decks_for_3_players() {
         let players = vec!["1".to_string(), "2".to_string(), "3".to_string()];
 
@@ -197,6 +216,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "thread-rng")]
     fn get_starting_player_decks_for_4_players() {
         let players = vec![
             "1".to_string(),
@@ -212,4 +232,18 @@ mod tests {
         assert_eq!(player_decks["1"].len(), 13);
         assert_eq!(player_decks["1"].len(), 13);
     }
+
+    #[test]
+    fn get_seeded_player_decks_is_deterministic() {
+        let players = vec!["1".to_string(), "2".to_string(), "3".to_string()];
+
+        assert_eq!(
+            get_seeded_player_decks(&players, 42),
+            get_seeded_player_decks(&players, 42)
+        );
+        assert_ne!(
+            get_seeded_player_decks(&players, 42),
+            get_seeded_player_decks(&players, 43)
+        );
+    }
 }
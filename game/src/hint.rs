@@ -0,0 +1,264 @@
+use crate::bot::Bot;
+use crate::card::{Card, CardSuit};
+use crate::view::{PlayerView, StateView};
+use serde::Serialize;
+use std::collections::HashSet;
+use ts_rs::TS;
+
+/// Suggested move for the current decision of a player, based only on their view.
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+pub struct Hint {
+    /// Three cards to pass or a single card to place.
+    pub cards: Vec<Card>,
+    pub reason: HintReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+pub enum HintReason {
+    /// Passing the last cards of a suit, so the player can discard when it is led.
+    #[serde(rename = "voidSuit")]
+    VoidSuit,
+    #[serde(rename = "passHighCards")]
+    PassHighCards,
+    /// Getting rid of a spade worth points, passed or discarded.
+    #[serde(rename = "dumpHighSpade")]
+    DumpHighSpade,
+    #[serde(rename = "dumpPoints")]
+    DumpPoints,
+    #[serde(rename = "dumpHighCard")]
+    DumpHighCard,
+    /// Playing under the card which takes the trick.
+    #[serde(rename = "duckTrick")]
+    DuckTrick,
+    /// The trick is taken anyway when playing last, so the highest card goes.
+    #[serde(rename = "takeTrickLast")]
+    TakeTrickLast,
+    #[serde(rename = "leadLow")]
+    LeadLow,
+    #[serde(rename = "playLow")]
+    PlayLow,
+}
+
+impl Hint {
+    /// `None` when the player has no decision to make.
+    pub fn for_view(view: &PlayerView, bot: &impl Bot) -> Option<Hint> {
+        match &view.state {
+            StateView::CardExchange(state) if state.your_exchange_cards.is_empty() => {
+                let cards = bot.choose_exchange_cards(view);
+                let reason = get_exchange_reason(&view.your_cards, &cards);
+                Some(Hint {
+                    cards: get_sorted(cards),
+                    reason,
+                })
+            }
+            StateView::RoundInProgress(state) => {
+                let legal_cards = &view.legal_cards;
+                let card = bot
+                    .choose_card(view)
+                    .filter(|card| legal_cards.contains(card))?;
+                let reason = match state.table_suit {
+                    None => HintReason::LeadLow,
+                    Some(table_suit) if card.suit != table_suit => get_discard_reason(card),
+                    Some(table_suit) => {
+                        let winning_value = state
+                            .cards_on_table
+                            .values()
+                            .filter(|card| card.suit == table_suit)
+                            .map(|card| card.value)
+                            .max()
+                            .unwrap_or(0);
+                        let is_last = state.cards_on_table.len() + 1 == view.players.len();
+                        match (card.value < winning_value, is_last) {
+                            (true, _) => HintReason::DuckTrick,
+                            (false, true) => HintReason::TakeTrickLast,
+                            (false, false) => HintReason::PlayLow,
+                        }
+                    }
+                };
+                Some(Hint {
+                    cards: vec![card],
+                    reason,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn get_exchange_reason(hand: &HashSet<Card>, cards: &HashSet<Card>) -> HintReason {
+    let voids_suit = cards.iter().any(|passed| {
+        hand.iter()
+            .filter(|card| card.suit == passed.suit)
+            .all(|card| cards.contains(card))
+    });
+    if voids_suit {
+        return HintReason::VoidSuit;
+    }
+    match cards.iter().any(is_high_spade) {
+        true => HintReason::DumpHighSpade,
+        false => HintReason::PassHighCards,
+    }
+}
+
+fn get_discard_reason(card: Card) -> HintReason {
+    if is_high_spade(&card) {
+        return HintReason::DumpHighSpade;
+    }
+    match card.score {
+        0 => HintReason::DumpHighCard,
+        _ => HintReason::DumpPoints,
+    }
+}
+
+fn is_high_spade(card: &Card) -> bool {
+    card.suit == CardSuit::Spade && card.score > 0
+}
+
+fn get_sorted(cards: HashSet<Card>) -> Vec<Card> {
+    let mut cards: Vec<Card> = cards.into_iter().collect();
+    cards.sort_by_key(|card| (card.suit, card.value));
+    cards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::SimpleBot;
+    use crate::game::GameSettings;
+    use crate::step::round_in_progress::get_legal_cards_of_hand;
+    use crate::view::{CardExchangeView, RoundInProgressView};
+    use std::collections::HashMap;
+
+    fn card(notation: &str) -> Card {
+        notation.parse().unwrap()
+    }
+
+    fn get_view(hand: &[&str], state: StateView) -> PlayerView {
+        let your_cards: HashSet<Card> = hand.iter().map(|notation| card(notation)).collect();
+        let legal_cards = match &state {
+            StateView::RoundInProgress(state) => {
+                get_legal_cards_of_hand(&your_cards, state.table_suit)
+            }
+            _ => Vec::new(),
+        };
+        PlayerView {
+            player: "1".to_string(),
+            settings: GameSettings::default(),
+            players: vec!["1".to_string(), "2".to_string(), "3".to_string()],
+            round: 1,
+            dealer: "1".to_string(),
+            scores: HashMap::new(),
+            is_finished: false,
+            player_decks: HashMap::new(),
+            your_cards,
+            left_players: Vec::new(),
+            end_votes: HashSet::new(),
+            take_back_request: None,
+            clocks: None,
+            hints_used: HashMap::new(),
+            state,
+            legal_cards,
+        }
+    }
+
+    fn get_exchange_view(hand: &[&str]) -> PlayerView {
+        get_view(
+            hand,
+            StateView::CardExchange(CardExchangeView {
+                player_exchange_cards: HashMap::new(),
+                your_exchange_cards: HashSet::new(),
+                confirmed_players: HashSet::new(),
+            }),
+        )
+    }
+
+    fn get_round_view(hand: &[&str], cards_on_table: &[(&str, &str)]) -> PlayerView {
+        let cards_on_table: HashMap<String, Card> = cards_on_table
+            .iter()
+            .map(|(player, notation)| (player.to_string(), card(notation)))
+            .collect();
+        get_view(
+            hand,
+            StateView::RoundInProgress(RoundInProgressView {
+                current_player: "1".to_string(),
+                table_suit: cards_on_table.values().next().map(|card| card.suit),
+                cards_on_table,
+            }),
+        )
+    }
+
+    fn get_hint(view: &PlayerView) -> (Vec<Card>, HintReason) {
+        let hint = Hint::for_view(view, &SimpleBot).unwrap();
+        (hint.cards, hint.reason)
+    }
+
+    #[test]
+    fn exchange_hint_names_the_reason() {
+        let view = get_exchange_view(&["AH", "KH", "QH", "2H", "3C", "4D"]);
+        assert_eq!(
+            get_hint(&view),
+            (
+                vec![card("QH"), card("KH"), card("AH")],
+                HintReason::PassHighCards
+            )
+        );
+
+        let view = get_exchange_view(&["QS", "AH", "KH", "2H", "3C", "4D"]);
+        assert_eq!(get_hint(&view).1, HintReason::VoidSuit);
+
+        let view = get_exchange_view(&["QS", "2S", "AH", "KH", "2H", "3C", "4D"]);
+        assert_eq!(get_hint(&view).1, HintReason::DumpHighSpade);
+    }
+
+    #[test]
+    fn card_hint_names_the_reason() {
+        let cases = [
+            (
+                get_round_view(&["3C", "QS"], &[]),
+                "3C",
+                HintReason::LeadLow,
+            ),
+            (
+                get_round_view(&["QS", "2H"], &[("2", "5C")]),
+                "QS",
+                HintReason::DumpHighSpade,
+            ),
+            (
+                get_round_view(&["2H", "4D"], &[("2", "5C")]),
+                "2H",
+                HintReason::DumpPoints,
+            ),
+            (
+                get_round_view(&["4C", "9C"], &[("2", "8C")]),
+                "4C",
+                HintReason::DuckTrick,
+            ),
+            (
+                get_round_view(&["9C", "KC"], &[("2", "8C"), ("3", "2C")]),
+                "KC",
+                HintReason::TakeTrickLast,
+            ),
+            (
+                get_round_view(&["9C", "KC"], &[("2", "8C")]),
+                "9C",
+                HintReason::PlayLow,
+            ),
+        ];
+
+        for (view, expected_card, reason) in cases {
+            assert_eq!(get_hint(&view), (vec![card(expected_card)], reason));
+        }
+    }
+
+    #[test]
+    fn no_hint_when_there_is_no_move_to_make() {
+        let mut view = get_round_view(&["3C"], &[]);
+        let StateView::RoundInProgress(state) = &mut view.state else {
+            unreachable!()
+        };
+        state.current_player = "2".to_string();
+        view.legal_cards.clear();
+
+        assert_eq!(Hint::for_view(&view, &SimpleBot), None);
+    }
+}
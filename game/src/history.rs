@@ -0,0 +1,700 @@
+//! Hand history of a game and its portable text format.
+//!
+//! The format is line based, loosely modelled after PBN. Every non-empty line that does not
+//! start with `%` is a tag of the form `[Name "value" "value" ...]`. Values are double quoted,
+//! `\"` and `\\` are the only escapes. Cards use the short notation (`QS`, `10H`, `2C`).
+//!
+//! ```text
+//! % black widow hand history
+//! [Players "alice" "bob" "carol"]
+//! [InitialScore "alice" "12"]
+//! [Round "1"]
+//! [Seed "8731"]
+//! [Deal "alice" "4S QS 3C ..."]
+//! [Pass "alice" "QS AS KS"]
+//! [Trick "bob 3C" "carol 9C" "alice KC"]
+//! [Score "alice" "25"]
+//! ```
+//!
+//! - `Players` comes first and lists players in seating order, cards are passed to the next one.
+//! - `InitialScore` is optional, it holds scores from before the first recorded round,
+//!   which is the case for a single round exported from a longer game.
+//! - `Round` starts a new round, the rest of the tags belong to the latest round.
+//! - `Seed` is optional, when present the deal has to match the one produced by the seed.
+//! - `Deal` and `Pass` hold the cards dealt to and passed by each player.
+//! - `Trick` holds the plays of a single trick in order, `player card` each.
+//!   The first trick starts with the automatic play of the starting card.
+//! - `Score` holds the total score of a player after the round, every player has one.
+
+use crate::card::{Card, Hand};
+use crate::error::{GameError, GameResult};
+use crate::game::validate_number_of_players;
+use crate::helper::{get_full_deck, get_player_to_player_map, get_seeded_player_decks};
+use crate::payload::{CardExchangePayload, PlaceCardPayload};
+use crate::step::card_exchange::CardExchangeState;
+use crate::step::round_finished::RoundFinishedState;
+use crate::step::round_in_progress::RoundInProgressState;
+use crate::step::GameStep;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GameHistory {
+    pub players: Vec<String>,
+    pub initial_scores: HashMap<String, usize>,
+    pub rounds: Vec<RoundHistory>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RoundHistory {
+    pub seed: Option<u64>,
+    pub dealt_hands: HashMap<String, HashSet<Card>>,
+    pub passes: HashMap<String, HashSet<Card>>,
+    pub tricks: Vec<Trick>,
+    pub scores: HashMap<String, usize>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Trick {
+    pub plays: Vec<(String, Card)>,
+}
+
+impl Trick {
+    pub fn winner(&self) -> Option<&str> {
+        let (_, leading_card) = self.plays.first()?;
+        self.plays
+            .iter()
+            .filter(|(_, card)| card.suit == leading_card.suit)
+            .max_by_key(|(_, card)| card.value)
+            .map(|(player, _)| player.as_str())
+    }
+}
+
+impl GameHistory {
+    pub fn new(players: &[String]) -> GameHistory {
+        GameHistory {
+            players: players.to_vec(),
+            initial_scores: HashMap::new(),
+            rounds: Vec::new(),
+        }
+    }
+
+    pub(crate) fn start_round(
+        &mut self,
+        seed: Option<u64>,
+        hands: &HashMap<String, HashSet<Card>>,
+    ) {
+        self.rounds.push(RoundHistory {
+            seed,
+            dealt_hands: hands.clone(),
+            ..RoundHistory::default()
+        });
+    }
+
+    pub(crate) fn current_round_mut(&mut self) -> &mut RoundHistory {
+        if self.rounds.is_empty() {
+            self.rounds.push(RoundHistory::default());
+        }
+        self.rounds.last_mut().unwrap()
+    }
+
+    pub(crate) fn record_pass(&mut self, player: &str, cards: &HashSet<Card>) {
+        self.current_round_mut()
+            .passes
+            .insert(player.to_string(), cards.clone());
+    }
+
+    pub(crate) fn record_play(&mut self, player: &str, card: Card, number_of_players: usize) {
+        let tricks = &mut self.current_round_mut().tricks;
+        match tricks.last_mut() {
+            Some(trick) if trick.plays.len() < number_of_players => {
+                trick.plays.push((player.to_string(), card))
+            }
+            _ => tricks.push(Trick {
+                plays: vec![(player.to_string(), card)],
+            }),
+        }
+    }
+
+    pub(crate) fn remove_pass(&mut self, player: &str) {
+        self.current_round_mut().passes.remove(player);
+    }
+
+    pub(crate) fn last_play(&self) -> Option<&(String, Card)> {
+        self.rounds.last()?.tricks.last()?.plays.last()
+    }
+
+    pub(crate) fn remove_last_play(&mut self) -> Option<(String, Card)> {
+        let tricks = &mut self.rounds.last_mut()?.tricks;
+        let play = tricks.last_mut()?.plays.pop();
+        if tricks.last().is_some_and(|trick| trick.plays.is_empty()) {
+            tricks.pop();
+        }
+        play
+    }
+
+    pub(crate) fn record_scores(&mut self, scores: &HashMap<String, usize>) {
+        self.current_round_mut().scores = scores.clone();
+    }
+
+    pub fn scores_before_round(&self, index: usize) -> HashMap<String, usize> {
+        match index {
+            0 => self.initial_scores.clone(),
+            _ => self.rounds[index - 1].scores.clone(),
+        }
+    }
+
+    /// History holding only the round with the given index, e.g. for sharing a single hand.
+    pub fn round(&self, index: usize) -> Option<GameHistory> {
+        let round = self.rounds.get(index)?;
+        Some(GameHistory {
+            players: self.players.clone(),
+            initial_scores: self.scores_before_round(index),
+            rounds: vec![round.clone()],
+        })
+    }
+
+    /// Plays the whole history through the game rules and checks that every move is legal
+    /// and that the recorded scores are the ones the rules produce.
+    pub fn replay(&self) -> GameResult<()> {
+        validate_number_of_players(&self.players)?;
+        for (index, round) in self.rounds.iter().enumerate() {
+            self.replay_round(index, round)
+                .map_err(|error| GameError(format!("Round {}: {}", index + 1, error.0)))?;
+        }
+
+        Ok(())
+    }
+
+    fn replay_round(&self, index: usize, round: &RoundHistory) -> GameResult<()> {
+        let finished_step = self.replay_round_plays(index, round)?;
+        for player in &self.players {
+            let expected_score = finished_step.scores.get(player).copied().unwrap_or(0);
+            let recorded_score = *round
+                .scores
+                .get(player)
+                .ok_or(GameError(format!("Missing score of player {}", player)))?;
+            if expected_score != recorded_score {
+                Err(GameError(format!(
+                    "Recorded score {} of player {} does not match expected score {}",
+                    recorded_score, player, expected_score
+                )))?
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Plays the whole round without checking the recorded scores.
+    pub(crate) fn replay_round_plays(
+        &self,
+        index: usize,
+        round: &RoundHistory,
+    ) -> GameResult<GameStep<RoundFinishedState>> {
+        let mut step = self.replay_round_start(index, round)?;
+        for (number, trick) in round.tricks.iter().enumerate() {
+            if trick.plays.len() != self.players.len() {
+                Err(GameError(format!(
+                    "Trick {} has to hold a card of every player",
+                    number + 1
+                )))?
+            }
+
+            // the starting card is placed when the round starts
+            for (player, card) in trick.plays.iter().skip(if number == 0 { 1 } else { 0 }) {
+                step.handle_payload(&PlaceCardPayload { card: *card }, player)?;
+            }
+            let played_trick = step
+                .history
+                .rounds
+                .last()
+                .and_then(|played_round| played_round.tricks.get(number));
+            if played_trick != Some(trick) {
+                Err(GameError(format!(
+                    "Trick {} does not match the trick played",
+                    number + 1
+                )))?
+            }
+        }
+
+        if !step.should_switch() {
+            Err(GameError(
+                "Round ended before all cards were played".to_string(),
+            ))?
+        }
+
+        Ok(step.to_round_finished())
+    }
+
+    /// Checks the deal and passes of the round and plays its starting card.
+    pub(crate) fn replay_round_start(
+        &self,
+        index: usize,
+        round: &RoundHistory,
+    ) -> GameResult<GameStep<RoundInProgressState>> {
+        let dealt_cards: Vec<&Card> = round.dealt_hands.values().flatten().collect();
+        let full_deck = get_full_deck(self.players.len());
+        if round.dealt_hands.len() != self.players.len()
+            || dealt_cards.len() != full_deck.len()
+            || !full_deck.iter().all(|card| dealt_cards.contains(&card))
+        {
+            Err(GameError(
+                "Dealt hands have to contain the whole deck".to_string(),
+            ))?
+        }
+
+        if let Some(seed) = round.seed {
+            if get_seeded_player_decks(&self.players, seed) != round.dealt_hands {
+                Err(GameError(format!("Dealt hands do not match seed {}", seed)))?
+            }
+        }
+
+        let mut exchange_step = GameStep {
+            players: self.players.clone(),
+            player_to_player_map: get_player_to_player_map(&self.players),
+            scores: self.scores_before_round(index),
+            player_decks: round.dealt_hands.clone(),
+            history: GameHistory::new(&self.players),
+            house_rules: None,
+            state: CardExchangeState::new(),
+        };
+
+        for player in &self.players {
+            let cards = round
+                .passes
+                .get(player)
+                .ok_or(GameError(format!("Missing pass of player {}", player)))?;
+            exchange_step.handle_payload(
+                &CardExchangePayload {
+                    cards_to_exchange: cards.clone(),
+                },
+                player,
+            )?;
+        }
+
+        let step = exchange_step.to_round_in_progress();
+        let first_play = round.tricks.first().and_then(|trick| trick.plays.first());
+        let (starting_player, starting_card) = step.state.cards_on_table.iter().next().unwrap();
+        if first_play != Some(&(starting_player.clone(), *starting_card)) {
+            Err(GameError(format!(
+                "First trick has to start with {} played by {}",
+                starting_card, starting_player
+            )))?
+        }
+
+        Ok(step)
+    }
+}
+
+impl Display for GameHistory {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(f, "% black widow hand history")?;
+        writeln!(f, "{}", tag("Players", self.players.iter()))?;
+        for player in &self.players {
+            if let Some(score) = self.initial_scores.get(player).filter(|&&score| score > 0) {
+                writeln!(f, "{}", tag("InitialScore", [player, &score.to_string()]))?;
+            }
+        }
+
+        for (index, round) in self.rounds.iter().enumerate() {
+            writeln!(f)?;
+            writeln!(f, "{}", tag("Round", [(index + 1).to_string()]))?;
+            if let Some(seed) = round.seed {
+                writeln!(f, "{}", tag("Seed", [seed.to_string()]))?;
+            }
+            for player in &self.players {
+                if let Some(cards) = round.dealt_hands.get(player) {
+                    writeln!(
+                        f,
+                        "{}",
+                        tag("Deal", [player, &Hand::new(cards).to_string()])
+                    )?;
+                }
+            }
+            for player in &self.players {
+                if let Some(cards) = round.passes.get(player) {
+                    writeln!(
+                        f,
+                        "{}",
+                        tag("Pass", [player, &Hand::new(cards).to_string()])
+                    )?;
+                }
+            }
+            for trick in &round.tricks {
+                let plays = trick.plays.iter().map(|(player, card)| {
+                    format!(
+                        "{} {}",
+                        player,
+                        card.to_notation(crate::CardNotation::Short)
+                    )
+                });
+                writeln!(f, "{}", tag("Trick", plays))?;
+            }
+            for player in &self.players {
+                if let Some(score) = round.scores.get(player) {
+                    writeln!(f, "{}", tag("Score", [player, &score.to_string()]))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for GameHistory {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> GameResult<GameHistory> {
+        let mut history: Option<GameHistory> = None;
+
+        for (line_number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('%') {
+                continue;
+            }
+
+            let (name, values) = parse_tag(line)
+                .map_err(|error| GameError(format!("Line {}: {}", line_number + 1, error)))?;
+
+            match (name.as_str(), history.as_mut()) {
+                ("Players", None) => {
+                    validate_number_of_players(&values).map_err(|error| {
+                        GameError(format!("Line {}: {}", line_number + 1, error.0))
+                    })?;
+                    if let Some((_, player)) = values
+                        .iter()
+                        .enumerate()
+                        .find(|(index, player)| values[..*index].contains(player))
+                    {
+                        Err(GameError(format!(
+                            "Line {}: Duplicate player {}",
+                            line_number + 1,
+                            player
+                        )))?
+                    }
+                    history = Some(GameHistory::new(&values))
+                }
+                ("Players", Some(_)) => Err(GameError("Duplicate Players tag".to_string()))?,
+                (_, None) => Err(GameError("Players tag has to come first".to_string()))?,
+                (_, Some(history)) => history
+                    .apply_tag(&name, &values)
+                    .map_err(|error| GameError(format!("Line {}: {}", line_number + 1, error)))?,
+            }
+        }
+
+        history.ok_or(GameError("Missing Players tag".to_string()))
+    }
+}
+
+impl GameHistory {
+    fn apply_tag(&mut self, name: &str, values: &[String]) -> Result<(), String> {
+        if name != "Round" && name != "InitialScore" && self.rounds.is_empty() {
+            Err(format!("{} tag outside of a round", name))?
+        }
+
+        match (name, values) {
+            ("InitialScore", [player, score]) => {
+                let score = parse_number(score)?;
+                self.initial_scores
+                    .insert(self.get_player(player)?.to_string(), score);
+            }
+            ("Round", [_]) => self.rounds.push(RoundHistory::default()),
+            ("Seed", [seed]) => self.current_round_mut().seed = Some(parse_number(seed)?),
+            ("Deal", [player, cards]) => {
+                let player = self.get_player(player)?.to_string();
+                self.current_round_mut()
+                    .dealt_hands
+                    .insert(player, parse_cards(cards)?);
+            }
+            ("Pass", [player, cards]) => {
+                let player = self.get_player(player)?.to_string();
+                self.current_round_mut()
+                    .passes
+                    .insert(player, parse_cards(cards)?);
+            }
+            ("Trick", plays) => {
+                let mut trick = Trick::default();
+                for play in plays {
+                    let (player, card) = play
+                        .rsplit_once(' ')
+                        .ok_or(format!("Invalid play: {}", play))?;
+                    trick
+                        .plays
+                        .push((self.get_player(player)?.to_string(), card.parse::<Card>()?));
+                }
+                self.current_round_mut().tricks.push(trick);
+            }
+            ("Score", [player, score]) => {
+                let player = self.get_player(player)?.to_string();
+                let score = parse_number(score)?;
+                self.current_round_mut().scores.insert(player, score);
+            }
+            _ => Err(format!("Invalid tag {} with {} values", name, values.len()))?,
+        }
+
+        Ok(())
+    }
+
+    fn get_player(&self, player: &str) -> Result<&str, String> {
+        self.players
+            .iter()
+            .find(|p| p.as_str() == player)
+            .map(|p| p.as_str())
+            .ok_or(format!("Unknown player {}", player))
+    }
+}
+
+fn tag<T: AsRef<str>>(name: &str, values: impl IntoIterator<Item = T>) -> String {
+    let values: Vec<String> = values
+        .into_iter()
+        .map(|value| {
+            let escaped = value.as_ref().replace('\\', "\\\\").replace('"', "\\\"");
+            format!("\"{}\"", escaped)
+        })
+        .collect();
+    format!("[{} {}]", name, values.join(" "))
+}
+
+pub(crate) fn parse_tag(line: &str) -> Result<(String, Vec<String>), String> {
+    let content = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or("Tag has to be enclosed in brackets".to_string())?;
+    let (name, rest) = content.split_once(' ').unwrap_or((content, ""));
+
+    let mut values = Vec::new();
+    let mut chars = rest.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' => continue,
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => value.push(chars.next().ok_or("Unfinished escape")?),
+                        Some(c) => value.push(c),
+                        None => Err("Unterminated value".to_string())?,
+                    }
+                }
+                values.push(value);
+            }
+            c => Err(format!("Unexpected character {}", c))?,
+        }
+    }
+
+    Ok((name.to_string(), values))
+}
+
+fn parse_number<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid number {}", value))
+}
+
+fn parse_cards(cards: &str) -> Result<HashSet<Card>, String> {
+    cards.split_whitespace().map(Card::from_str).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardSuit::{Club, Spade};
+    use crate::step::round_in_progress::RoundInProgressState;
+
+    fn get_players() -> Vec<String> {
+        vec!["1".to_string(), "2".to_string(), "3".to_string()]
+    }
+
+    fn play_round(seed: u64) -> GameStep<RoundInProgressState> {
+        let players = get_players();
+        let mut step = GameStep::initialize_from_seed(&players, seed);
+        for player in &players {
+            let cards = step.player_decks[player].iter().take(3).copied().collect();
+            step.handle_payload(
+                &CardExchangePayload {
+                    cards_to_exchange: cards,
+                },
+                player,
+            )
+            .unwrap();
+        }
+
+        let mut step = step.to_round_in_progress();
+        while !step.should_switch() {
+            let player = step.state.current_player.clone();
+            let card = step.player_decks[&player]
+                .iter()
+                .copied()
+                .find(|card| {
+                    step.validate_payload(&PlaceCardPayload { card: *card }, &player)
+                        .is_ok()
+                })
+                .unwrap();
+            step.handle_payload(&PlaceCardPayload { card }, &player)
+                .unwrap();
+        }
+        step
+    }
+
+    #[test]
+    fn trick_winner_is_highest_card_of_leading_suit() {
+        let trick = Trick {
+            plays: vec![
+                ("1".to_string(), Card::new(Club, 5).unwrap()),
+                ("2".to_string(), Card::new(Spade, 14).unwrap()),
+                ("3".to_string(), Card::new(Club, 9).unwrap()),
+            ],
+        };
+
+        assert_eq!(trick.winner(), Some("3"));
+        assert_eq!(Trick::default().winner(), None);
+    }
+
+    #[test]
+    fn recorded_round_replays() {
+        let history = play_round(7).to_round_finished().history;
+
+        assert_eq!(history.rounds.len(), 1);
+        assert_eq!(history.rounds[0].seed, Some(7));
+        assert_eq!(history.rounds[0].tricks.len(), 17);
+        assert_eq!(history.replay(), Ok(()));
+    }
+
+    #[test]
+    fn history_round_trips_through_text_format() {
+        let history = play_round(11).to_round_finished().history;
+        let text = history.to_string();
+
+        assert_eq!(text.parse::<GameHistory>(), Ok(history));
+    }
+
+    #[test]
+    fn single_round_keeps_initial_scores() {
+        let mut history = play_round(3).to_round_finished().history;
+        history.initial_scores = HashMap::from([("1".to_string(), 20)]);
+        let round = history.round(0).unwrap();
+
+        assert_eq!(round.initial_scores["1"], 20);
+        assert!(round.to_string().contains("[InitialScore \"1\" \"20\"]"));
+    }
+
+    #[test]
+    fn replay_fails_when_scores_do_not_match() {
+        let mut history = play_round(5).to_round_finished().history;
+        *history.rounds[0].scores.get_mut("1").unwrap() += 1;
+
+        assert!(history
+            .replay()
+            .unwrap_err()
+            .0
+            .contains("does not match expected score"));
+    }
+
+    #[test]
+    fn replay_fails_when_deal_does_not_match_seed() {
+        let mut history = play_round(5).to_round_finished().history;
+        history.rounds[0].seed = Some(6);
+
+        assert_eq!(
+            history.replay(),
+            Err(GameError(
+                "Round 1: Dealt hands do not match seed 6".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn replay_fails_when_deal_is_incomplete() {
+        let mut history = play_round(5).to_round_finished().history;
+        history.rounds[0].dealt_hands.remove("3");
+
+        assert_eq!(
+            history.replay(),
+            Err(GameError(
+                "Round 1: Dealt hands have to contain the whole deck".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_tag_with_escaped_values() {
+        assert_eq!(
+            parse_tag(r#"[Players "a \"b\"" "c\\d"]"#),
+            Ok((
+                "Players".to_string(),
+                vec!["a \"b\"".to_string(), "c\\d".to_string()]
+            ))
+        );
+        assert!(parse_tag("Players \"a\"").is_err());
+        assert!(parse_tag("[Players \"a]").is_err());
+    }
+
+    #[test]
+    fn parse_history_with_two_players() {
+        let text = "[Players \"1\" \"2\"]\n[Round \"1\"]\n";
+
+        assert_eq!(
+            text.parse::<GameHistory>(),
+            Err(GameError("Line 1: Invalid number of players".to_string()))
+        );
+    }
+
+    #[test]
+    fn replay_fails_with_two_players() {
+        let mut history = play_round(5).to_round_finished().history;
+        history.players.pop();
+
+        assert_eq!(
+            history.replay(),
+            Err(GameError("Invalid number of players".to_string()))
+        );
+    }
+
+    #[test]
+    fn replay_fails_when_score_is_missing() {
+        let mut history = play_round(5).to_round_finished().history;
+        history.rounds[0].scores.remove("2");
+
+        assert_eq!(
+            history.replay(),
+            Err(GameError("Round 1: Missing score of player 2".to_string()))
+        );
+    }
+
+    #[test]
+    fn replay_fails_when_tricks_are_split_differently() {
+        let mut history = play_round(5).to_round_finished().history;
+        let tricks = &mut history.rounds[0].tricks;
+        let play = tricks[1].plays.remove(0);
+        tricks[0].plays.push(play);
+
+        assert_eq!(
+            history.replay(),
+            Err(GameError(
+                "Round 1: Trick 1 has to hold a card of every player".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_history_with_duplicate_player() {
+        let text = "[Players \"1\" \"2\" \"1\"]\n";
+
+        assert_eq!(
+            text.parse::<GameHistory>(),
+            Err(GameError("Line 1: Duplicate player 1".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_history_with_unknown_player() {
+        let text = "[Players \"1\" \"2\" \"3\"]\n[Round \"1\"]\n[Score \"4\" \"0\"]\n";
+
+        assert_eq!(
+            text.parse::<GameHistory>(),
+            Err(GameError("Line 3: Unknown player 4".to_string()))
+        );
+    }
+}
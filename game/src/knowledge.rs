@@ -0,0 +1,295 @@
+use crate::card::{Card, CardSuit};
+use crate::helper::{get_full_deck, get_player_to_player_map};
+use crate::history::RoundHistory;
+use std::collections::{HashMap, HashSet};
+
+/// What a single player can infer about the current round from their own hand and public events.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlayerKnowledge {
+    pub player: String,
+    pub players: Vec<String>,
+    pub hand: HashSet<Card>,
+    pub played_cards: HashMap<Card, String>,
+    pub voids: HashMap<String, HashSet<CardSuit>>,
+    pub passed_cards: HashSet<Card>,
+    pub passed_to: String,
+    pub received_cards: HashSet<Card>,
+    pub received_from: String,
+    trick: Vec<(String, Card)>,
+}
+
+impl PlayerKnowledge {
+    pub fn new(player: &str, players: &[String]) -> PlayerKnowledge {
+        let player_to_player_map = get_player_to_player_map(players);
+        let received_from = player_to_player_map
+            .iter()
+            .find(|(_, to_player)| to_player.as_str() == player)
+            .map(|(from_player, _)| from_player.clone())
+            .unwrap_or_default();
+
+        PlayerKnowledge {
+            player: player.to_string(),
+            players: players.to_vec(),
+            hand: HashSet::new(),
+            played_cards: HashMap::new(),
+            voids: HashMap::new(),
+            passed_cards: HashSet::new(),
+            passed_to: player_to_player_map
+                .get(player)
+                .cloned()
+                .unwrap_or_default(),
+            received_cards: HashSet::new(),
+            received_from,
+            trick: Vec::new(),
+        }
+    }
+
+    /// Knowledge of `player` after all events recorded so far in `round`.
+    pub fn from_round(player: &str, players: &[String], round: &RoundHistory) -> PlayerKnowledge {
+        let mut knowledge = PlayerKnowledge::new(player, players);
+        if let Some(hand) = round.dealt_hands.get(player) {
+            knowledge.record_deal(hand);
+        }
+
+        let passed_cards = round.passes.get(player);
+        let received_cards = round.passes.get(&knowledge.received_from);
+        if round.passes.len() == players.len() {
+            knowledge.record_pass(passed_cards.unwrap(), received_cards.unwrap());
+        }
+
+        for (player, card) in round.tricks.iter().flat_map(|trick| trick.plays.iter()) {
+            knowledge.record_play(player, *card);
+        }
+
+        knowledge
+    }
+
+    pub fn record_deal(&mut self, hand: &HashSet<Card>) {
+        self.hand = hand.clone();
+    }
+
+    pub fn record_pass(&mut self, passed_cards: &HashSet<Card>, received_cards: &HashSet<Card>) {
+        self.hand.retain(|card| !passed_cards.contains(card));
+        self.hand.extend(received_cards);
+        self.passed_cards = passed_cards.clone();
+        self.received_cards = received_cards.clone();
+    }
+
+    pub fn record_play(&mut self, player: &str, card: Card) {
+        if self.trick.len() == self.players.len() {
+            self.trick.clear();
+        }
+
+        match self.trick.first() {
+            Some((_, leading_card)) if leading_card.suit != card.suit => {
+                self.add_void(player, leading_card.suit)
+            }
+            // hearts can only be led when nothing else is left in hand
+            None if card.suit == CardSuit::Heart && !self.played_cards.is_empty() => {
+                for suit in [CardSuit::Spade, CardSuit::Club, CardSuit::Diamond] {
+                    self.add_void(player, suit);
+                }
+            }
+            _ => {}
+        }
+
+        if player == self.player {
+            self.hand.remove(&card);
+        }
+        self.trick.push((player.to_string(), card));
+        self.played_cards.insert(card, player.to_string());
+    }
+
+    fn add_void(&mut self, player: &str, suit: CardSuit) {
+        self.voids
+            .entry(player.to_string())
+            .or_default()
+            .insert(suit);
+    }
+
+    pub fn is_void(&self, player: &str, suit: CardSuit) -> bool {
+        self.voids
+            .get(player)
+            .is_some_and(|voids| voids.contains(&suit))
+    }
+
+    /// Players that could still hold the card, empty once the card is played or not in the deck.
+    pub fn possible_holders(&self, card: &Card) -> Vec<String> {
+        if self.hand.contains(card) {
+            return vec![self.player.clone()];
+        }
+
+        if self.played_cards.contains_key(card) || !get_full_deck(self.players.len()).contains(card)
+        {
+            return Vec::new();
+        }
+
+        if self.passed_cards.contains(card) {
+            return vec![self.passed_to.clone()];
+        }
+
+        self.players
+            .iter()
+            .filter(|&player| player != &self.player && !self.is_void(player, card.suit))
+            .cloned()
+            .collect()
+    }
+
+    /// Cards which are neither in the player's hand nor played yet, so one of the opponents holds them.
+    pub fn unseen_cards(&self) -> HashSet<Card> {
+        get_full_deck(self.players.len())
+            .into_iter()
+            .filter(|card| !self.hand.contains(card) && !self.played_cards.contains_key(card))
+            .collect()
+    }
+
+    pub fn remaining_cards_per_suit(&self) -> HashMap<CardSuit, usize> {
+        let mut remaining = HashMap::from([
+            (CardSuit::Spade, 0),
+            (CardSuit::Club, 0),
+            (CardSuit::Heart, 0),
+            (CardSuit::Diamond, 0),
+        ]);
+        for card in self.unseen_cards() {
+            *remaining.get_mut(&card.suit).unwrap() += 1;
+        }
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardSuit::{Club, Diamond, Heart, Spade};
+    use crate::history::Trick;
+
+    fn get_players() -> Vec<String> {
+        vec!["1".to_string(), "2".to_string(), "3".to_string()]
+    }
+
+    fn card(notation: &str) -> Card {
+        notation.parse().unwrap()
+    }
+
+    fn get_knowledge() -> PlayerKnowledge {
+        let mut knowledge = PlayerKnowledge::new("1", &get_players());
+        knowledge.record_deal(&HashSet::from([
+            card("3C"),
+            card("QS"),
+            card("4D"),
+            card("5D"),
+        ]));
+        knowledge
+    }
+
+    #[test]
+    fn new_knowledge_knows_pass_directions() {
+        let knowledge = PlayerKnowledge::new("1", &get_players());
+
+        assert_eq!(knowledge.passed_to, "2");
+        assert_eq!(knowledge.received_from, "3");
+    }
+
+    #[test]
+    fn pass_updates_hand() {
+        let mut knowledge = get_knowledge();
+        knowledge.record_pass(&HashSet::from([card("QS")]), &HashSet::from([card("AH")]));
+
+        assert_eq!(
+            knowledge.hand,
+            HashSet::from([card("3C"), card("AH"), card("4D"), card("5D")])
+        );
+        assert_eq!(
+            knowledge.possible_holders(&card("QS")),
+            vec!["2".to_string()]
+        );
+    }
+
+    #[test]
+    fn not_following_suit_marks_player_void() {
+        let mut knowledge = get_knowledge();
+        knowledge.record_play("1", card("3C"));
+        knowledge.record_play("2", card("KS"));
+        knowledge.record_play("3", card("9C"));
+
+        assert!(knowledge.is_void("2", Club));
+        assert!(!knowledge.is_void("3", Club));
+        assert!(!knowledge.hand.contains(&card("3C")));
+        assert_eq!(
+            knowledge.possible_holders(&card("AC")),
+            vec!["3".to_string()]
+        );
+    }
+
+    #[test]
+    fn leading_heart_marks_player_void_in_other_suits() {
+        let mut knowledge = get_knowledge();
+        knowledge.record_play("1", card("3C"));
+        knowledge.record_play("2", card("4C"));
+        knowledge.record_play("3", card("9C"));
+        knowledge.record_play("3", card("2H"));
+
+        assert!(knowledge.is_void("3", Spade));
+        assert!(knowledge.is_void("3", Diamond));
+        assert!(!knowledge.is_void("3", Heart));
+    }
+
+    #[test]
+    fn possible_holders_of_queen_of_spades() {
+        let mut knowledge = get_knowledge();
+        assert_eq!(
+            knowledge.possible_holders(&card("QS")),
+            vec!["1".to_string()]
+        );
+
+        knowledge.record_play("1", card("QS"));
+        assert!(knowledge.possible_holders(&card("QS")).is_empty());
+    }
+
+    #[test]
+    fn two_of_clubs_is_not_held_in_3_player_game() {
+        let knowledge = get_knowledge();
+
+        assert!(knowledge.possible_holders(&card("2C")).is_empty());
+    }
+
+    #[test]
+    fn remaining_cards_per_suit_excludes_own_hand_and_played_cards() {
+        let mut knowledge = get_knowledge();
+        knowledge.record_play("1", card("3C"));
+        knowledge.record_play("2", card("4C"));
+
+        let remaining = knowledge.remaining_cards_per_suit();
+        assert_eq!(remaining[&Club], 10);
+        assert_eq!(remaining[&Spade], 12);
+        assert_eq!(remaining[&Diamond], 11);
+        assert_eq!(remaining[&Heart], 13);
+    }
+
+    #[test]
+    fn from_round_matches_incremental_updates() {
+        let players = get_players();
+        let round = RoundHistory {
+            seed: None,
+            dealt_hands: HashMap::from([
+                ("1".to_string(), HashSet::from([card("3C"), card("QS")])),
+                ("2".to_string(), HashSet::from([card("4C"), card("AH")])),
+                ("3".to_string(), HashSet::from([card("9H"), card("KS")])),
+            ]),
+            passes: HashMap::from([
+                ("1".to_string(), HashSet::from([card("QS")])),
+                ("2".to_string(), HashSet::from([card("AH")])),
+                ("3".to_string(), HashSet::from([card("KS")])),
+            ]),
+            tricks: vec![Trick {
+                plays: vec![("2".to_string(), card("4C")), ("3".to_string(), card("AH"))],
+            }],
+            scores: HashMap::new(),
+        };
+
+        let knowledge = PlayerKnowledge::from_round("1", &players, &round);
+        assert_eq!(knowledge.hand, HashSet::from([card("3C"), card("KS")]));
+        assert_eq!(knowledge.received_cards, HashSet::from([card("KS")]));
+        assert!(knowledge.is_void("3", Club));
+    }
+}
@@ -1,15 +1,50 @@
+mod analysis;
+mod bot;
 mod card;
+mod challenge;
+mod clock;
+pub mod dataset;
 mod error;
 pub mod game;
 pub mod helper;
+mod hint;
+pub mod history;
+mod knowledge;
+mod observer;
 mod payload;
+#[cfg(feature = "house-rules")]
+mod rules;
+#[cfg(not(feature = "house-rules"))]
+#[path = "rules_unsupported.rs"]
+mod rules;
+mod snapshot;
+mod solver;
 pub mod step;
+mod tournament;
+mod tutorial;
+mod view;
 
-pub use card::{Card, CardSuit};
+pub use analysis::{Blunder, GameAnalysis, MoveAnalysis};
+pub use bot::{Bot, LowestCardBot, SimpleBot};
+pub use card::{Card, CardNotation, CardSuit, Hand};
+pub use challenge::{ChallengeResult, DailyChallenge, Leaderboard};
+pub use clock::{Clock, PlayerClocks, SystemClock, TimeControl, TimeoutPolicy};
 pub use error::{GameError, GameResult};
 pub use game::GameState::{self, CardExchange, RoundFinished, RoundInProgress};
-pub use game::{Game, GameSettings};
+pub use game::{EndCondition, Game, GameSettings, TakeBackRequest};
+pub use hint::{Hint, HintReason};
+pub use history::{GameHistory, RoundHistory, Trick};
+pub use knowledge::PlayerKnowledge;
+pub use observer::GameObserver;
 pub use payload::{CardExchangePayload, ClaimReadinessPayload, PlaceCardPayload};
+pub use rules::HouseRules;
+pub use snapshot::GameSnapshot;
+pub use solver::Solver;
 pub use step::card_exchange::CardExchangeState;
 pub use step::round_finished::RoundFinishedState;
 pub use step::round_in_progress::RoundInProgressState;
+pub use tournament::{DuplicateTournament, TournamentStanding};
+pub use tutorial::{Explanation, Lesson, LessonPoint, Tutorial};
+pub use view::{
+    CardExchangeView, PlayerView, RoundFinishedView, RoundInProgressView, SpectatorView, StateView,
+};
@@ -0,0 +1,126 @@
+use crate::card::Card;
+use crate::history::Trick;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// Callbacks for integrations which react to the game as it runs, e.g. statistics or audit logs.
+/// All of them do nothing by default.
+pub trait GameObserver: Send + Sync {
+    /// Cards were dealt for the round with the given number, starting from 1.
+    fn on_deal(&self, _round: usize, _hands: &HashMap<String, HashSet<Card>>) {}
+
+    /// Called for every player once everyone declared their cards and the exchange happened.
+    fn on_pass(&self, _from_player: &str, _to_player: &str, _cards: &HashSet<Card>) {}
+
+    /// Includes the starting card, which is played automatically.
+    fn on_card_played(&self, _player: &str, _card: Card) {}
+
+    /// The card went back to the hand of the player, a completed trick it was part of is
+    /// no longer won.
+    fn on_card_taken_back(&self, _player: &str, _card: Card) {}
+
+    fn on_trick_won(&self, _player: &str, _trick: &Trick) {}
+
+    /// Holds the total scores after the round.
+    fn on_round_scored(&self, _round: usize, _scores: &HashMap<String, usize>) {}
+
+    /// The player took all the points of the round, called before `on_round_scored`.
+    fn on_moon(&self, _player: &str) {}
+
+    fn on_game_over(&self, _scores: &HashMap<String, usize>) {}
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct GameObservers(Vec<Arc<dyn GameObserver>>);
+
+impl GameObservers {
+    pub(crate) fn add(&mut self, observer: Arc<dyn GameObserver>) {
+        self.0.push(observer);
+    }
+
+    pub(crate) fn notify(&self, event: impl Fn(&dyn GameObserver)) {
+        for observer in &self.0 {
+            event(observer.as_ref());
+        }
+    }
+}
+
+impl Debug for GameObservers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GameObservers({})", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{EndCondition, Game, GameSettings};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl RecordingObserver {
+        fn record(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl GameObserver for RecordingObserver {
+        fn on_deal(&self, round: usize, _hands: &HashMap<String, HashSet<Card>>) {
+            self.record(format!("deal {}", round));
+        }
+
+        fn on_pass(&self, from_player: &str, to_player: &str, _cards: &HashSet<Card>) {
+            self.record(format!("pass {} {}", from_player, to_player));
+        }
+
+        fn on_card_played(&self, _player: &str, _card: Card) {
+            self.record("card".to_string());
+        }
+
+        fn on_trick_won(&self, _player: &str, _trick: &Trick) {
+            self.record("trick".to_string());
+        }
+
+        fn on_round_scored(&self, round: usize, _scores: &HashMap<String, usize>) {
+            self.record(format!("score {}", round));
+        }
+
+        fn on_game_over(&self, _scores: &HashMap<String, usize>) {
+            self.record("game over".to_string());
+        }
+    }
+
+    #[test]
+    fn observer_is_notified_about_the_whole_game() {
+        let players = vec!["1".to_string(), "2".to_string(), "3".to_string()];
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 1 },
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&players, settings, 7).unwrap();
+        let observer = Arc::new(RecordingObserver::default());
+        game.add_observer(observer.clone());
+        for player in &players {
+            game.replace_with_bot(player).unwrap();
+        }
+        game.play_bots(&crate::bot::SimpleBot);
+
+        let events = observer.events.lock().unwrap();
+        let count = |event: &str| events.iter().filter(|&e| e == event).count();
+        assert_eq!(
+            events[..4],
+            ["deal 1", "pass 1 2", "pass 2 3", "pass 3 1"].map(String::from)
+        );
+        assert_eq!(count("card"), 51);
+        assert_eq!(count("trick"), 17);
+        assert_eq!(
+            events[events.len() - 3..],
+            ["trick", "score 1", "game over"].map(String::from)
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! House rules written as [Rhai](https://rhai.rs) scripts.
+//!
+//! A script can define any of the following functions, the standard rules apply to the rest.
+//!
+//! - `validate_card(state, card)` is called for a card which is legal under the standard rules,
+//!   it can forbid it by returning `false` or a string with the reason. When it forbids every
+//!   legal card, the standard rules apply to that move.
+//! - `trick_winner(state)` returns the name of the player who takes the trick in `state.table`.
+//! - `round_score(state, score)` returns the points `state.player` takes for the round,
+//!   before shooting the moon is checked.
+//!
+//! `state` is a read-only copy of the round with `players`, `player` (the one the hook is about),
+//! `hand`, `table_suit`, `table` and `tricks` (completed, with their `winner`), `round_scores`
+//! and `scores`. Cards are maps with `suit` (`"SPADE"`, `"CLUB"`, `"HEART"`, `"DIAMOND"`),
+//! `value` (2 to 14) and `score`.
+//!
+//! ```text
+//! // ten of clubs doubles the round score of the player who took it
+//! fn round_score(state, score) {
+//!     for trick in state.tricks {
+//!         if trick.winner == state.player
+//!             && trick.plays.some(|play| play.card.suit == "CLUB" && play.card.value == 10) {
+//!             return score * 2;
+//!         }
+//!     }
+//!     score
+//! }
+//! ```
+//!
+//! Scripts have no access to anything outside of the state passed to them. A hook which fails,
+//! e.g. by running over the operation limit, falls back to the standard rules.
+//! Bots and hints pick from the cards the script allows, the solver and history replay only
+//! know the standard rules.
+
+use crate::card::{Card, CardSuit};
+use crate::error::{GameError, GameResult};
+use crate::step::round_in_progress::RoundInProgressState;
+use crate::step::GameStep;
+use rhai::{CallFnOptions, Dynamic, Engine, Scope, AST};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// Bounds the execution time of a single hook call.
+const MAX_OPERATIONS: u64 = 100_000;
+
+#[derive(Clone)]
+pub struct HouseRules {
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+}
+
+#[derive(Serialize)]
+struct ScriptState<'a> {
+    players: &'a [String],
+    player: &'a str,
+    hand: Vec<Card>,
+    table_suit: Option<CardSuit>,
+    table: Vec<ScriptPlay>,
+    tricks: Vec<ScriptTrick>,
+    round_scores: &'a HashMap<String, usize>,
+    scores: &'a HashMap<String, usize>,
+}
+
+#[derive(Serialize)]
+struct ScriptPlay {
+    player: String,
+    card: Card,
+}
+
+#[derive(Serialize)]
+struct ScriptTrick {
+    winner: String,
+    plays: Vec<ScriptPlay>,
+}
+
+impl HouseRules {
+    pub fn compile(script: &str) -> GameResult<HouseRules> {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(1_000)
+            .set_max_array_size(1_000)
+            .set_max_map_size(1_000)
+            .disable_symbol("eval")
+            .on_print(|_| {})
+            .on_debug(|_, _, _| {});
+
+        let ast = engine
+            .compile(script)
+            .map_err(|error| GameError(format!("Invalid house rules: {}", error)))?;
+
+        Ok(HouseRules {
+            engine: Arc::new(engine),
+            ast: Arc::new(ast),
+        })
+    }
+
+    fn call(&self, name: &str, args: impl rhai::FuncArgs) -> Option<Dynamic> {
+        let defined = self
+            .ast
+            .iter_functions()
+            .any(|function| function.name == name);
+        if !defined {
+            return None;
+        }
+
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options(options, &mut Scope::new(), &self.ast, name, args)
+            .ok()
+    }
+
+    pub(crate) fn validate_card(
+        &self,
+        step: &GameStep<RoundInProgressState>,
+        player: &str,
+        card: Card,
+    ) -> GameResult<()> {
+        let state = script_state(step, player);
+        let Some(result) = self.call("validate_card", (state, to_dynamic(&card))) else {
+            return Ok(());
+        };
+
+        if let Some(reason) = result.clone().try_cast::<String>() {
+            Err(GameError(reason))?
+        }
+        if result.as_bool() == Ok(false) {
+            Err(GameError(format!(
+                "Card {} is not allowed by house rules",
+                card
+            )))?
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn trick_winner(
+        &self,
+        step: &GameStep<RoundInProgressState>,
+        player: &str,
+    ) -> Option<String> {
+        let state = script_state(step, player);
+        let winner = self.call("trick_winner", (state,))?.try_cast::<String>()?;
+        step.state
+            .cards_on_table
+            .contains_key(&winner)
+            .then_some(winner)
+    }
+
+    pub(crate) fn round_score(
+        &self,
+        step: &GameStep<RoundInProgressState>,
+        player: &str,
+        score: usize,
+    ) -> Option<usize> {
+        let state = script_state(step, player);
+        let score = self
+            .call("round_score", (state, score as rhai::INT))?
+            .as_int()
+            .ok()?;
+        usize::try_from(score).ok()
+    }
+}
+
+impl Debug for HouseRules {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HouseRules")
+    }
+}
+
+fn to_dynamic(value: &impl Serialize) -> Dynamic {
+    rhai::serde::to_dynamic(value).unwrap()
+}
+
+fn script_state(step: &GameStep<RoundInProgressState>, player: &str) -> Dynamic {
+    let round = step.history.rounds.last();
+    let tricks = round
+        .map(|round| round.tricks.as_slice())
+        .unwrap_or_default();
+    let to_plays = |plays: &[(String, Card)]| {
+        plays
+            .iter()
+            .map(|(player, card)| ScriptPlay {
+                player: player.clone(),
+                card: *card,
+            })
+            .collect()
+    };
+
+    let completed_tricks = step.state.trick_winners.len();
+    let table = match step.state.cards_on_table.is_empty() {
+        true => Vec::new(),
+        false => tricks
+            .get(completed_tricks)
+            .map(|trick| to_plays(&trick.plays))
+            .unwrap_or_default(),
+    };
+    let mut hand: Vec<Card> = step.player_decks[player].iter().copied().collect();
+    hand.sort_by_key(|card| (card.suit, card.value));
+
+    to_dynamic(&ScriptState {
+        players: &step.players,
+        player,
+        hand,
+        table_suit: step.state.table_suit,
+        table,
+        tricks: tricks
+            .iter()
+            .zip(&step.state.trick_winners)
+            .map(|(trick, winner)| ScriptTrick {
+                winner: winner.clone(),
+                plays: to_plays(&trick.plays),
+            })
+            .collect(),
+        round_scores: &step.state.round_score,
+        scores: &step.scores,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::SimpleBot;
+    use crate::game::GameState::{CardExchange, RoundFinished, RoundInProgress};
+    use crate::game::{EndCondition, Game, GameSettings};
+    use crate::payload::PlaceCardPayload;
+    use crate::step::round_finished::RoundFinishedState;
+
+    fn get_players() -> Vec<String> {
+        vec!["1".to_string(), "2".to_string(), "3".to_string()]
+    }
+
+    fn get_game(rule_script: Option<&str>) -> GameResult<Game> {
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 1 },
+            rule_script: rule_script.map(String::from),
+            ..GameSettings::default()
+        };
+        Game::from_seed(&get_players(), settings, 7)
+    }
+
+    fn play_round(rule_script: Option<&str>) -> GameStep<RoundFinishedState> {
+        let mut game = get_game(rule_script).unwrap();
+        for player in get_players() {
+            game.replace_with_bot(&player).unwrap();
+        }
+        game.play_bots(&SimpleBot);
+
+        let RoundFinished(step) = game.state else {
+            panic!("Round should be finished")
+        };
+        step
+    }
+
+    #[test]
+    fn invalid_script_is_rejected() {
+        let error = get_game(Some("fn round_score(state, score) {")).unwrap_err();
+        assert!(error.0.starts_with("Invalid house rules"));
+    }
+
+    fn start_round(game: &mut Game) -> GameStep<RoundInProgressState> {
+        let CardExchange(step) = &mut game.state else {
+            unreachable!()
+        };
+        for player in get_players() {
+            step.state
+                .cards_to_exchange
+                .insert(player, Default::default());
+        }
+        step.clone().to_round_in_progress()
+    }
+
+    #[test]
+    fn forbidden_card_is_rejected() {
+        for (script, reason) in [
+            (
+                "fn validate_card(state, card) { if card.value == 5 { \"No fives today\" } else { true } }",
+                "No fives today",
+            ),
+            (
+                "fn validate_card(state, card) { card.value != 5 }",
+                "is not allowed by house rules",
+            ),
+        ] {
+            let mut game = get_game(Some(script)).unwrap();
+            let mut step = start_round(&mut game);
+
+            let player = step.state.current_player.clone();
+            let legal_cards = step.legal_cards(&player);
+            assert!(!legal_cards.is_empty());
+            assert!(legal_cards.iter().all(|card| card.value != 5));
+            let card = *step.player_decks[&player]
+                .iter()
+                .find(|card| Some(card.suit) == step.state.table_suit && card.value == 5)
+                .unwrap();
+            let error = step
+                .handle_payload(&PlaceCardPayload { card }, &player)
+                .unwrap_err();
+            assert!(error.0.contains(reason));
+        }
+    }
+
+    #[test]
+    fn standard_rules_apply_when_script_forbids_every_card() {
+        let script = "fn validate_card(state, card) { false }";
+        let mut game = get_game(Some(script)).unwrap();
+        let mut step = start_round(&mut game);
+
+        let player = step.state.current_player.clone();
+        let mut standard_step = step.clone();
+        standard_step.house_rules = None;
+        let legal_cards = step.legal_cards(&player);
+        assert!(!legal_cards.is_empty());
+        assert_eq!(legal_cards, standard_step.legal_cards(&player));
+        step.handle_payload(
+            &PlaceCardPayload {
+                card: legal_cards[0],
+            },
+            &player,
+        )
+        .unwrap();
+
+        let step = play_round(Some(script));
+        assert!(step.history.rounds[0].tricks.len() > 1);
+    }
+
+    #[test]
+    fn views_only_offer_cards_allowed_by_script() {
+        let mut game = get_game(Some("fn validate_card(state, card) { card.value > 5 }")).unwrap();
+        game.state = RoundInProgress(start_round(&mut game));
+        let RoundInProgress(step) = &game.state else {
+            unreachable!()
+        };
+
+        let player = step.state.current_player.clone();
+        let view = game.view_for(&player).unwrap();
+        assert_eq!(view.legal_cards, step.legal_cards(&player));
+        assert!(!view.legal_cards.is_empty());
+        assert!(view.legal_cards.iter().all(|card| card.value > 5));
+    }
+
+    #[test]
+    fn trick_winner_is_chosen_by_script() {
+        let step = play_round(Some("fn trick_winner(state) { state.table[0].player }"));
+        let round = &step.history.rounds[0];
+
+        for (trick, next_trick) in round.tricks.iter().zip(&round.tricks[1..]) {
+            assert_eq!(trick.plays[0].0, next_trick.plays[0].0);
+        }
+    }
+
+    #[test]
+    fn round_score_is_changed_by_script() {
+        let script = r#"
+            fn round_score(state, score) {
+                for trick in state.tricks {
+                    if trick.winner == state.player
+                        && trick.plays.some(|play| play.card.suit == "CLUB" && play.card.value == 10) {
+                        return score * 2;
+                    }
+                }
+                score
+            }
+        "#;
+        let standard_step = play_round(None);
+        let house_step = play_round(Some(script));
+
+        let ten_of_clubs: Card = "10C".parse().unwrap();
+        let taker = standard_step.history.rounds[0]
+            .tricks
+            .iter()
+            .find(|trick| trick.plays.iter().any(|(_, card)| card == &ten_of_clubs))
+            .and_then(|trick| trick.winner())
+            .unwrap();
+        for player in get_players() {
+            let score = standard_step.scores[&player];
+            match player == taker {
+                true => assert_eq!(house_step.scores[&player], score * 2),
+                false => assert_eq!(house_step.scores[&player], score),
+            }
+        }
+    }
+}
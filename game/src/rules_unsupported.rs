@@ -0,0 +1,44 @@
+//! Stand-in for the house rules when the `house-rules` feature is disabled,
+//! any script is rejected and the standard rules apply.
+
+use crate::card::Card;
+use crate::error::{GameError, GameResult};
+use crate::step::round_in_progress::RoundInProgressState;
+use crate::step::GameStep;
+
+#[derive(Clone, Debug)]
+pub enum HouseRules {}
+
+impl HouseRules {
+    pub fn compile(_script: &str) -> GameResult<HouseRules> {
+        Err(GameError(
+            "House rules are not supported in this build".to_string(),
+        ))
+    }
+
+    pub(crate) fn validate_card(
+        &self,
+        _step: &GameStep<RoundInProgressState>,
+        _player: &str,
+        _card: Card,
+    ) -> GameResult<()> {
+        match *self {}
+    }
+
+    pub(crate) fn trick_winner(
+        &self,
+        _step: &GameStep<RoundInProgressState>,
+        _player: &str,
+    ) -> Option<String> {
+        match *self {}
+    }
+
+    pub(crate) fn round_score(
+        &self,
+        _step: &GameStep<RoundInProgressState>,
+        _player: &str,
+        _score: usize,
+    ) -> Option<usize> {
+        match *self {}
+    }
+}
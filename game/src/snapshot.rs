@@ -0,0 +1,282 @@
+//! Snapshots of running games, e.g. to keep them over a server restart.
+//!
+//! A snapshot holds the hand history of the game and the few things the history does not
+//! record. The game is restored by replaying the history through the rules, so a snapshot
+//! cannot bring back a game the rules would not allow.
+
+use crate::card::Card;
+use crate::clock::PlayerClocks;
+use crate::error::{GameError, GameResult};
+use crate::game::GameState::{CardExchange, RoundFinished, RoundInProgress};
+use crate::game::{validate_number_of_players, Game, GameSettings, TakeBackRequest};
+use crate::history::{GameHistory, RoundHistory};
+use crate::payload::{CardExchangePayload, ClaimReadinessPayload, PlaceCardPayload};
+use crate::step::GameStep;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub players: Vec<String>,
+    pub settings: GameSettings,
+    /// Kept apart from settings, which do not serialize the deal.
+    pub deal_seeds: Vec<u64>,
+    pub deal: Option<Vec<Vec<Card>>>,
+    /// In the text format of the `history` module.
+    pub history: String,
+    /// Players who confirmed their exchange in the current round.
+    pub confirmed_players: Vec<String>,
+    /// Players ready for the next round, once the current one is finished.
+    pub ready_players: Vec<String>,
+    pub is_finished: bool,
+    pub left_players: Vec<String>,
+    pub end_votes: Vec<String>,
+    pub take_back_request: Option<TakeBackRequest>,
+    pub clocks: Option<PlayerClocks>,
+    pub hints_used: HashMap<String, usize>,
+}
+
+impl Game {
+    pub fn snapshot(&self) -> GameSnapshot {
+        let (mut confirmed_players, mut ready_players) = match &self.state {
+            CardExchange(step) => (
+                step.state.confirmed_players.iter().cloned().collect(),
+                Vec::new(),
+            ),
+            RoundInProgress(_) => (Vec::new(), Vec::new()),
+            RoundFinished(step) => (
+                Vec::new(),
+                step.state
+                    .players_ready
+                    .iter()
+                    .filter(|(_, &ready)| ready)
+                    .map(|(player, _)| player.clone())
+                    .collect(),
+            ),
+        };
+        let mut end_votes: Vec<String> = self.end_votes.iter().cloned().collect();
+        confirmed_players.sort();
+        ready_players.sort();
+        end_votes.sort();
+
+        GameSnapshot {
+            players: self.players.clone(),
+            settings: self.settings.clone(),
+            deal_seeds: self.settings.deal_seeds.clone(),
+            deal: self.settings.deal.clone(),
+            history: self.history().to_string(),
+            confirmed_players,
+            ready_players,
+            is_finished: self.is_finished,
+            left_players: self.left_players.clone(),
+            end_votes,
+            take_back_request: self.take_back_request.clone(),
+            clocks: self.clocks.clone(),
+            hints_used: self.hints_used.clone(),
+        }
+    }
+
+    /// Replays the history of the snapshot.
+    pub fn from_snapshot(snapshot: &GameSnapshot) -> GameResult<Game> {
+        let history: GameHistory = snapshot.history.parse()?;
+        validate_number_of_players(&snapshot.players)?;
+        if history.players != snapshot.players {
+            Err(GameError(
+                "Snapshot history does not match its players".to_string(),
+            ))?
+        }
+        let first_round = history
+            .rounds
+            .first()
+            .ok_or(GameError("Snapshot history has no rounds".to_string()))?;
+
+        let mut settings = snapshot.settings.clone();
+        settings.deal_seeds = snapshot.deal_seeds.clone();
+        settings.deal = snapshot.deal.clone();
+        // later rounds are dealt again from their recorded seeds
+        let mut replay_settings = settings.clone();
+        replay_settings.deal_seeds = history
+            .rounds
+            .iter()
+            .map(|round| round.seed.unwrap_or_default())
+            .collect();
+
+        let step = GameStep::initialize(
+            &snapshot.players,
+            first_round.dealt_hands.clone(),
+            first_round.seed,
+        );
+        let mut game = Game::from_step(step, replay_settings)?;
+        for (index, round) in history.rounds.iter().enumerate() {
+            game.replay_snapshot_round(index, round, snapshot)
+                .map_err(|error| GameError(format!("Round {}: {}", index + 1, error.0)))?;
+        }
+        if let RoundFinished(_) = game.state {
+            for player in &snapshot.ready_players {
+                game.claim_readiness(player, &ClaimReadinessPayload { ready: true })?;
+            }
+        }
+
+        game.settings = settings;
+        game.is_finished = snapshot.is_finished;
+        game.left_players = snapshot.left_players.clone();
+        game.end_votes = snapshot.end_votes.iter().cloned().collect();
+        game.take_back_request = snapshot.take_back_request.clone();
+        game.hints_used = snapshot.hints_used.clone();
+        game.clocks = snapshot.clocks.clone();
+        Ok(game)
+    }
+
+    // earlier rounds were confirmed and claimed ready by everyone
+    fn replay_snapshot_round(
+        &mut self,
+        index: usize,
+        round: &RoundHistory,
+        snapshot: &GameSnapshot,
+    ) -> GameResult<()> {
+        let players = self.players.clone();
+        if index > 0 {
+            for player in &players {
+                self.claim_readiness(player, &ClaimReadinessPayload { ready: true })?;
+            }
+        }
+        let dealt_hands = self
+            .history()
+            .rounds
+            .get(index)
+            .map(|round| &round.dealt_hands);
+        if dealt_hands != Some(&round.dealt_hands) {
+            Err(GameError("Dealt hands do not match the seed".to_string()))?
+        }
+
+        for player in &players {
+            if let Some(cards) = round.passes.get(player) {
+                let payload = CardExchangePayload {
+                    cards_to_exchange: cards.clone(),
+                };
+                self.exchange_cards(player, &payload)?;
+            }
+        }
+        if self.settings.confirm_card_exchange {
+            for player in &players {
+                if !round.tricks.is_empty() || snapshot.confirmed_players.contains(player) {
+                    self.confirm_exchange_cards(player)?;
+                }
+            }
+        }
+
+        // the starting card is played with the exchange
+        for (player, card) in round
+            .tricks
+            .iter()
+            .flat_map(|trick| trick.plays.iter())
+            .skip(1)
+        {
+            self.place_card(player, &PlaceCardPayload { card: *card })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{Bot, SimpleBot};
+    use crate::card::CardNotation;
+    use crate::clock::TimeControl;
+    use crate::game::EndCondition;
+    use crate::helper::get_full_deck;
+
+    fn get_players() -> Vec<String> {
+        vec!["1".to_string(), "2".to_string(), "3".to_string()]
+    }
+
+    fn play_move(game: &mut Game, player: &str) {
+        let view = game.view_for(player).unwrap();
+        match &game.state {
+            CardExchange(step) if !step.state.cards_to_exchange.contains_key(player) => {
+                let payload = CardExchangePayload {
+                    cards_to_exchange: SimpleBot.choose_exchange_cards(&view),
+                };
+                game.exchange_cards(player, &payload).unwrap();
+            }
+            CardExchange(_) => game.confirm_exchange_cards(player).unwrap(),
+            RoundInProgress(_) => {
+                let card = SimpleBot.choose_card(&view).unwrap();
+                game.place_card(player, &PlaceCardPayload { card }).unwrap();
+            }
+            RoundFinished(_) => {
+                let payload = ClaimReadinessPayload { ready: true };
+                game.claim_readiness(player, &payload).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn game_is_restored_at_every_move() {
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 2 },
+            confirm_card_exchange: true,
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&get_players(), settings, 3).unwrap();
+        game.left_players = vec!["2".to_string(), "3".to_string()];
+        game.end_votes.insert("2".to_string());
+
+        while !game.is_finished {
+            game.play_bots(&SimpleBot);
+            let snapshot = game.snapshot();
+            let json = serde_json::to_string(&snapshot).unwrap();
+            let restored = Game::from_snapshot(&serde_json::from_str(&json).unwrap()).unwrap();
+            assert_eq!(restored.snapshot(), snapshot);
+            assert_eq!(restored.view_for("1"), game.view_for("1"));
+
+            if !game.is_finished {
+                play_move(&mut game, "1");
+            }
+        }
+        assert_eq!(game.round(), 2);
+    }
+
+    #[test]
+    fn preset_deal_and_clocks_are_kept() {
+        let deal: Vec<Vec<Card>> = get_full_deck(3).chunks(17).map(<[Card]>::to_vec).collect();
+        let settings = GameSettings {
+            time_control: Some(TimeControl::PerMove { move_millis: 1000 }),
+            deal: Some(deal.clone()),
+            deal_seeds: vec![0, 5],
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&get_players(), settings, 3).unwrap();
+        play_move(&mut game, "2");
+        let clocks = game.clocks.as_mut().unwrap();
+        clocks.updated_at = Some(1234);
+        clocks.remaining_millis.insert("1".to_string(), 300);
+
+        let restored = Game::from_snapshot(&game.snapshot()).unwrap();
+        assert_eq!(restored.settings, game.settings);
+        assert_eq!(restored.history(), game.history());
+        let clocks = restored.clocks.unwrap();
+        assert_eq!(clocks.updated_at, Some(1234));
+        assert_eq!(clocks.remaining_millis["1"], 300);
+    }
+
+    #[test]
+    fn snapshot_with_illegal_moves_is_rejected() {
+        let mut game = Game::from_seed(&get_players(), GameSettings::default(), 3).unwrap();
+        for player in get_players() {
+            play_move(&mut game, &player);
+        }
+        // the starting player plays again instead of the next one
+        let mut snapshot = game.snapshot();
+        let (player, card) = game.history().last_play().unwrap().clone();
+        let play = format!("{} {}", player, card.to_notation(CardNotation::Short));
+        snapshot.history = snapshot.history.replace(
+            &format!("[Trick \"{}\"]", play),
+            &format!("[Trick \"{}\" \"{}\"]", play, play),
+        );
+
+        let error = Game::from_snapshot(&snapshot).unwrap_err();
+        assert!(error.0.starts_with("Round 1: "));
+    }
+}
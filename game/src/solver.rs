@@ -0,0 +1,314 @@
+use crate::card::{Card, CardSuit};
+use crate::payload::PlaceCardPayload;
+use crate::step::round_in_progress::RoundInProgressState;
+use crate::step::{GameStep, ALL_POINTS};
+use std::cmp::{max, min};
+use std::collections::HashMap;
+
+/// Double dummy solver for the rest of a round.
+///
+/// Every player's hand is known. For a given player it finds the round points that player can
+/// guarantee when all the other players play against them. The search is exponential in the
+/// number of cards left, so `with_node_limit` should be used for positions early in a round.
+pub struct Solver {
+    node_limit: Option<usize>,
+    nodes: usize,
+    memo: HashMap<PositionKey, (i32, Bound)>,
+}
+
+#[derive(Clone, Copy)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct PositionKey {
+    player: usize,
+    current_player: usize,
+    hands: Vec<u64>,
+    table: Vec<Option<u8>>,
+    table_suit: Option<CardSuit>,
+    round_score: Vec<usize>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            node_limit: None,
+            nodes: 0,
+            memo: HashMap::new(),
+        }
+    }
+
+    pub fn with_node_limit(node_limit: usize) -> Solver {
+        Solver {
+            node_limit: Some(node_limit),
+            ..Solver::new()
+        }
+    }
+
+    /// Round points the player ends up with under optimal play of everyone, including the
+    /// points already taken in this round and the shoot the moon rule.
+    /// Returns `None` when the node limit was exceeded.
+    pub fn guaranteed_points(
+        &mut self,
+        step: &GameStep<RoundInProgressState>,
+        player: &str,
+    ) -> Option<usize> {
+        self.nodes = 0;
+        let player = step.players.iter().position(|p| p == player)?;
+        let value = self.search(&Self::prepare(step), player, i32::MIN, i32::MAX)?;
+        Some(value as usize)
+    }
+
+    /// Guaranteed points of the current player after playing each of their legal cards.
+    pub fn evaluate_cards(
+        &mut self,
+        step: &GameStep<RoundInProgressState>,
+    ) -> Option<Vec<(Card, usize)>> {
+        self.nodes = 0;
+        let step = Self::prepare(step);
+        let current_player = step.state.current_player.clone();
+        let player = step.players.iter().position(|p| p == &current_player)?;
+
+        let mut evaluations = Vec::new();
+        for card in step.legal_cards(&current_player) {
+            let mut child = step.clone();
+            child.dispatch_payload(&PlaceCardPayload { card }, &current_player);
+            let value = self.search(&child, player, i32::MIN, i32::MAX)?;
+            evaluations.push((card, value as usize));
+        }
+
+        Some(evaluations)
+    }
+
+    pub fn best_card(&mut self, step: &GameStep<RoundInProgressState>) -> Option<(Card, usize)> {
+        self.evaluate_cards(step)?
+            .into_iter()
+            .min_by_key(|&(_, points)| points)
+    }
+
+    // history is not needed for the search and would only grow with every cloned position
+    fn prepare(step: &GameStep<RoundInProgressState>) -> GameStep<RoundInProgressState> {
+        let mut step = step.clone();
+        step.history.rounds.clear();
+        step
+    }
+
+    fn search(
+        &mut self,
+        step: &GameStep<RoundInProgressState>,
+        player: usize,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> Option<i32> {
+        self.nodes += 1;
+        if self.node_limit.is_some_and(|limit| self.nodes > limit) {
+            return None;
+        }
+
+        if step.should_switch() {
+            return Some(get_round_points(step, player) as i32);
+        }
+
+        let key = get_position_key(step, player);
+        if let Some(&(value, bound)) = self.memo.get(&key) {
+            match bound {
+                Bound::Exact => return Some(value),
+                Bound::Lower => alpha = max(alpha, value),
+                Bound::Upper => beta = min(beta, value),
+            }
+            if alpha >= beta {
+                return Some(value);
+            }
+        }
+
+        let (alpha_original, beta_original) = (alpha, beta);
+        let current_player = step.state.current_player.clone();
+        let maximizing = current_player != step.players[player];
+        let mut best = if maximizing { i32::MIN } else { i32::MAX };
+
+        for card in step.legal_cards(&current_player) {
+            let mut child = step.clone();
+            child.dispatch_payload(&PlaceCardPayload { card }, &current_player);
+            let value = self.search(&child, player, alpha, beta)?;
+
+            if maximizing {
+                best = max(best, value);
+                alpha = max(alpha, best);
+            } else {
+                best = min(best, value);
+                beta = min(beta, best);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best <= alpha_original {
+            Bound::Upper
+        } else if best >= beta_original {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.memo.insert(key, (best, bound));
+
+        Some(best)
+    }
+}
+
+fn get_round_points(step: &GameStep<RoundInProgressState>, player: usize) -> usize {
+    match step.state.moon_shooter() {
+        Some(all_scorer) if all_scorer == &step.players[player] => 0,
+        Some(_) => ALL_POINTS,
+        None => step
+            .state
+            .round_score
+            .get(&step.players[player])
+            .copied()
+            .unwrap_or(0),
+    }
+}
+
+fn get_card_index(card: &Card) -> u8 {
+    let suit_index = match card.suit {
+        CardSuit::Spade => 0,
+        CardSuit::Club => 1,
+        CardSuit::Heart => 2,
+        CardSuit::Diamond => 3,
+    };
+    suit_index * 13 + card.value as u8 - 2
+}
+
+fn get_position_key(step: &GameStep<RoundInProgressState>, player: usize) -> PositionKey {
+    PositionKey {
+        player,
+        current_player: step
+            .players
+            .iter()
+            .position(|p| p == &step.state.current_player)
+            .unwrap(),
+        hands: step
+            .players
+            .iter()
+            .map(|p| {
+                step.player_decks[p]
+                    .iter()
+                    .fold(0, |hand, card| hand | 1 << get_card_index(card))
+            })
+            .collect(),
+        table: step
+            .players
+            .iter()
+            .map(|p| step.state.cards_on_table.get(p).map(get_card_index))
+            .collect(),
+        table_suit: step.state.table_suit,
+        round_score: step
+            .players
+            .iter()
+            .map(|p| step.state.round_score.get(p).copied().unwrap_or(0))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::get_player_to_player_map;
+    use crate::history::GameHistory;
+    use std::collections::HashSet;
+
+    fn get_step(
+        hands: [&[&str]; 3],
+        round_score: HashMap<String, usize>,
+    ) -> GameStep<RoundInProgressState> {
+        let players = vec!["1".to_string(), "2".to_string(), "3".to_string()];
+        GameStep {
+            player_to_player_map: get_player_to_player_map(&players),
+            scores: round_score.clone(),
+            player_decks: players
+                .iter()
+                .zip(hands)
+                .map(|(player, hand)| {
+                    let cards: HashSet<Card> =
+                        hand.iter().map(|card| card.parse().unwrap()).collect();
+                    (player.clone(), cards)
+                })
+                .collect(),
+            history: GameHistory::new(&players),
+            house_rules: None,
+            state: RoundInProgressState {
+                current_player: "1".to_string(),
+                table_suit: None,
+                cards_on_table: HashMap::new(),
+                round_score,
+                trick_winners: Vec::new(),
+            },
+            players,
+        }
+    }
+
+    #[test]
+    fn leading_queen_of_spades_into_a_void_loses_13_points() {
+        let step = get_step(
+            [&["QS", "2D"], &["AS", "3S"], &["4D", "5D"]],
+            HashMap::new(),
+        );
+        let mut solver = Solver::new();
+
+        assert_eq!(
+            solver.evaluate_cards(&step),
+            Some(vec![
+                ("QS".parse().unwrap(), 13),
+                ("2D".parse().unwrap(), 0)
+            ])
+        );
+        assert_eq!(solver.best_card(&step), Some(("2D".parse().unwrap(), 0)));
+        assert_eq!(solver.guaranteed_points(&step, "1"), Some(0));
+    }
+
+    #[test]
+    fn guaranteed_points_include_points_taken_earlier() {
+        let step = get_step(
+            [&["2D"], &["3D"], &["4D"]],
+            HashMap::from([("1".to_string(), 5)]),
+        );
+
+        assert_eq!(Solver::new().guaranteed_points(&step, "1"), Some(5));
+    }
+
+    #[test]
+    fn shooting_the_moon_gives_other_players_all_points() {
+        let step = get_step(
+            [&["AH"], &["2H"], &["3H"]],
+            HashMap::from([("1".to_string(), 40)]),
+        );
+        let mut solver = Solver::new();
+
+        assert_eq!(solver.guaranteed_points(&step, "1"), Some(0));
+        assert_eq!(solver.guaranteed_points(&step, "2"), Some(43));
+    }
+
+    #[test]
+    fn node_limit_stops_the_search() {
+        let step = get_step(
+            [&["QS", "2D"], &["AS", "3S"], &["4D", "5D"]],
+            HashMap::new(),
+        );
+
+        assert_eq!(
+            Solver::with_node_limit(2).guaranteed_points(&step, "1"),
+            None
+        );
+    }
+}
@@ -1,17 +1,24 @@
 use crate::card::Card;
 use crate::error::{GameError, GameResult};
+use crate::history::GameHistory;
+use crate::rules::HouseRules;
 use std::collections::{HashMap, HashSet};
 
 pub mod card_exchange;
 pub mod round_finished;
 pub mod round_in_progress;
 
+/// Points of all hearts and the queen of spades, a player taking all of them shoots the moon.
+pub const ALL_POINTS: usize = 43;
+
 #[derive(Debug, Clone)]
 pub struct GameStep<S> {
     pub players: Vec<String>,
     pub player_to_player_map: HashMap<String, String>,
     pub scores: HashMap<String, usize>,
     pub player_decks: HashMap<String, HashSet<Card>>,
+    pub history: GameHistory,
+    pub house_rules: Option<HouseRules>,
     pub state: S,
 }
 
@@ -29,10 +36,17 @@ impl<S> GameStep<S> {
 }
 
 #[cfg(test)]
+#[allow(clippy::clone_on_copy)]
 mod tests {
     use super::*;
     use crate::card::CardSuit::{Club, Spade};
-    use crate::payload::{CardExchangePayload, PlaceCardPayload};
+    use crate::helper::get_full_deck;
+    use crate::payload::{CardExchangePayload, ClaimReadinessPayload, PlaceCardPayload};
+    use crate::step::card_exchange::CardExchangeState;
+    use crate::step::round_finished::RoundFinishedState;
+    use crate::step::round_in_progress::RoundInProgressState;
+    use proptest::prelude::*;
+    use proptest::sample::Index;
 
     fn get_players() -> Vec<String> {
         vec!["1".to_string(), "2".to_string(), "3".to_string()]
@@ -204,4 +218,160 @@ mod tests {
         assert!(!round_finished_step.should_switch());
         assert!(round_finished_step.game_finished(10));
     }
+
+    fn sorted(mut cards: Vec<Card>) -> Vec<Card> {
+        cards.sort_by_key(|card| (card.suit, card.value));
+        cards
+    }
+
+    fn check_card_exchange(step: &GameStep<CardExchangeState>) {
+        let hands = step.player_decks.values().flatten().copied().collect();
+        assert_eq!(sorted(hands), get_full_deck_sorted(step.players.len()));
+    }
+
+    fn check_round_in_progress(step: &GameStep<RoundInProgressState>) {
+        let number_of_players = step.players.len();
+        let tricks = &step.history.rounds.last().unwrap().tricks;
+        let (completed_tricks, unfinished_tricks): (Vec<_>, Vec<_>) = tricks
+            .iter()
+            .partition(|trick| trick.plays.len() == number_of_players);
+        let taken: Vec<Card> = completed_tricks
+            .iter()
+            .flat_map(|trick| trick.plays.iter().map(|(_, card)| *card))
+            .collect();
+        let table: Vec<Card> = step.state.cards_on_table.values().copied().collect();
+        let unfinished: Vec<Card> = unfinished_tricks
+            .iter()
+            .flat_map(|trick| trick.plays.iter().map(|(_, card)| *card))
+            .collect();
+        assert_eq!(sorted(unfinished), sorted(table.clone()));
+
+        // every card is in exactly one of the hands, on the table or in a taken trick
+        let cards = step
+            .player_decks
+            .values()
+            .flatten()
+            .copied()
+            .chain(table)
+            .chain(taken.iter().copied())
+            .collect();
+        assert_eq!(sorted(cards), get_full_deck_sorted(number_of_players));
+
+        let taken_score: usize = taken.iter().map(|card| card.score).sum();
+        assert_eq!(step.state.round_score.values().sum::<usize>(), taken_score);
+
+        if !step.should_switch() {
+            assert!(!step.player_decks[&step.state.current_player].is_empty());
+        }
+    }
+
+    fn check_round_finished(
+        step: &GameStep<RoundFinishedState>,
+        previous_scores: &HashMap<String, usize>,
+        round_score: &HashMap<String, usize>,
+    ) {
+        let all_scorer = round_score
+            .iter()
+            .find(|(_, &score)| score == ALL_POINTS)
+            .map(|(player, _)| player);
+
+        for player in &step.players {
+            let gained = step.scores[player] - previous_scores.get(player).unwrap_or(&0);
+            let expected = match all_scorer {
+                Some(all_scorer) if all_scorer == player => 0,
+                Some(_) => ALL_POINTS,
+                None => round_score.get(player).copied().unwrap_or(0),
+            };
+            assert_eq!(gained, expected);
+        }
+        if all_scorer.is_none() {
+            assert_eq!(round_score.values().sum::<usize>(), ALL_POINTS);
+        }
+    }
+
+    fn get_full_deck_sorted(number_of_players: usize) -> Vec<Card> {
+        sorted(get_full_deck(number_of_players))
+    }
+
+    // plays a whole game with moves picked by `choices`, checking the invariants after every move
+    fn play_random_game(number_of_players: usize, seed: u64, choices: &[Index]) {
+        let players: Vec<String> = (1..=number_of_players).map(|i| i.to_string()).collect();
+        let mut choices = choices.iter().cycle();
+        let mut exchange_step = GameStep::initialize_from_seed(&players, seed);
+
+        for round in 1..=30 {
+            // card exchange
+            assert_eq!(exchange_step.history.rounds.len(), round);
+            check_card_exchange(&exchange_step);
+            for player in &players {
+                let mut cards: Vec<Card> =
+                    sorted(exchange_step.player_decks[player].iter().copied().collect());
+                let cards_to_exchange = (0..3)
+                    .map(|_| cards.remove(choices.next().unwrap().index(cards.len())))
+                    .collect();
+                assert!(!exchange_step.should_switch());
+                exchange_step
+                    .handle_payload(&CardExchangePayload { cards_to_exchange }, player)
+                    .unwrap();
+                check_card_exchange(&exchange_step);
+            }
+            assert!(exchange_step.should_switch());
+
+            // round in progress
+            let previous_scores = exchange_step.scores.clone();
+            let mut round_step = exchange_step.to_round_in_progress();
+            check_round_in_progress(&round_step);
+            while !round_step.should_switch() {
+                let player = round_step.state.current_player.clone();
+                let legal_cards = round_step.legal_cards(&player);
+                assert!(!legal_cards.is_empty());
+                let card = legal_cards[choices.next().unwrap().index(legal_cards.len())];
+                round_step
+                    .handle_payload(&PlaceCardPayload { card }, &player)
+                    .unwrap();
+                check_round_in_progress(&round_step);
+            }
+
+            // round finished
+            let round_score = round_step.state.round_score.clone();
+            let mut finished_step = round_step.to_round_finished();
+            check_round_finished(&finished_step, &previous_scores, &round_score);
+            for player in &players {
+                let ready = choices.next().unwrap().index(2) == 0;
+                finished_step.handle_payload(&ClaimReadinessPayload { ready }, player);
+            }
+            let everyone_ready = finished_step
+                .state
+                .players_ready
+                .values()
+                .all(|&ready| ready);
+            assert_eq!(finished_step.should_switch(), everyone_ready);
+            for player in &players {
+                finished_step.handle_payload(&ClaimReadinessPayload { ready: true }, player);
+            }
+            assert!(finished_step.should_switch());
+
+            if finished_step.game_finished(100) {
+                assert_eq!(finished_step.history.replay(), Ok(()));
+                return;
+            }
+            exchange_step =
+                finished_step.to_card_exchange_from_seed(seed.wrapping_add(round as u64));
+        }
+
+        panic!("Game should be finished after 30 rounds");
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn random_legal_play_keeps_invariants(
+            number_of_players in 3..=4usize,
+            seed in any::<u64>(),
+            choices in prop::collection::vec(any::<Index>(), 1..64),
+        ) {
+            play_random_game(number_of_players, seed, &choices);
+        }
+    }
 }
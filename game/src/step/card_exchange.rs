@@ -1,21 +1,51 @@
 use crate::card::Card;
 use crate::card::CardSuit::Club;
 use crate::error::{GameError, GameResult};
+#[cfg(feature = "thread-rng")]
+use crate::helper::get_random_seed;
 use crate::helper::{
-    get_player_to_player_map, get_starting_player_decks, pick_player_with_starting_card,
+    get_player_to_player_map, get_seeded_player_decks, pick_player_with_starting_card,
 };
+use crate::history::GameHistory;
 use crate::payload::CardExchangePayload;
 use crate::step::round_in_progress::RoundInProgressState;
 use crate::step::GameStep;
 use std::collections::{HashMap, HashSet};
 
 impl GameStep<CardExchangeState> {
+    #[cfg(feature = "thread-rng")]
     pub fn initialize_from_players(players: &[String]) -> GameStep<CardExchangeState> {
+        GameStep::initialize_from_seed(players, get_random_seed())
+    }
+
+    pub fn initialize_from_seed(players: &[String], seed: u64) -> GameStep<CardExchangeState> {
+        let player_decks = get_seeded_player_decks(players, seed);
+        GameStep::initialize(players, player_decks, Some(seed))
+    }
+
+    /// Starts the round with the given hands, which are not checked against the deck.
+    pub fn initialize_from_decks(
+        players: &[String],
+        player_decks: HashMap<String, HashSet<Card>>,
+    ) -> GameStep<CardExchangeState> {
+        GameStep::initialize(players, player_decks, None)
+    }
+
+    pub(crate) fn initialize(
+        players: &[String],
+        player_decks: HashMap<String, HashSet<Card>>,
+        seed: Option<u64>,
+    ) -> GameStep<CardExchangeState> {
+        let mut history = GameHistory::new(players);
+        history.start_round(seed, &player_decks);
+
         GameStep {
             players: players.to_vec(),
-            player_to_player_map: get_player_to_player_map(&players),
+            player_to_player_map: get_player_to_player_map(players),
             scores: HashMap::new(),
-            player_decks: get_starting_player_decks(&players),
+            player_decks,
+            history,
+            house_rules: None,
             state: CardExchangeState::new(),
         }
     }
@@ -23,7 +53,7 @@ impl GameStep<CardExchangeState> {
     pub fn empty_from_players(players: &[String]) -> GameStep<CardExchangeState> {
         GameStep {
             players: players.to_vec(),
-            player_to_player_map: get_player_to_player_map(&players),
+            player_to_player_map: get_player_to_player_map(players),
             scores: HashMap::new(),
             player_decks: HashMap::from_iter(
                 players
@@ -32,6 +62,8 @@ impl GameStep<CardExchangeState> {
                     .map(|player| (player, HashSet::new()))
                     .collect::<HashMap<_, _>>(),
             ),
+            history: GameHistory::new(players),
+            house_rules: None,
             state: CardExchangeState::new(),
         }
     }
@@ -41,12 +73,7 @@ impl GameStep<CardExchangeState> {
         payload: &CardExchangePayload,
         player: &str,
     ) -> GameResult<()> {
-        if self.state.cards_to_exchange.get(player).is_some() {
-            Err(GameError(format!(
-                "Player {} has already declared cards for exchange",
-                player
-            )))?
-        }
+        self.validate_not_confirmed(player)?;
 
         if payload.cards_to_exchange.len() != 3 {
             Err(GameError(
@@ -61,7 +88,31 @@ impl GameStep<CardExchangeState> {
         Ok(())
     }
 
+    fn validate_not_confirmed(&self, player: &str) -> GameResult<()> {
+        if self.state.confirmed_players.contains(player) {
+            Err(GameError(format!(
+                "Player {} has already confirmed cards for exchange",
+                player
+            )))?
+        }
+
+        Ok(())
+    }
+
+    fn validate_declared(&self, player: &str) -> GameResult<()> {
+        if !self.state.cards_to_exchange.contains_key(player) {
+            Err(GameError(format!(
+                "Player {} has not declared cards for exchange",
+                player
+            )))?
+        }
+
+        Ok(())
+    }
+
+    // declaring cards again replaces the previous selection
     pub(crate) fn dispatch_payload(&mut self, payload: &CardExchangePayload, player: &str) {
+        self.history.record_pass(player, &payload.cards_to_exchange);
         self.state
             .cards_to_exchange
             .insert(player.to_string(), payload.cards_to_exchange.clone());
@@ -72,16 +123,39 @@ impl GameStep<CardExchangeState> {
         payload: &CardExchangePayload,
         player: &str,
     ) -> Result<(), GameError> {
-        self.validate_payload(&payload, player)?;
-        self.dispatch_payload(&payload, player);
+        self.validate_payload(payload, player)?;
+        self.dispatch_payload(payload, player);
 
         Ok(())
     }
 
+    /// Cancels the selection, which can be declared again until it is confirmed.
+    pub fn withdraw_exchange_cards(&mut self, player: &str) -> GameResult<()> {
+        self.validate_not_confirmed(player)?;
+        self.validate_declared(player)?;
+
+        self.state.cards_to_exchange.remove(player);
+        self.history.remove_pass(player);
+        Ok(())
+    }
+
+    /// Locks the selection, so it cannot be changed anymore.
+    pub fn confirm_exchange_cards(&mut self, player: &str) -> GameResult<()> {
+        self.validate_not_confirmed(player)?;
+        self.validate_declared(player)?;
+
+        self.state.confirmed_players.insert(player.to_string());
+        Ok(())
+    }
+
     pub fn should_switch(&self) -> bool {
         self.players.len() == self.state.cards_to_exchange.len()
     }
 
+    pub fn is_confirmed_by_everyone(&self) -> bool {
+        self.players.len() == self.state.confirmed_players.len()
+    }
+
     pub fn to_round_in_progress(mut self) -> GameStep<RoundInProgressState> {
         self.exchange_cards_between_players();
 
@@ -90,12 +164,15 @@ impl GameStep<CardExchangeState> {
             .get_mut(&player)
             .unwrap()
             .remove(&starting_card);
+        self.history
+            .record_play(&player, starting_card, self.players.len());
 
         let state = RoundInProgressState {
             current_player: self.player_to_player_map.get(&player).unwrap().to_string(),
             table_suit: Some(Club),
             cards_on_table: HashMap::from([(player, starting_card)]),
             round_score: HashMap::new(),
+            trick_winners: Vec::new(),
         };
 
         GameStep {
@@ -103,6 +180,8 @@ impl GameStep<CardExchangeState> {
             player_to_player_map: self.player_to_player_map,
             scores: self.scores,
             player_decks: self.player_decks,
+            history: self.history,
+            house_rules: self.house_rules,
             state,
         }
     }
@@ -112,10 +191,7 @@ impl GameStep<CardExchangeState> {
         for (from_player, to_player) in &self.player_to_player_map {
             for card in &self.state.cards_to_exchange[from_player] {
                 player_decks.get_mut(from_player).unwrap().remove(card);
-                player_decks
-                    .get_mut(to_player)
-                    .unwrap()
-                    .insert(card.clone());
+                player_decks.get_mut(to_player).unwrap().insert(*card);
             }
         }
     }
@@ -124,17 +200,26 @@ impl GameStep<CardExchangeState> {
 #[derive(Debug, PartialEq, Clone)]
 pub struct CardExchangeState {
     pub cards_to_exchange: HashMap<String, HashSet<Card>>,
+    pub confirmed_players: HashSet<String>,
+}
+
+impl Default for CardExchangeState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CardExchangeState {
     pub fn new() -> CardExchangeState {
         CardExchangeState {
             cards_to_exchange: HashMap::new(),
+            confirmed_players: HashSet::new(),
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::ptr_arg, clippy::useless_conversion)]
 mod tests {
     use super::*;
     use crate::card::CardSuit::Spade;
@@ -198,7 +283,7 @@ mod tests {
     }
 
     #[test]
-    fn validate_payload_returns_error_when_player_already_placed_cards_for_exchange() {
+    fn validate_payload_returns_error_when_player_already_confirmed_cards_for_exchange() {
         let players = get_players();
         let mut step = GameStep::empty_from_players(&players);
         let cards = HashSet::from([
@@ -210,6 +295,7 @@ mod tests {
         step.state
             .cards_to_exchange
             .insert("1".to_string(), cards.clone());
+        step.state.confirmed_players.insert("1".to_string());
         let payload = CardExchangePayload {
             cards_to_exchange: cards.clone(),
         };
@@ -217,11 +303,81 @@ mod tests {
         assert_eq!(
             step.validate_payload(&payload, &players[0]),
             Err(GameError(
-                "Player 1 has already declared cards for exchange".to_string()
+                "Player 1 has already confirmed cards for exchange".to_string()
             ))
         )
     }
 
+    #[test]
+    fn change_and_withdraw_cards_for_exchange() {
+        let players = get_players();
+        let vec_of_cards = get_decks_of_three_cards();
+        let mut step = GameStep::empty_from_players(&players);
+        insert_decks_of_cards(&mut step, &vec_of_cards);
+        step.player_decks
+            .get_mut("1")
+            .unwrap()
+            .insert(Card::new(Spade, 11).unwrap());
+
+        let cards = HashSet::from([
+            Card::new(Spade, 2).unwrap(),
+            Card::new(Spade, 4).unwrap(),
+            Card::new(Spade, 11).unwrap(),
+        ]);
+        step.handle_payload(
+            &CardExchangePayload {
+                cards_to_exchange: vec_of_cards[0].clone(),
+            },
+            "1",
+        )
+        .unwrap();
+        step.handle_payload(
+            &CardExchangePayload {
+                cards_to_exchange: cards.clone(),
+            },
+            "1",
+        )
+        .unwrap();
+        assert_eq!(step.state.cards_to_exchange["1"], cards);
+        assert_eq!(step.history.rounds[0].passes["1"], cards);
+
+        step.withdraw_exchange_cards("1").unwrap();
+        assert!(step.state.cards_to_exchange.is_empty());
+        assert!(step.history.rounds[0].passes.is_empty());
+        assert_eq!(
+            step.withdraw_exchange_cards("1"),
+            Err(GameError(
+                "Player 1 has not declared cards for exchange".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn confirmed_cards_cannot_be_withdrawn() {
+        let players = get_players();
+        let vec_of_cards = get_decks_of_three_cards();
+        let mut step = GameStep::empty_from_players(&players);
+        insert_decks_of_cards(&mut step, &vec_of_cards);
+
+        assert!(step.confirm_exchange_cards("1").is_err());
+        step.handle_payload(
+            &CardExchangePayload {
+                cards_to_exchange: vec_of_cards[0].clone(),
+            },
+            "1",
+        )
+        .unwrap();
+        step.confirm_exchange_cards("1").unwrap();
+
+        assert_eq!(
+            step.withdraw_exchange_cards("1"),
+            Err(GameError(
+                "Player 1 has already confirmed cards for exchange".to_string()
+            ))
+        );
+        assert!(!step.is_confirmed_by_everyone());
+    }
+
     #[test]
     fn player_1_dispatch_payload() {
         let players = get_players();
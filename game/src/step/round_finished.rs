@@ -1,4 +1,6 @@
-use crate::helper::get_starting_player_decks;
+#[cfg(feature = "thread-rng")]
+use crate::helper::get_random_seed;
+use crate::helper::get_seeded_player_decks;
 use crate::payload::ClaimReadinessPayload;
 use crate::step::card_exchange::CardExchangeState;
 use crate::step::GameStep;
@@ -15,27 +17,38 @@ impl GameStep<RoundFinishedState> {
         self.state
             .players_ready
             .values()
-            .map(|&ready| ready == true)
-            .len()
+            .filter(|&&ready| ready)
+            .count()
             == self.players.len()
     }
 
     pub fn game_finished(&self, max_score: usize) -> bool {
-        self.scores
+        *self
+            .scores
             .iter()
             .max_by_key(|(_, &score)| score)
             .unwrap()
             .1
-            .clone()
             >= max_score
     }
 
+    #[cfg(feature = "thread-rng")]
     pub fn to_card_exchange(self) -> GameStep<CardExchangeState> {
+        let seed = get_random_seed();
+        self.to_card_exchange_from_seed(seed)
+    }
+
+    pub fn to_card_exchange_from_seed(mut self, seed: u64) -> GameStep<CardExchangeState> {
+        let player_decks = get_seeded_player_decks(&self.players, seed);
+        self.history.start_round(Some(seed), &player_decks);
+
         GameStep {
-            players: self.players.clone(),
+            players: self.players,
             player_to_player_map: self.player_to_player_map,
             scores: self.scores,
-            player_decks: get_starting_player_decks(&self.players),
+            player_decks,
+            history: self.history,
+            house_rules: self.house_rules,
             state: CardExchangeState::new(),
         }
     }
@@ -47,9 +60,11 @@ pub struct RoundFinishedState {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use crate::helper::get_player_to_player_map;
+    use crate::history::GameHistory;
     use std::collections::HashMap;
 
     fn get_step() -> GameStep<RoundFinishedState> {
@@ -59,6 +74,8 @@ mod tests {
             player_to_player_map: get_player_to_player_map(&players),
             scores: HashMap::new(),
             player_decks: HashMap::new(),
+            history: GameHistory::new(&players),
+            house_rules: None,
             state: RoundFinishedState {
                 players_ready: HashMap::new(),
             },
@@ -85,6 +102,21 @@ mod tests {
         assert_eq!(step.state.players_ready["1"], false);
     }
 
+    #[test]
+    fn should_not_switch_when_one_of_players_is_not_ready() {
+        let mut step = get_step();
+        step.state.players_ready = HashMap::from([
+            ("1".to_string(), true),
+            ("2".to_string(), true),
+            ("3".to_string(), false),
+        ]);
+
+        assert!(!step.should_switch());
+
+        step.state.players_ready.insert("3".to_string(), true);
+        assert!(step.should_switch());
+    }
+
     #[test]
     fn game_finished_when_one_of_players_has_score_equal_or_more_than_100() {
         let mut step = get_step();
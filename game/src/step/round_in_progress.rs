@@ -4,7 +4,7 @@ use crate::error::{GameError, GameResult};
 use crate::helper::{check_if_player_has_only_one_suit_remaining, check_if_player_has_suit};
 use crate::payload::PlaceCardPayload;
 use crate::step::round_finished::RoundFinishedState;
-use crate::step::GameStep;
+use crate::step::{GameStep, ALL_POINTS};
 use std::collections::HashMap;
 
 impl GameStep<RoundInProgressState> {
@@ -54,9 +54,11 @@ impl GameStep<RoundInProgressState> {
             .get_mut(current_player)
             .unwrap()
             .remove(card);
+        self.history
+            .record_play(current_player, *card, self.players.len());
         self.state
             .cards_on_table
-            .insert(current_player.clone(), card.clone());
+            .insert(current_player.clone(), *card);
     }
 
     fn get_scoring_player(&self) -> String {
@@ -73,13 +75,17 @@ impl GameStep<RoundInProgressState> {
     fn get_total_score_of_cards_on_table(&self) -> usize {
         self.state
             .cards_on_table
-            .iter()
-            .map(|(_, card)| card.score)
+            .values()
+            .map(|card| card.score)
             .sum()
     }
 
     fn prepare_table_for_next_turn(&mut self) {
-        let scoring_player = self.get_scoring_player();
+        let scoring_player = self
+            .house_rules
+            .as_ref()
+            .and_then(|rules| rules.trick_winner(self, &self.state.current_player))
+            .unwrap_or_else(|| self.get_scoring_player());
         let score = self.get_total_score_of_cards_on_table();
 
         *self.scores.entry(scoring_player.clone()).or_insert(0) += score;
@@ -91,6 +97,7 @@ impl GameStep<RoundInProgressState> {
 
         self.state.cards_on_table = HashMap::new();
         self.state.table_suit = None;
+        self.state.trick_winners.push(scoring_player.clone());
         self.state.current_player = scoring_player;
     }
 
@@ -99,18 +106,60 @@ impl GameStep<RoundInProgressState> {
         payload: &PlaceCardPayload,
         player: &str,
     ) -> GameResult<()> {
+        self.validate_standard_rules(payload, player)?;
+
+        let Some(rules) = &self.house_rules else {
+            return Ok(());
+        };
+        let Err(error) = rules.validate_card(self, player, payload.card) else {
+            return Ok(());
+        };
+        // a script forbidding every card would stall the round, the standard rules apply then
+        let allowed_cards = self.get_allowed_cards(player);
+        match allowed_cards.is_empty() {
+            true => Ok(()),
+            false => Err(error),
+        }
+    }
+
+    fn validate_standard_rules(&self, payload: &PlaceCardPayload, player: &str) -> GameResult<()> {
         self.validate_current_player(player)?;
         self.validate_player_has_card(&payload.card, &self.state.current_player)?;
 
         match self.state.table_suit {
             Some(table_suit) => self.validate_placed_suit(payload.card.suit, table_suit, player),
-            None => {
-                if payload.card.suit == Heart {
-                    self.validate_only_heart_left(player)?
-                }
+            None => match payload.card.suit {
+                Heart => self.validate_only_heart_left(player),
+                _ => Ok(()),
+            },
+        }
+    }
 
-                Ok(())
-            }
+    fn get_standard_legal_cards(&self, player: &str) -> Vec<Card> {
+        let mut cards: Vec<Card> = self.player_decks[player]
+            .iter()
+            .copied()
+            .filter(|&card| {
+                self.validate_standard_rules(&PlaceCardPayload { card }, player)
+                    .is_ok()
+            })
+            .collect();
+        cards.sort_by_key(|card| (card.suit, card.value));
+        cards
+    }
+
+    fn get_allowed_cards(&self, player: &str) -> Vec<Card> {
+        let mut cards = self.get_standard_legal_cards(player);
+        if let Some(rules) = &self.house_rules {
+            cards.retain(|&card| rules.validate_card(self, player, card).is_ok());
+        }
+        cards
+    }
+
+    pub fn legal_cards(&self, player: &str) -> Vec<Card> {
+        match self.get_allowed_cards(player) {
+            cards if cards.is_empty() => self.get_standard_legal_cards(player),
+            cards => cards,
         }
     }
 
@@ -133,38 +182,115 @@ impl GameStep<RoundInProgressState> {
         payload: &PlaceCardPayload,
         player: &str,
     ) -> Result<(), GameError> {
-        self.validate_payload(&payload, player)?;
-        self.dispatch_payload(&payload, player);
+        self.validate_payload(payload, player)?;
+        self.dispatch_payload(payload, player);
 
         Ok(())
     }
 
+    /// Returns the last card to the hand of the player who placed it.
+    /// A card which completed a trick can only be taken back with `completed_tricks`,
+    /// the points of that trick are then taken away from its winner.
+    pub fn take_back_last_card(
+        &mut self,
+        player: &str,
+        completed_tricks: bool,
+    ) -> GameResult<Card> {
+        let (last_player, card) = self
+            .history
+            .last_play()
+            .cloned()
+            .ok_or(GameError("There is no card to take back".to_string()))?;
+
+        if last_player != player {
+            Err(GameError(format!(
+                "Only player {} can take back the last card",
+                last_player
+            )))?
+        }
+
+        let round = self.history.rounds.last().unwrap();
+        if round.tricks.len() == 1 && round.tricks[0].plays.len() == 1 {
+            Err(GameError("Starting card cannot be taken back".to_string()))?
+        }
+
+        if self.state.cards_on_table.is_empty() {
+            if !completed_tricks {
+                Err(GameError(
+                    "Cannot take back a card from a completed trick".to_string(),
+                ))?
+            }
+
+            let trick = round.tricks.last().unwrap();
+            let winner = self.state.trick_winners.pop().unwrap();
+            let score: usize = trick.plays.iter().map(|(_, card)| card.score).sum();
+            *self.scores.get_mut(&winner).unwrap() -= score;
+            *self.state.round_score.get_mut(&winner).unwrap() -= score;
+
+            self.state.table_suit = Some(trick.plays[0].1.suit);
+            self.state.cards_on_table = trick.plays[..trick.plays.len() - 1]
+                .iter()
+                .cloned()
+                .collect();
+        } else {
+            self.state.cards_on_table.remove(player);
+            if self.state.cards_on_table.is_empty() {
+                self.state.table_suit = None;
+            }
+        }
+
+        self.history.remove_last_play();
+        self.player_decks.get_mut(player).unwrap().insert(card);
+        self.state.current_player = player.to_string();
+
+        Ok(card)
+    }
+
     pub fn should_switch(&self) -> bool {
         self.player_decks.iter().all(|(_, cards)| cards.is_empty())
     }
 
     pub fn to_round_finished(mut self) -> GameStep<RoundFinishedState> {
-        if let Some((all_scorer, _)) = self
-            .state
-            .round_score
-            .iter()
-            .find(|(_, &score)| score == 43)
-        {
+        // players who have not taken any trick yet are missing from scores
+        for player in &self.players {
+            self.scores.entry(player.clone()).or_insert(0);
+        }
+
+        if let Some(rules) = &self.house_rules {
+            let round_scores: Vec<(String, usize, usize)> = self
+                .players
+                .iter()
+                .map(|player| {
+                    let score = self.state.round_score.get(player).copied().unwrap_or(0);
+                    let house_score = rules.round_score(&self, player, score).unwrap_or(score);
+                    (player.clone(), score, house_score)
+                })
+                .collect();
+            for (player, score, house_score) in round_scores {
+                let total = self.scores.get_mut(&player).unwrap();
+                *total = (*total + house_score).saturating_sub(score);
+                self.state.round_score.insert(player, house_score);
+            }
+        }
+
+        if let Some(all_scorer) = self.state.moon_shooter() {
             for (player, score) in self.scores.iter_mut() {
                 match player == all_scorer {
-                    true => *score -= 43,
-                    false => *score += 43,
+                    true => *score -= ALL_POINTS,
+                    false => *score += ALL_POINTS,
                 }
             }
         }
 
-        println!("{:?}", &self.scores);
+        self.history.record_scores(&self.scores);
 
         GameStep {
             players: self.players,
             player_to_player_map: self.player_to_player_map,
             scores: self.scores,
             player_decks: self.player_decks,
+            history: self.history,
+            house_rules: self.house_rules,
             state: RoundFinishedState {
                 players_ready: HashMap::new(),
             },
@@ -178,13 +304,52 @@ pub struct RoundInProgressState {
     pub table_suit: Option<CardSuit>,
     pub cards_on_table: HashMap<String, Card>,
     pub round_score: HashMap<String, usize>,
+    /// Winners of the completed tricks of the round, in order.
+    pub trick_winners: Vec<String>,
 }
 
+impl RoundInProgressState {
+    /// Player who took all the points of the round so far.
+    pub fn moon_shooter(&self) -> Option<&String> {
+        self.round_score
+            .iter()
+            .find(|(_, &score)| score == ALL_POINTS)
+            .map(|(player, _)| player)
+    }
+}
+
+/// Legal cards of a hand, for views built by tests.
 #[cfg(test)]
+pub(crate) fn get_legal_cards_of_hand(
+    hand: &std::collections::HashSet<Card>,
+    table_suit: Option<CardSuit>,
+) -> Vec<Card> {
+    let player = "1".to_string();
+    let step = GameStep {
+        players: vec![player.clone()],
+        player_to_player_map: HashMap::from([(player.clone(), player.clone())]),
+        scores: HashMap::new(),
+        player_decks: HashMap::from([(player.clone(), hand.clone())]),
+        history: crate::history::GameHistory::new(std::slice::from_ref(&player)),
+        house_rules: None,
+        state: RoundInProgressState {
+            current_player: player.clone(),
+            table_suit,
+            cards_on_table: HashMap::new(),
+            round_score: HashMap::new(),
+            trick_winners: Vec::new(),
+        },
+    };
+    step.legal_cards(&player)
+}
+
+#[cfg(test)]
+#[allow(clippy::clone_on_copy, clippy::needless_borrow)]
 mod tests {
     use super::*;
     use crate::card::CardSuit::{Diamond, Spade};
     use crate::helper::get_player_to_player_map;
+    use crate::history::GameHistory;
     use std::collections::HashSet;
 
     fn get_players() -> Vec<String> {
@@ -203,11 +368,14 @@ mod tests {
                     .map(|player| (player, HashSet::new()))
                     .collect::<HashMap<_, _>>(),
             ),
+            history: GameHistory::new(players),
+            house_rules: None,
             state: RoundInProgressState {
                 current_player: players[0].clone(),
                 table_suit: None,
                 cards_on_table: HashMap::new(),
                 round_score: HashMap::new(),
+                trick_winners: Vec::new(),
             },
         }
     }
@@ -336,6 +504,52 @@ mod tests {
         assert!(step.validate_payload(&payload, &players[0]).is_ok());
     }
 
+    #[test]
+    fn legal_cards_when_player_has_to_follow_suit() {
+        let players = get_players();
+        let mut step = get_step_from_players(&players);
+        step.state.table_suit = Some(Spade);
+        step.player_decks.insert(
+            players[0].clone(),
+            HashSet::from([
+                Card::new(Spade, 9).unwrap(),
+                Card::new(Spade, 3).unwrap(),
+                Card::new(Diamond, 2).unwrap(),
+            ]),
+        );
+
+        assert_eq!(
+            step.legal_cards(&players[0]),
+            vec![Card::new(Spade, 3).unwrap(), Card::new(Spade, 9).unwrap()]
+        );
+        assert!(step.legal_cards(&players[1]).is_empty());
+    }
+
+    #[test]
+    fn legal_cards_of_hand_lead_with_hearts_only_when_nothing_else_is_left() {
+        let cards = HashSet::from([
+            Card::new(Heart, 2).unwrap(),
+            Card::new(Spade, 12).unwrap(),
+            Card::new(Diamond, 4).unwrap(),
+        ]);
+
+        assert_eq!(
+            get_legal_cards_of_hand(&cards, Some(CardSuit::Club)).len(),
+            3
+        );
+        assert_eq!(
+            get_legal_cards_of_hand(&cards, None),
+            vec![
+                Card::new(Spade, 12).unwrap(),
+                Card::new(Diamond, 4).unwrap()
+            ]
+        );
+        assert_eq!(
+            get_legal_cards_of_hand(&HashSet::from([Card::new(Heart, 2).unwrap()]), None),
+            vec![Card::new(Heart, 2).unwrap()]
+        );
+    }
+
     #[test]
     fn dispatch_payload_when_decks_are_left_empty() {
         let players = get_players();
@@ -398,4 +612,93 @@ mod tests {
         assert_eq!(round_finished_step.scores["2"], 40);
         assert_eq!(round_finished_step.scores["3"], 0);
     }
+
+    #[test]
+    fn to_round_finished_when_all_scorer_is_the_only_player_with_score() {
+        let players = get_players();
+        let mut step = get_step_from_players(&players);
+        step.scores.insert("1".to_string(), 43);
+        step.state.round_score.insert("1".to_string(), 43);
+
+        let round_finished_step = step.to_round_finished();
+
+        assert_eq!(round_finished_step.scores["1"], 0);
+        assert_eq!(round_finished_step.scores["2"], 43);
+        assert_eq!(round_finished_step.scores["3"], 43);
+    }
+
+    fn get_step_with_trick_in_progress() -> GameStep<RoundInProgressState> {
+        let players = get_players();
+        let mut step = get_step_from_players(&players);
+        for (player, cards) in [
+            ("1", ["2S", "5S"]),
+            ("2", ["QS", "6S"]),
+            ("3", ["4S", "7S"]),
+        ] {
+            let deck = step.player_decks.get_mut(player).unwrap();
+            deck.extend(cards.map(|card| card.parse::<Card>().unwrap()));
+        }
+
+        for (player, card) in [("1", "2S"), ("2", "QS")] {
+            let payload = PlaceCardPayload {
+                card: card.parse().unwrap(),
+            };
+            step.handle_payload(&payload, player).unwrap();
+        }
+        step
+    }
+
+    #[test]
+    fn take_back_last_card_within_trick() {
+        let mut step = get_step_with_trick_in_progress();
+
+        assert_eq!(
+            step.take_back_last_card("1", false),
+            Err(GameError(
+                "Only player 2 can take back the last card".to_string()
+            ))
+        );
+        assert_eq!(
+            step.take_back_last_card("2", false),
+            Ok("QS".parse().unwrap())
+        );
+        assert_eq!(step.state.current_player, "2");
+        assert_eq!(step.state.cards_on_table.len(), 1);
+        assert!(step.player_decks["2"].contains(&"QS".parse().unwrap()));
+        assert_eq!(step.history.rounds[0].tricks[0].plays.len(), 1);
+
+        assert_eq!(
+            step.take_back_last_card("1", false),
+            Err(GameError("Starting card cannot be taken back".to_string()))
+        );
+    }
+
+    #[test]
+    fn take_back_card_which_completed_trick() {
+        let mut step = get_step_with_trick_in_progress();
+        let payload = PlaceCardPayload {
+            card: "4S".parse().unwrap(),
+        };
+        step.handle_payload(&payload, "3").unwrap();
+        assert_eq!(step.scores["2"], 13);
+
+        assert_eq!(
+            step.take_back_last_card("3", false),
+            Err(GameError(
+                "Cannot take back a card from a completed trick".to_string()
+            ))
+        );
+        assert!(step.take_back_last_card("3", true).is_ok());
+        assert_eq!(step.scores["2"], 0);
+        assert_eq!(step.state.round_score["2"], 0);
+        assert_eq!(step.state.current_player, "3");
+        assert_eq!(step.state.table_suit, Some(Spade));
+        assert_eq!(
+            step.state.cards_on_table,
+            HashMap::from([
+                ("1".to_string(), "2S".parse().unwrap()),
+                ("2".to_string(), "QS".parse().unwrap())
+            ])
+        );
+    }
 }
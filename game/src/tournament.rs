@@ -0,0 +1,249 @@
+use crate::error::{GameError, GameResult};
+use crate::game::{EndCondition, Game, GameSettings};
+use crate::history::GameHistory;
+use serde::Serialize;
+use std::collections::HashMap;
+use ts_rs::TS;
+
+/// Duplicate format, every table plays the same sequence of deals and players are compared
+/// with those who held the same cards at the other tables.
+///
+/// Hands are dealt by seat, the seats of every table are rotated by its index, so the first
+/// entrant of a table does not always sit first.
+#[derive(Debug, Clone)]
+pub struct DuplicateTournament {
+    /// Players of every table in seating order.
+    pub tables: Vec<Vec<String>>,
+    pub settings: GameSettings,
+    // points taken in every finished deal, by table
+    points: Vec<Vec<HashMap<String, usize>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+pub struct TournamentStanding {
+    pub player: String,
+    /// Points fewer than taken by the players who held the same cards at other tables,
+    /// summed over all deals.
+    #[ts(type = "number")]
+    pub score: i64,
+}
+
+impl DuplicateTournament {
+    /// `settings` apply to all tables, with rounds limited to the number of deals.
+    pub fn new(
+        entrants: &[String],
+        table_size: usize,
+        settings: GameSettings,
+        deal_seeds: &[u64],
+    ) -> GameResult<DuplicateTournament> {
+        let is_seated = (3..=4).contains(&table_size)
+            && entrants.chunks_exact(table_size).remainder().is_empty();
+        if !is_seated {
+            Err(GameError(format!(
+                "Cannot seat {} players at tables of {}",
+                entrants.len(),
+                table_size
+            )))?
+        }
+        if deal_seeds.is_empty() {
+            Err(GameError("Tournament needs at least one deal".to_string()))?
+        }
+
+        let tables = entrants
+            .chunks(table_size)
+            .enumerate()
+            .map(|(index, players)| {
+                let mut players = players.to_vec();
+                players.rotate_left(index % table_size);
+                players
+            })
+            .collect::<Vec<_>>();
+
+        Ok(DuplicateTournament {
+            points: vec![Vec::new(); tables.len()],
+            tables,
+            settings: GameSettings {
+                end_condition: EndCondition::MaxRounds {
+                    max_rounds: deal_seeds.len(),
+                },
+                deal_seeds: deal_seeds.to_vec(),
+                ..settings
+            },
+        })
+    }
+
+    pub fn number_of_deals(&self) -> usize {
+        self.settings.deal_seeds.len()
+    }
+
+    pub fn start_table(&self, table: usize) -> GameResult<Game> {
+        let players = self
+            .tables
+            .get(table)
+            .ok_or(GameError(format!("Table {} does not exist", table)))?;
+        Game::from_seed(players, self.settings.clone(), self.settings.deal_seeds[0])
+    }
+
+    pub fn finished_deals(&self, table: usize) -> usize {
+        self.points[table].len()
+    }
+
+    /// Takes the points of the deals finished at the table so far.
+    pub fn record_table(&mut self, table: usize, history: &GameHistory) {
+        self.points[table] = history
+            .rounds
+            .iter()
+            .enumerate()
+            .take(self.number_of_deals())
+            .filter(|(_, round)| !round.scores.is_empty())
+            .map(|(index, round)| {
+                let scores_before = history.scores_before_round(index);
+                round
+                    .scores
+                    .iter()
+                    .map(|(player, score)| {
+                        let before = scores_before.get(player).copied().unwrap_or(0);
+                        (player.clone(), score.saturating_sub(before))
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        (0..self.tables.len()).all(|table| self.finished_deals(table) == self.number_of_deals())
+    }
+
+    /// Best first, only deals finished at more than one table are compared.
+    pub fn standings(&self) -> Vec<TournamentStanding> {
+        let mut scores: HashMap<&str, i64> = HashMap::new();
+        for (table, players) in self.tables.iter().enumerate() {
+            for (seat, player) in players.iter().enumerate() {
+                let score = scores.entry(player).or_insert(0);
+                for (deal, points) in self.points[table].iter().enumerate() {
+                    for other_table in (0..self.tables.len()).filter(|&other| other != table) {
+                        let other_player = &self.tables[other_table][seat];
+                        if let Some(other_points) = self.points[other_table].get(deal) {
+                            *score += other_points[other_player] as i64 - points[player] as i64;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut standings: Vec<TournamentStanding> = self
+            .tables
+            .iter()
+            .flatten()
+            .map(|player| TournamentStanding {
+                player: player.clone(),
+                score: scores[player.as_str()],
+            })
+            .collect();
+        standings.sort_by_key(|standing| -standing.score);
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::SimpleBot;
+
+    fn get_entrants() -> Vec<String> {
+        (1..=6).map(|number| number.to_string()).collect()
+    }
+
+    fn get_tournament() -> DuplicateTournament {
+        DuplicateTournament::new(&get_entrants(), 3, GameSettings::default(), &[7, 8]).unwrap()
+    }
+
+    fn play_table(tournament: &DuplicateTournament, table: usize) -> Game {
+        let mut game = tournament.start_table(table).unwrap();
+        for player in &tournament.tables[table] {
+            game.replace_with_bot(player).unwrap();
+        }
+        game.play_bots(&SimpleBot);
+        game
+    }
+
+    #[test]
+    fn entrants_are_seated_with_rotation() {
+        let tournament = get_tournament();
+
+        assert_eq!(tournament.tables[0], vec!["1", "2", "3"]);
+        assert_eq!(tournament.tables[1], vec!["5", "6", "4"]);
+        assert!(
+            DuplicateTournament::new(&get_entrants(), 4, GameSettings::default(), &[7]).is_err()
+        );
+    }
+
+    #[test]
+    fn every_table_plays_the_same_deals() {
+        let tournament = get_tournament();
+        let first_game = play_table(&tournament, 0);
+        let second_game = play_table(&tournament, 1);
+
+        assert!(first_game.is_finished && second_game.is_finished);
+        for (first_round, second_round) in first_game
+            .history()
+            .rounds
+            .iter()
+            .zip(&second_game.history().rounds)
+        {
+            for seat in 0..3 {
+                let first_player = &tournament.tables[0][seat];
+                let second_player = &tournament.tables[1][seat];
+                assert_eq!(
+                    first_round.dealt_hands[first_player],
+                    second_round.dealt_hands[second_player]
+                );
+            }
+        }
+        assert_eq!(first_game.history().rounds.len(), 2);
+    }
+
+    #[test]
+    fn same_play_at_every_table_gives_equal_scores() {
+        let mut tournament = get_tournament();
+        for table in 0..2 {
+            assert!(!tournament.is_finished());
+            let game = play_table(&tournament, table);
+            tournament.record_table(table, game.history());
+        }
+
+        assert!(tournament.is_finished());
+        assert!(tournament
+            .standings()
+            .iter()
+            .all(|standing| standing.score == 0));
+    }
+
+    #[test]
+    fn players_are_compared_with_the_same_seat_at_other_tables() {
+        let mut tournament = get_tournament();
+        tournament.points = vec![
+            vec![HashMap::from([
+                ("1".to_string(), 10),
+                ("2".to_string(), 30),
+                ("3".to_string(), 3),
+            ])],
+            vec![HashMap::from([
+                ("5".to_string(), 20),
+                ("6".to_string(), 20),
+                ("4".to_string(), 3),
+            ])],
+        ];
+
+        let standings: Vec<(String, i64)> = tournament
+            .standings()
+            .into_iter()
+            .map(|standing| (standing.player, standing.score))
+            .collect();
+        assert_eq!(
+            &standings[..2],
+            &[("1".to_string(), 10), ("6".to_string(), 10)]
+        );
+        assert_eq!(standings[5], ("5".to_string(), -10));
+    }
+}
@@ -0,0 +1,521 @@
+//! Lessons for new players, a single scripted round against bots with explanations on the way.
+//!
+//! A lesson is a hand history of one round (see the `history` module) with a few more tags:
+//!
+//! ```text
+//! % black widow lesson
+//! [Lesson "Getting rid of the queen of spades"]
+//! [Student "You"]
+//! [Players "You" "West" "North" "East"]
+//! [Round "1"]
+//! [Deal "You" "QS 2C ..."]
+//! [Explain "The queen of spades is worth 13 points, pass it on."]
+//! [Pass "You" "QS AH KH"]
+//! [Trick "You 2C" "West 5C" "North KC" "East 3C"]
+//! [Explain "Play under the highest card of the suit, so someone else takes the trick."]
+//! [Trick "North 4D" "East 9D" "You 2D" "West 3D"]
+//! ```
+//!
+//! - `Lesson` holds the title, `Student` the seat of the player. All other seats are bots which
+//!   pass and play the scripted cards, the moves of the student are checked against the script.
+//! - `Explain` pauses the lesson until the student reads it. Before the first `Pass` tag it is
+//!   shown when the round starts, after `n` tricks it is shown once they are played, before
+//!   the student plays to the next one. Explanations after the last trick end the lesson.
+//! - `Score` tags are not needed.
+
+use crate::card::{Card, CardNotation};
+use crate::error::{GameError, GameResult};
+use crate::game::GameState::{CardExchange, RoundFinished, RoundInProgress};
+use crate::game::{EndCondition, Game, GameSettings};
+use crate::history::{parse_tag, GameHistory, Trick};
+use crate::payload::{CardExchangePayload, PlaceCardPayload};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lesson {
+    pub title: String,
+    pub student: String,
+    pub script: GameHistory,
+    /// In the order they are shown.
+    pub explanations: Vec<Explanation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub point: LessonPoint,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LessonPoint {
+    BeforePass,
+    /// After the given number of tricks, once cards are passed.
+    BeforeTrick(usize),
+}
+
+impl FromStr for Lesson {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> GameResult<Lesson> {
+        let mut title = None;
+        let mut student = None;
+        let mut explanations = Vec::new();
+        let mut has_passes = false;
+        let mut tricks = 0;
+        // lesson tags are left out as empty lines, so errors of the script keep line numbers
+        let mut script = String::new();
+
+        for (line_number, line) in s.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('%') {
+                script.push('\n');
+                continue;
+            }
+
+            let (name, values) = parse_tag(trimmed)
+                .map_err(|error| GameError(format!("Line {}: {}", line_number + 1, error)))?;
+            match (name.as_str(), values.as_slice()) {
+                ("Lesson", [value]) => title = Some(value.clone()),
+                ("Student", [value]) => student = Some(value.clone()),
+                ("Explain", [text]) => explanations.push(Explanation {
+                    point: match has_passes {
+                        false => LessonPoint::BeforePass,
+                        true => LessonPoint::BeforeTrick(tricks),
+                    },
+                    text: text.clone(),
+                }),
+                ("Lesson" | "Student" | "Explain", values) => Err(GameError(format!(
+                    "Line {}: Invalid tag {} with {} values",
+                    line_number + 1,
+                    name,
+                    values.len()
+                )))?,
+                (name, _) => {
+                    match name {
+                        "Pass" => has_passes = true,
+                        "Trick" => tricks += 1,
+                        _ => {}
+                    }
+                    script.push_str(line);
+                }
+            }
+            script.push('\n');
+        }
+
+        let script: GameHistory = script.parse()?;
+        let title = title.ok_or(GameError("Missing Lesson tag".to_string()))?;
+        let student = student.ok_or(GameError("Missing Student tag".to_string()))?;
+        if !script.players.contains(&student) {
+            Err(GameError(format!("Unknown student {}", student)))?
+        }
+        if script.rounds.len() != 1 {
+            Err(GameError("Lesson has to have a single round".to_string()))?
+        }
+        script.replay_round_plays(0, &script.rounds[0])?;
+
+        Ok(Lesson {
+            title,
+            student,
+            script,
+            explanations,
+        })
+    }
+}
+
+/// A lesson taken by a player, who sits in the seat of the student.
+#[derive(Debug, Clone)]
+pub struct Tutorial {
+    pub lesson: Lesson,
+    explanations_read: usize,
+}
+
+impl Tutorial {
+    pub fn new(lesson: &Lesson, player: &str) -> GameResult<Tutorial> {
+        if player != lesson.student && lesson.script.players.iter().any(|p| p == player) {
+            Err(GameError(format!(
+                "Player {} cannot take the lesson {}",
+                player, lesson.title
+            )))?
+        }
+
+        let lesson = Lesson {
+            title: lesson.title.clone(),
+            student: player.to_string(),
+            script: rename_player(&lesson.script, &lesson.student, player),
+            explanations: lesson.explanations.clone(),
+        };
+        Ok(Tutorial {
+            lesson,
+            explanations_read: 0,
+        })
+    }
+
+    pub fn player(&self) -> &str {
+        &self.lesson.student
+    }
+
+    /// Bots make their scripted moves up to the first move of the student. Their seats count
+    /// as left, so only the student is an active player, and as long as the script is played
+    /// after every move they never have a move left for `Game::play_bots`.
+    pub fn start_game(&self) -> GameResult<Game> {
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 1 },
+            ..GameSettings::default()
+        };
+        let round = &self.lesson.script.rounds[0];
+        let mut game = Game::from_deal(
+            &self.lesson.script.players,
+            settings,
+            round.dealt_hands.clone(),
+        )?;
+        for bot in self.bots() {
+            game.replace_with_bot(bot)?;
+        }
+        self.play_script(&mut game)?;
+        Ok(game)
+    }
+
+    /// Makes the scripted moves of the bots until the student has to move.
+    pub fn play_script(&self, game: &mut Game) -> GameResult<()> {
+        let round = &self.lesson.script.rounds[0];
+        loop {
+            let mut bots = self.bots();
+            let bot = match &game.state {
+                CardExchange(step) => {
+                    bots.find(|&bot| !step.state.cards_to_exchange.contains_key(bot))
+                }
+                RoundInProgress(step) => bots.find(|&bot| bot == &step.state.current_player),
+                RoundFinished(_) => None,
+            };
+            let Some(bot) = bot.cloned() else {
+                return Ok(());
+            };
+
+            match &game.state {
+                CardExchange(_) => {
+                    let payload = CardExchangePayload {
+                        cards_to_exchange: round.passes[&bot].clone(),
+                    };
+                    game.exchange_cards(&bot, &payload)?
+                }
+                _ => {
+                    let (_, card) = self
+                        .next_play(game)
+                        .ok_or(GameError("Lesson has no more moves".to_string()))?;
+                    game.place_card(&bot, &PlaceCardPayload { card: *card })?
+                }
+            }
+        }
+    }
+
+    /// Text the student has to read before moving on, if the lesson is paused.
+    pub fn explanation(&self, game: &Game) -> Option<&str> {
+        let explanation = self.lesson.explanations.get(self.explanations_read)?;
+        let is_due = match explanation.point {
+            LessonPoint::BeforePass => true,
+            LessonPoint::BeforeTrick(tricks) => {
+                !matches!(game.state, CardExchange(_)) && get_completed_tricks(game) >= tricks
+            }
+        };
+        is_due.then_some(explanation.text.as_str())
+    }
+
+    pub fn read_explanation(&mut self, game: &Game) -> GameResult<()> {
+        if self.explanation(game).is_none() {
+            Err(GameError("There is no explanation to read".to_string()))?
+        }
+        self.explanations_read += 1;
+        Ok(())
+    }
+
+    /// The round is played and every explanation was read.
+    pub fn is_finished(&self, game: &Game) -> bool {
+        game.is_finished && self.explanations_read == self.lesson.explanations.len()
+    }
+
+    pub fn check_exchange(&self, game: &Game, cards: &HashSet<Card>) -> GameResult<()> {
+        self.check_not_paused(game)?;
+        if self.lesson.script.rounds[0].passes.get(self.player()) != Some(cards) {
+            Err(GameError(
+                "These are not the cards to pass in this lesson, try again".to_string(),
+            ))?
+        }
+        Ok(())
+    }
+
+    /// Only checked on the turn of the student, other moves are left to the game.
+    pub fn check_card(&self, game: &Game, card: Card) -> GameResult<()> {
+        self.check_not_paused(game)?;
+        match self.next_play(game) {
+            Some((player, expected)) if player == self.player() && expected != &card => {
+                Err(GameError(format!(
+                    "{} is not the move of this lesson, try again",
+                    card.to_notation(CardNotation::Short)
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn bots(&self) -> impl Iterator<Item = &String> {
+        self.lesson
+            .script
+            .players
+            .iter()
+            .filter(|&player| player != self.player())
+    }
+
+    fn check_not_paused(&self, game: &Game) -> GameResult<()> {
+        match self.explanation(game) {
+            Some(_) => Err(GameError(
+                "Read the explanation before moving on".to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn next_play(&self, game: &Game) -> Option<&(String, Card)> {
+        let played = game.history().rounds.last().map_or(0, |round| {
+            round.tricks.iter().map(|trick| trick.plays.len()).sum()
+        });
+        self.lesson.script.rounds[0]
+            .tricks
+            .iter()
+            .flat_map(|trick| trick.plays.iter())
+            .nth(played)
+    }
+}
+
+fn get_completed_tricks(game: &Game) -> usize {
+    game.history().rounds.last().map_or(0, |round| {
+        round
+            .tricks
+            .iter()
+            .filter(|trick| trick.plays.len() == game.players.len())
+            .count()
+    })
+}
+
+fn rename_player(history: &GameHistory, from: &str, to: &str) -> GameHistory {
+    let rename = |player: &String| match player == from {
+        true => to.to_string(),
+        false => player.clone(),
+    };
+    let rename_keys = |map: &HashMap<String, HashSet<Card>>| {
+        map.iter()
+            .map(|(player, cards)| (rename(player), cards.clone()))
+            .collect()
+    };
+
+    let mut renamed = GameHistory::new(&history.players.iter().map(rename).collect::<Vec<_>>());
+    renamed.initial_scores = history
+        .initial_scores
+        .iter()
+        .map(|(player, score)| (rename(player), *score))
+        .collect();
+    for round in &history.rounds {
+        let mut round = round.clone();
+        round.dealt_hands = rename_keys(&round.dealt_hands);
+        round.passes = rename_keys(&round.passes);
+        round.scores = round
+            .scores
+            .iter()
+            .map(|(player, score)| (rename(player), *score))
+            .collect();
+        round.tricks = round
+            .tricks
+            .iter()
+            .map(|trick| Trick {
+                plays: trick
+                    .plays
+                    .iter()
+                    .map(|(player, card)| (rename(player), *card))
+                    .collect(),
+            })
+            .collect();
+        renamed.rounds.push(round);
+    }
+    renamed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::SimpleBot;
+
+    // a bot game with explanations before the pass and after the second trick
+    fn get_lesson_text() -> String {
+        let players = vec!["1".to_string(), "2".to_string(), "3".to_string()];
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 1 },
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&players, settings, 7).unwrap();
+        for player in &players {
+            game.replace_with_bot(player).unwrap();
+        }
+        game.play_bots(&SimpleBot);
+
+        let mut lines = vec![
+            "[Lesson \"Passing\"]".to_string(),
+            "[Student \"2\"]".to_string(),
+        ];
+        let mut tricks = 0;
+        for line in game.history().to_string().lines() {
+            if line.starts_with("[Pass \"1\"") {
+                lines.push("[Explain \"Pass your highest cards.\"]".to_string());
+            }
+            lines.push(line.to_string());
+            if line.starts_with("[Trick") {
+                tricks += 1;
+                if tricks == 2 {
+                    lines.push("[Explain \"Duck the trick.\"]".to_string());
+                }
+            }
+        }
+        lines.push("[Explain \"Well done.\"]".to_string());
+        lines.join("\n")
+    }
+
+    fn play_student_move(tutorial: &Tutorial, game: &mut Game) {
+        let round = &tutorial.lesson.script.rounds[0];
+        match &game.state {
+            CardExchange(_) => {
+                let cards_to_exchange = round.passes["alice"].clone();
+                tutorial.check_exchange(game, &cards_to_exchange).unwrap();
+                game.exchange_cards("alice", &CardExchangePayload { cards_to_exchange })
+            }
+            _ => {
+                let (_, card) = tutorial.next_play(game).unwrap().clone();
+                tutorial.check_card(game, card).unwrap();
+                game.place_card("alice", &PlaceCardPayload { card })
+            }
+        }
+        .unwrap();
+        tutorial.play_script(game).unwrap();
+    }
+
+    #[test]
+    fn bundled_lessons_are_valid() {
+        let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/../lessons");
+        for entry in std::fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            let text = std::fs::read_to_string(&path).unwrap();
+            if let Err(error) = text.parse::<Lesson>() {
+                panic!("{}: {}", path.display(), error);
+            }
+        }
+    }
+
+    #[test]
+    fn explanations_are_placed_between_script_tags() {
+        let lesson: Lesson = get_lesson_text().parse().unwrap();
+
+        assert_eq!(lesson.title, "Passing");
+        assert_eq!(lesson.student, "2");
+        let points: Vec<LessonPoint> = lesson.explanations.iter().map(|e| e.point).collect();
+        assert_eq!(
+            points,
+            vec![
+                LessonPoint::BeforePass,
+                LessonPoint::BeforeTrick(2),
+                LessonPoint::BeforeTrick(17)
+            ]
+        );
+        assert_eq!(lesson.script.rounds[0].tricks.len(), 17);
+    }
+
+    #[test]
+    fn invalid_lessons_are_rejected() {
+        let text = get_lesson_text();
+        let cases = [
+            (text.replace("[Student \"2\"]", ""), "Missing Student tag"),
+            (
+                text.replace("[Student \"2\"]", "[Student \"4\"]"),
+                "Unknown student 4",
+            ),
+            (
+                text.replace("[Student \"2\"]", "[Student \"2\" \"3\"]"),
+                "Line 2: Invalid tag Student with 2 values",
+            ),
+            (
+                format!("{}\n[Round \"2\"]", text),
+                "Lesson has to have a single round",
+            ),
+        ];
+
+        for (text, error) in cases {
+            assert_eq!(text.parse::<Lesson>(), Err(GameError(error.to_string())));
+        }
+    }
+
+    #[test]
+    fn student_moves_are_checked_against_the_script() {
+        let lesson: Lesson = get_lesson_text().parse().unwrap();
+        let mut tutorial = Tutorial::new(&lesson, "alice").unwrap();
+        let mut game = tutorial.start_game().unwrap();
+        let passes = tutorial.lesson.script.rounds[0].passes.clone();
+
+        assert_eq!(
+            tutorial.explanation(&game),
+            Some("Pass your highest cards.")
+        );
+        assert!(tutorial.check_exchange(&game, &passes["alice"]).is_err());
+        tutorial.read_explanation(&game).unwrap();
+        assert_eq!(tutorial.explanation(&game), None);
+        assert!(tutorial.read_explanation(&game).is_err());
+        assert!(tutorial.check_exchange(&game, &passes["1"]).is_err());
+
+        play_student_move(&tutorial, &mut game);
+        while tutorial.explanation(&game).is_none() {
+            play_student_move(&tutorial, &mut game);
+        }
+        assert_eq!(get_completed_tricks(&game), 2);
+        tutorial.read_explanation(&game).unwrap();
+
+        let RoundInProgress(step) = &game.state else {
+            unreachable!()
+        };
+        let (_, card) = tutorial.next_play(&game).unwrap();
+        let wrong_card = step
+            .legal_cards("alice")
+            .into_iter()
+            .find(|legal_card| legal_card != card);
+        if let Some(wrong_card) = wrong_card {
+            assert!(tutorial.check_card(&game, wrong_card).is_err());
+        }
+    }
+
+    #[test]
+    fn lesson_ends_with_the_scripted_round() {
+        let lesson: Lesson = get_lesson_text().parse().unwrap();
+        let mut tutorial = Tutorial::new(&lesson, "alice").unwrap();
+        let mut game = tutorial.start_game().unwrap();
+        assert!(Tutorial::new(&lesson, "3").is_err());
+        assert_eq!(game.active_players(), vec!["alice".to_string()]);
+
+        while !game.is_finished {
+            if tutorial.explanation(&game).is_some() {
+                tutorial.read_explanation(&game).unwrap();
+            } else {
+                play_student_move(&tutorial, &mut game);
+            }
+            // bot seats are always done with their moves
+            let history = game.history().clone();
+            game.play_bots(&SimpleBot);
+            assert_eq!(game.history(), &history);
+        }
+
+        assert!(!tutorial.is_finished(&game));
+        assert_eq!(tutorial.explanation(&game), Some("Well done."));
+        tutorial.read_explanation(&game).unwrap();
+        assert!(tutorial.is_finished(&game));
+
+        let played = &game.history().rounds[0];
+        let script = &tutorial.lesson.script.rounds[0];
+        assert_eq!(played.passes, script.passes);
+        assert_eq!(played.tricks, script.tricks);
+        assert_eq!(played.scores, script.scores);
+    }
+}
@@ -0,0 +1,290 @@
+use crate::card::{Card, CardSuit};
+use crate::clock::PlayerClocks;
+use crate::error::{GameError, GameResult};
+use crate::game::GameState::{CardExchange, RoundFinished, RoundInProgress};
+use crate::game::{Game, GameSettings, GameState, TakeBackRequest};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use ts_rs::TS;
+
+/// Everything a single player is allowed to see: their own cards and only the card counts of others.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayerView {
+    pub player: String,
+    pub settings: GameSettings,
+    pub players: Vec<String>,
+    pub round: usize,
+    pub dealer: String,
+    pub scores: HashMap<String, usize>,
+    #[serde(rename = "isFinished")]
+    pub is_finished: bool,
+    #[serde(rename = "playerDecks")]
+    pub player_decks: HashMap<String, usize>,
+    #[serde(rename = "yourCards")]
+    pub your_cards: HashSet<Card>,
+    #[serde(rename = "leftPlayers")]
+    pub left_players: Vec<String>,
+    #[serde(rename = "endVotes")]
+    pub end_votes: HashSet<String>,
+    #[serde(rename = "takeBackRequest")]
+    pub take_back_request: Option<TakeBackRequest>,
+    pub clocks: Option<PlayerClocks>,
+    #[serde(rename = "hintsUsed")]
+    pub hints_used: HashMap<String, usize>,
+    pub state: StateView,
+    /// Cards the player can place right now, house rules included, empty when it is not
+    /// their turn.
+    #[serde(skip)]
+    pub legal_cards: Vec<Card>,
+}
+
+/// Public part of the game, without any cards in hands or selected for exchange.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpectatorView {
+    pub settings: GameSettings,
+    pub players: Vec<String>,
+    pub round: usize,
+    pub dealer: String,
+    pub scores: HashMap<String, usize>,
+    #[serde(rename = "isFinished")]
+    pub is_finished: bool,
+    #[serde(rename = "playerDecks")]
+    pub player_decks: HashMap<String, usize>,
+    #[serde(rename = "leftPlayers")]
+    pub left_players: Vec<String>,
+    #[serde(rename = "endVotes")]
+    pub end_votes: HashSet<String>,
+    #[serde(rename = "takeBackRequest")]
+    pub take_back_request: Option<TakeBackRequest>,
+    pub clocks: Option<PlayerClocks>,
+    #[serde(rename = "hintsUsed")]
+    pub hints_used: HashMap<String, usize>,
+    pub state: StateView,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "step")]
+pub enum StateView {
+    CardExchange(CardExchangeView),
+    RoundInProgress(RoundInProgressView),
+    RoundFinished(RoundFinishedView),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(rename = "CardExchangeState")]
+pub struct CardExchangeView {
+    #[serde(rename = "playerExchangeCards")]
+    pub player_exchange_cards: HashMap<String, bool>,
+    #[serde(rename = "yourExchangeCards")]
+    pub your_exchange_cards: HashSet<Card>,
+    /// Players whose selection can no longer be changed.
+    #[serde(rename = "confirmedPlayers")]
+    pub confirmed_players: HashSet<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(rename = "RoundInProgressState")]
+pub struct RoundInProgressView {
+    #[serde(rename = "currentPlayer")]
+    pub current_player: String,
+    #[serde(rename = "tableSuit")]
+    pub table_suit: Option<CardSuit>,
+    #[serde(rename = "cardsOnTable")]
+    pub cards_on_table: HashMap<String, Card>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(rename = "RoundFinishedState")]
+pub struct RoundFinishedView {
+    #[serde(rename = "playersReady")]
+    pub players_ready: HashMap<String, bool>,
+}
+
+impl PlayerView {
+    pub(crate) fn new(game: &Game, player: &str) -> GameResult<PlayerView> {
+        if !game.players.iter().any(|p| p == player) {
+            Err(GameError(format!("Player {} is not in the game", player)))?
+        }
+
+        let (scores, player_decks) = get_scores_and_decks(&game.state);
+        Ok(PlayerView {
+            player: player.to_string(),
+            settings: game.settings.clone(),
+            players: game.players.to_vec(),
+            round: game.round(),
+            dealer: game.dealer().to_string(),
+            scores: scores.clone(),
+            is_finished: game.is_finished,
+            player_decks: get_deck_sizes(player_decks, Some(player)),
+            your_cards: player_decks.get(player).cloned().unwrap_or_default(),
+            left_players: game.left_players.clone(),
+            end_votes: game.end_votes.clone(),
+            take_back_request: game.take_back_request.clone(),
+            clocks: game.clocks.clone(),
+            hints_used: game.hints_used.clone(),
+            state: StateView::new(game, Some(player)),
+            legal_cards: match &game.state {
+                RoundInProgress(step) if step.state.current_player == player => {
+                    step.legal_cards(player)
+                }
+                _ => Vec::new(),
+            },
+        })
+    }
+}
+
+impl SpectatorView {
+    pub(crate) fn new(game: &Game) -> SpectatorView {
+        let (scores, player_decks) = get_scores_and_decks(&game.state);
+        SpectatorView {
+            settings: game.settings.clone(),
+            players: game.players.to_vec(),
+            round: game.round(),
+            dealer: game.dealer().to_string(),
+            scores: scores.clone(),
+            is_finished: game.is_finished,
+            player_decks: get_deck_sizes(player_decks, None),
+            left_players: game.left_players.clone(),
+            end_votes: game.end_votes.clone(),
+            take_back_request: game.take_back_request.clone(),
+            clocks: game.clocks.clone(),
+            hints_used: game.hints_used.clone(),
+            state: StateView::new(game, None),
+        }
+    }
+}
+
+impl StateView {
+    fn new(game: &Game, player: Option<&str>) -> StateView {
+        match &game.state {
+            CardExchange(step) => {
+                let cards_to_exchange = &step.state.cards_to_exchange;
+                StateView::CardExchange(CardExchangeView {
+                    player_exchange_cards: game
+                        .players
+                        .iter()
+                        .filter(|&p| Some(p.as_str()) != player)
+                        .map(|p| {
+                            let declared = cards_to_exchange.get(p).is_some_and(|c| !c.is_empty());
+                            (p.clone(), declared)
+                        })
+                        .collect(),
+                    your_exchange_cards: player
+                        .and_then(|player| cards_to_exchange.get(player))
+                        .cloned()
+                        .unwrap_or_default(),
+                    confirmed_players: step.state.confirmed_players.clone(),
+                })
+            }
+            RoundInProgress(step) => StateView::RoundInProgress(RoundInProgressView {
+                current_player: step.state.current_player.clone(),
+                table_suit: step.state.table_suit,
+                cards_on_table: step.state.cards_on_table.clone(),
+            }),
+            RoundFinished(step) => StateView::RoundFinished(RoundFinishedView {
+                players_ready: step.state.players_ready.clone(),
+            }),
+        }
+    }
+}
+
+fn get_scores_and_decks(
+    state: &GameState,
+) -> (&HashMap<String, usize>, &HashMap<String, HashSet<Card>>) {
+    match state {
+        CardExchange(step) => (&step.scores, &step.player_decks),
+        RoundInProgress(step) => (&step.scores, &step.player_decks),
+        RoundFinished(step) => (&step.scores, &step.player_decks),
+    }
+}
+
+fn get_deck_sizes(
+    decks: &HashMap<String, HashSet<Card>>,
+    player: Option<&str>,
+) -> HashMap<String, usize> {
+    decks
+        .iter()
+        .filter(|(k, _)| Some(k.as_str()) != player)
+        .map(|(k, v)| (k.clone(), v.len()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::CardExchangePayload;
+
+    fn get_players() -> Vec<String> {
+        vec!["1".to_string(), "2".to_string(), "3".to_string()]
+    }
+
+    fn get_game() -> Game {
+        Game::from_seed(&get_players(), GameSettings::default(), 7).unwrap()
+    }
+
+    #[test]
+    fn player_view_hides_other_hands() {
+        let game = get_game();
+        let view = game.view_for("1").unwrap();
+
+        let CardExchange(step) = &game.state else {
+            panic!("Game should start with card exchange")
+        };
+        assert_eq!(view.your_cards, step.player_decks["1"]);
+        assert_eq!(
+            view.player_decks,
+            HashMap::from([("2".to_string(), 17), ("3".to_string(), 17)])
+        );
+    }
+
+    #[test]
+    fn player_view_shows_only_whether_others_declared_exchange_cards() {
+        let mut game = get_game();
+        let CardExchange(step) = &mut game.state else {
+            panic!("Game should start with card exchange")
+        };
+        let cards: Vec<Card> = step.player_decks["2"].iter().copied().take(3).collect();
+        step.handle_payload(
+            &CardExchangePayload {
+                cards_to_exchange: HashSet::from_iter(cards.iter().copied()),
+            },
+            "2",
+        )
+        .unwrap();
+
+        let StateView::CardExchange(state) = game.view_for("1").unwrap().state else {
+            panic!("Game should be in card exchange")
+        };
+        assert_eq!(
+            state.player_exchange_cards,
+            HashMap::from([("2".to_string(), true), ("3".to_string(), false)])
+        );
+        assert!(state.your_exchange_cards.is_empty());
+
+        let StateView::CardExchange(state) = game.view_for("2").unwrap().state else {
+            panic!("Game should be in card exchange")
+        };
+        assert_eq!(state.your_exchange_cards, HashSet::from_iter(cards));
+    }
+
+    #[test]
+    fn spectator_view_has_no_cards() {
+        let game = get_game();
+        let view = game.spectator_view();
+
+        assert_eq!(view.player_decks.len(), 3);
+        let StateView::CardExchange(state) = view.state else {
+            panic!("Game should be in card exchange")
+        };
+        assert_eq!(state.player_exchange_cards.len(), 3);
+        assert!(state.your_exchange_cards.is_empty());
+    }
+
+    #[test]
+    fn view_for_player_outside_of_game() {
+        assert_eq!(
+            get_game().view_for("4"),
+            Err(GameError("Player 4 is not in the game".to_string()))
+        );
+    }
+}
@@ -0,0 +1,332 @@
+//! SVG images of a player's view, a single trick or a whole round, e.g. for sharing hands.
+//!
+//! Images are self-contained and use only basic shapes and text, so they display the same
+//! in browsers and chat previews.
+
+use game::{Card, CardSuit, GameHistory, PlayerView, StateView, Trick};
+use std::fmt::Write;
+
+const CARD_WIDTH: usize = 44;
+const CARD_HEIGHT: usize = 64;
+const CARD_GAP: usize = 6;
+const MARGIN: usize = 16;
+const LINE_HEIGHT: usize = 22;
+
+/// What the player sees: seats with scores and card counts, the table and their own hand.
+pub fn render_view(view: &PlayerView) -> String {
+    let mut hand: Vec<&Card> = view.your_cards.iter().collect();
+    sort_cards(&mut hand);
+    let width = get_width(hand.len().max(view.players.len()));
+    let mut svg = Svg::new(width);
+
+    svg.title(&format!("Round {}", view.round));
+    for player in &view.players {
+        let cards = match player == &view.player {
+            true => view.your_cards.len(),
+            false => view.player_decks.get(player).copied().unwrap_or(0),
+        };
+        let mut line = format!(
+            "{}: {} points, {} cards",
+            player,
+            view.scores.get(player).copied().unwrap_or(0),
+            cards
+        );
+        if player == &view.dealer {
+            line.push_str(", dealer");
+        }
+        svg.text(&line, player == &view.player);
+    }
+
+    match &view.state {
+        StateView::CardExchange(state) => {
+            let mut cards: Vec<&Card> = state.your_exchange_cards.iter().collect();
+            sort_cards(&mut cards);
+            svg.text("Passing", false);
+            svg.cards(cards.iter().map(|&card| (card, None)));
+        }
+        StateView::RoundInProgress(state) => {
+            svg.text(&format!("{} to play", state.current_player), false);
+            let table = view.players.iter().filter_map(|player| {
+                let card = state.cards_on_table.get(player)?;
+                Some((card, Some(player.as_str())))
+            });
+            svg.cards(table);
+        }
+        StateView::RoundFinished(_) => svg.text("Round finished", false),
+    }
+
+    svg.text(&format!("{}'s hand", view.player), false);
+    svg.cards(hand.into_iter().map(|card| (card, None)));
+    svg.finish()
+}
+
+/// Cards in the order they were played, with the player who takes the trick.
+pub fn render_trick(trick: &Trick) -> String {
+    let mut svg = Svg::new(get_width(trick.plays.len()));
+    let points: usize = trick.plays.iter().map(|(_, card)| card.score).sum();
+
+    match trick.winner() {
+        Some(winner) => svg.title(&format!("{} takes {} points", winner, points)),
+        None => svg.title("Empty trick"),
+    }
+    svg.cards(
+        trick
+            .plays
+            .iter()
+            .map(|(player, card)| (card, Some(player.as_str()))),
+    );
+    svg.finish()
+}
+
+/// Dealt hands, passes and scores of the round with the given index, `None` if there is none.
+pub fn render_round(history: &GameHistory, index: usize) -> Option<String> {
+    let round = history.rounds.get(index)?;
+    let scores_before = history.scores_before_round(index);
+    let hand_size = round.dealt_hands.values().map(|hand| hand.len()).max();
+    let mut svg = Svg::new(get_width(hand_size.unwrap_or(0)));
+
+    svg.title(&format!("Round {}", index + 1));
+    for player in &history.players {
+        let before = scores_before.get(player).copied().unwrap_or(0);
+        let line = match round.scores.get(player) {
+            Some(&score) => format!(
+                "{}: {} points, {} in total",
+                player,
+                score.saturating_sub(before),
+                score
+            ),
+            None => format!("{}: {} points before the round", player, before),
+        };
+        svg.text(&line, true);
+
+        let passed = round.passes.get(player);
+        let mut hand: Vec<&Card> = round
+            .dealt_hands
+            .get(player)
+            .into_iter()
+            .flatten()
+            .collect();
+        sort_cards(&mut hand);
+        svg.cards(hand.into_iter().map(|card| {
+            let label = passed.is_some_and(|cards| cards.contains(card));
+            (card, label.then_some("passed"))
+        }));
+    }
+    Some(svg.finish())
+}
+
+struct Svg {
+    width: usize,
+    height: usize,
+    body: String,
+}
+
+impl Svg {
+    fn new(width: usize) -> Svg {
+        Svg {
+            width,
+            height: MARGIN,
+            body: String::new(),
+        }
+    }
+
+    fn title(&mut self, content: &str) {
+        self.height += LINE_HEIGHT;
+        let _ = write!(
+            self.body,
+            r#"<text x="{}" y="{}" font-size="18" font-weight="bold">{}</text>"#,
+            MARGIN,
+            self.height,
+            escape(content)
+        );
+        self.height += LINE_HEIGHT / 2;
+    }
+
+    fn text(&mut self, content: &str, bold: bool) {
+        self.height += LINE_HEIGHT;
+        let weight = match bold {
+            true => "bold",
+            false => "normal",
+        };
+        let _ = write!(
+            self.body,
+            r#"<text x="{}" y="{}" font-size="14" font-weight="{}">{}</text>"#,
+            MARGIN,
+            self.height,
+            weight,
+            escape(content)
+        );
+    }
+
+    // a row of cards, each with an optional label underneath
+    fn cards<'a>(&mut self, cards: impl Iterator<Item = (&'a Card, Option<&'a str>)>) {
+        let y = self.height + CARD_GAP;
+        let mut has_labels = false;
+        for (position, (card, label)) in cards.enumerate() {
+            let x = MARGIN + position * (CARD_WIDTH + CARD_GAP);
+            self.card(card, x, y);
+            if let Some(label) = label {
+                has_labels = true;
+                let _ = write!(
+                    self.body,
+                    r#"<text x="{}" y="{}" font-size="11" text-anchor="middle">{}</text>"#,
+                    x + CARD_WIDTH / 2,
+                    y + CARD_HEIGHT + 14,
+                    escape(label)
+                );
+            }
+        }
+        self.height = y + CARD_HEIGHT + if has_labels { 18 } else { 0 };
+    }
+
+    fn card(&mut self, card: &Card, x: usize, y: usize) {
+        let color = match card.suit {
+            CardSuit::Heart | CardSuit::Diamond => "#c62828",
+            CardSuit::Spade | CardSuit::Club => "#212121",
+        };
+        let _ = write!(
+            self.body,
+            concat!(
+                r#"<g class="card" fill="{color}">"#,
+                r##"<rect x="{x}" y="{y}" width="{width}" height="{height}" rx="4" fill="#fff" stroke="#616161"/>"##,
+                r#"<text x="{text_x}" y="{rank_y}" font-size="14" font-weight="bold">{rank}</text>"#,
+                r#"<text x="{suit_x}" y="{suit_y}" font-size="24" text-anchor="middle">{suit}</text>"#,
+                "</g>"
+            ),
+            color = color,
+            x = x,
+            y = y,
+            width = CARD_WIDTH,
+            height = CARD_HEIGHT,
+            text_x = x + 5,
+            rank_y = y + 17,
+            rank = get_rank(card),
+            suit_x = x + CARD_WIDTH / 2,
+            suit_y = y + CARD_HEIGHT - 14,
+            suit = get_suit_glyph(card.suit),
+        );
+    }
+
+    fn finish(self) -> String {
+        let height = self.height + MARGIN;
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" "#,
+                r#"viewBox="0 0 {width} {height}" font-family="sans-serif">"#,
+                r##"<rect width="100%" height="100%" fill="#2e7d32"/>"##,
+                r##"<g fill="#fff">{body}</g></svg>"##
+            ),
+            width = self.width,
+            height = height,
+            body = self.body
+        )
+    }
+}
+
+fn get_width(cards_in_row: usize) -> usize {
+    let cards_width = cards_in_row * (CARD_WIDTH + CARD_GAP);
+    (2 * MARGIN + cards_width).max(320)
+}
+
+fn sort_cards(cards: &mut [&Card]) {
+    cards.sort_by_key(|card| (card.suit, card.value));
+}
+
+fn get_rank(card: &Card) -> String {
+    match card.value {
+        11 => "J".to_string(),
+        12 => "Q".to_string(),
+        13 => "K".to_string(),
+        14 => "A".to_string(),
+        value => value.to_string(),
+    }
+}
+
+fn get_suit_glyph(suit: CardSuit) -> char {
+    match suit {
+        CardSuit::Spade => '♠',
+        CardSuit::Club => '♣',
+        CardSuit::Heart => '♥',
+        CardSuit::Diamond => '♦',
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{EndCondition, Game, GameSettings, SimpleBot};
+
+    fn get_game() -> Game {
+        let players = vec![
+            "alice".to_string(),
+            "<bob>".to_string(),
+            "carol".to_string(),
+        ];
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 1 },
+            ..GameSettings::default()
+        };
+        Game::from_seed(&players, settings, 7).unwrap()
+    }
+
+    fn count_cards(svg: &str) -> usize {
+        svg.matches(r#"class="card""#).count()
+    }
+
+    #[test]
+    fn view_shows_own_hand_and_escaped_names() {
+        let game = get_game();
+        let svg = render_view(&game.view_for("alice").unwrap());
+
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        assert!(svg.contains("&lt;bob&gt;: 0 points, 17 cards"));
+        assert!(!svg.contains("<bob>"));
+        assert!(svg.contains("alice&#39;s hand"));
+        assert_eq!(count_cards(&svg), 17);
+    }
+
+    #[test]
+    fn trick_shows_its_winner() {
+        let trick = Trick {
+            plays: vec![
+                ("alice".to_string(), "10S".parse().unwrap()),
+                ("<bob>".to_string(), "QS".parse().unwrap()),
+                ("carol".to_string(), "AH".parse().unwrap()),
+            ],
+        };
+        let svg = render_trick(&trick);
+
+        assert!(svg.contains("&lt;bob&gt; takes 14 points"));
+        assert!(svg.contains(">Q</text>") && svg.contains("♠"));
+        assert_eq!(count_cards(&svg), 3);
+    }
+
+    #[test]
+    fn round_shows_every_dealt_hand() {
+        let mut game = get_game();
+        for player in game.players.clone() {
+            game.replace_with_bot(&player).unwrap();
+        }
+        game.play_bots(&SimpleBot);
+        let svg = render_round(game.history(), 0).unwrap();
+
+        assert_eq!(count_cards(&svg), 51);
+        assert_eq!(svg.matches(">passed</text>").count(), 9);
+        assert!(render_round(game.history(), 1).is_none());
+    }
+}
@@ -0,0 +1,52 @@
+//! Renders a round or a single trick of a hand history file as SVG to stdout.
+//!
+//! `$ cargo run -p render -- <history file> [round] [trick] > hand.svg`
+//!
+//! Rounds and tricks are numbered from 1, the first round is rendered by default.
+
+use game::GameHistory;
+use render::{render_round, render_trick};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(svg) => {
+            println!("{}", svg);
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<String, String> {
+    let path = args
+        .first()
+        .ok_or("Usage: render <history file> [round] [trick]")?;
+    let history: GameHistory = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())?
+        .parse()
+        .map_err(|e: game::GameError| e.to_string())?;
+
+    let number = |index: usize| -> Result<Option<usize>, String> {
+        args.get(index)
+            .map(|arg| match arg.parse::<usize>() {
+                Ok(number) if number > 0 => Ok(number - 1),
+                _ => Err(format!("Invalid number {}", arg)),
+            })
+            .transpose()
+    };
+    let round = number(1)?.unwrap_or(0);
+
+    match number(2)? {
+        Some(trick) => history
+            .rounds
+            .get(round)
+            .and_then(|round| round.tricks.get(trick))
+            .map(render_trick)
+            .ok_or(format!("Trick {} not found", trick + 1)),
+        None => render_round(&history, round).ok_or(format!("Round {} not found", round + 1)),
+    }
+}
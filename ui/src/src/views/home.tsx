@@ -14,6 +14,12 @@ import { WEB_SOCKET_URL } from "../config/consts";
 import { constructSxStyles } from "../utils/construct-sx-styles";
 import { Layout } from "../components/layout";
 import { GameLayout } from "../components/game-layout";
+import { EndCondition } from "../../../../bindings/common/EndCondition";
+
+const formatEndCondition = (endCondition: EndCondition) =>
+  endCondition.type === "maxScore"
+    ? `MaxScore: ${endCondition.maxScore}`
+    : `Rounds: ${endCondition.maxRounds}`;
 
 const HomeView = () => {
   const dispatch = useAppDispatch();
@@ -103,7 +109,7 @@ const HomeView = () => {
             <Typography>
               Players: {lobby.players.length}/{lobby.maxPlayers}
             </Typography>
-            <Typography>MaxScore: {lobby.maxScore}</Typography>
+            <Typography>{formatEndCondition(lobby.endCondition)}</Typography>
             <Button
               variant="contained"
               size="small"
@@ -124,7 +130,7 @@ const HomeView = () => {
           <Typography>
             Players: {lobby.players.length}/{lobby.maxPlayers}
           </Typography>
-          <Typography>MaxScore: {lobby.maxScore}</Typography>
+          <Typography>{formatEndCondition(lobby.endCondition)}</Typography>
           <Button
             variant="contained"
             size="small"
@@ -190,7 +196,19 @@ const HomeView = () => {
           }}
           onClick={() =>
             dispatch(
-              send({ action: "createLobby", maxPlayers: 3, maxScore: 10 })
+              send({
+                action: "createLobby",
+                maxPlayers: 3,
+                endCondition: { type: "maxScore", maxScore: 10 },
+                takeBackCompletedTricks: false,
+                timeControl: null,
+                timeoutPolicy: "autoPlay",
+                confirmCardExchange: false,
+                ruleScript: null,
+                hints: false,
+                tables: null,
+                debugDeal: null,
+              })
             )
           }
         >
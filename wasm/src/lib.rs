@@ -0,0 +1,97 @@
+use game::step::GameStep;
+use game::{
+    Card, CardNotation, CardSuit, GameHistory, PlaceCardPayload, RoundInProgressState, Trick,
+};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+// Cards cross the boundary as strings in any notation accepted by `Card::from_str`,
+// and are returned in the short notation, e.g. `QS`.
+
+const PLAYER: &str = "you";
+
+fn parse_card(card: &str) -> Result<Card, JsValue> {
+    card.parse::<Card>().map_err(|e| JsValue::from_str(&e))
+}
+
+fn parse_cards(cards: &[String]) -> Result<Vec<Card>, JsValue> {
+    cards.iter().map(|card| parse_card(card)).collect()
+}
+
+// step seen from a single player, which is enough to run the engine's move validation
+fn get_step(
+    hand: &[String],
+    table_suit: Option<String>,
+) -> Result<GameStep<RoundInProgressState>, JsValue> {
+    let table_suit = table_suit
+        .map(|suit| suit.parse::<CardSuit>())
+        .transpose()
+        .map_err(|e| JsValue::from_str(&e))?;
+    let players = vec![PLAYER.to_string()];
+
+    Ok(GameStep {
+        player_to_player_map: HashMap::from([(PLAYER.to_string(), PLAYER.to_string())]),
+        scores: HashMap::new(),
+        player_decks: HashMap::from([(PLAYER.to_string(), HashSet::from_iter(parse_cards(hand)?))]),
+        history: GameHistory::new(&players),
+        house_rules: None,
+        players,
+        state: RoundInProgressState {
+            current_player: PLAYER.to_string(),
+            table_suit,
+            cards_on_table: HashMap::new(),
+            round_score: HashMap::new(),
+            trick_winners: Vec::new(),
+        },
+    })
+}
+
+#[wasm_bindgen(js_name = legalCards)]
+pub fn legal_cards(hand: Vec<String>, table_suit: Option<String>) -> Result<Vec<String>, JsValue> {
+    let step = get_step(&hand, table_suit)?;
+    Ok(step
+        .legal_cards(PLAYER)
+        .iter()
+        .map(|card| card.to_notation(CardNotation::Short))
+        .collect())
+}
+
+/// Fails with the same message the server would send for an invalid move.
+#[wasm_bindgen(js_name = validateCard)]
+pub fn validate_card(
+    hand: Vec<String>,
+    table_suit: Option<String>,
+    card: String,
+) -> Result<(), JsValue> {
+    let mut step = get_step(&hand, table_suit)?;
+    step.handle_payload(
+        &PlaceCardPayload {
+            card: parse_card(&card)?,
+        },
+        PLAYER,
+    )
+    .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen(js_name = cardScore)]
+pub fn card_score(card: String) -> Result<usize, JsValue> {
+    Ok(parse_card(&card)?.score)
+}
+
+#[wasm_bindgen(js_name = trickScore)]
+pub fn trick_score(cards: Vec<String>) -> Result<usize, JsValue> {
+    Ok(parse_cards(&cards)?.iter().map(|card| card.score).sum())
+}
+
+/// Index of the winning card, cards have to be given in the order they were played.
+#[wasm_bindgen(js_name = trickWinner)]
+pub fn trick_winner(cards: Vec<String>) -> Result<Option<usize>, JsValue> {
+    let trick = Trick {
+        plays: parse_cards(&cards)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, card)| (i.to_string(), card))
+            .collect(),
+    };
+    Ok(trick.winner().map(|winner| winner.parse().unwrap()))
+}
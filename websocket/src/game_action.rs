@@ -2,25 +2,35 @@ use crate::error::HandlerError::{ActionError, SenderError};
 use crate::error::{HandlerError, HandlerResult};
 use crate::lobby::Lobby;
 use crate::network::{
-    broadcast_game_to_players, broadcast_text, send_text, BroadcastSender, Sender,
+    broadcast_game_to_players, broadcast_text, send_text, send_text_to_player, Sender,
 };
 use crate::payload::{
-    CardExchangePayload, ClaimReadinessPayload, CreateLobbyPayload, InputCard, PlaceCardPayload,
+    AnswerTakeBackPayload, CardExchangePayload, ClaimReadinessPayload, CreateLobbyPayload,
+    InputCard, PlaceCardPayload, StartTutorialPayload,
 };
 use crate::response::{
-    get_obfuscated_game_details_json, GameListResponse, IdResponse, ListedGame,
-    LobbyDetailsResponse, LobbyListResponse, ToJson, WebSocketResponse::*,
+    get_game_details_json, GameAnalysisResponse, GameListResponse, HintResponse, IdResponse,
+    LessonListResponse, ListedGame, ListedLesson, LobbyDetailsResponse, LobbyListResponse, ToJson,
+    TournamentResponse, TutorialResponse, WebSocketResponse::*,
 };
+use crate::storage::{forget_game, forget_lobby, store_game, store_lobby};
 use crate::timeout::{
-    cancel_game_finished_timeout, cancel_lobby_timeout, schedule_delete_finished_game,
-    schedule_delete_lobby,
+    cancel_game_finished_timeout, cancel_lobby_timeout, restart_game_clock,
+    schedule_delete_finished_game, schedule_delete_lobby,
 };
 use crate::WebSocketState;
-use game::{Card, CardExchange, Game, GameSettings, RoundFinished, RoundInProgress};
+use game::helper::get_random_seed;
+use game::{
+    Card, ChallengeResult, DailyChallenge, DuplicateTournament, EndCondition, Game, GameAnalysis,
+    GameResult, Leaderboard, SimpleBot, SystemClock, Tutorial,
+};
 use std::collections::HashSet;
 use std::sync::Arc;
 use uuid::Uuid;
 
+// bounds the solver search of a single move, later moves of a round are rated within it
+const ANALYSIS_NODE_LIMIT: usize = 200_000;
+
 pub(crate) async fn list_lobbies(sender: &mut Sender, state: Arc<WebSocketState>) -> HandlerResult {
     let lobbies = state.lobbies.lock().await;
     let response = LobbyList(LobbyListResponse {
@@ -42,7 +52,7 @@ pub(crate) async fn get_lobby_details(
         .ok_or(ActionError(format!("Lobby with id {} not found", &id)))?;
 
     let response = LobbyDetails(LobbyDetailsResponse {
-        id: id.clone(),
+        id: *id,
         lobby: lobby.clone(),
     });
 
@@ -54,36 +64,30 @@ pub(crate) async fn get_lobby_details(
 pub(crate) async fn create_lobby(
     payload: &CreateLobbyPayload,
     player: &str,
-    broadcast_sender: &mut BroadcastSender,
     state: Arc<WebSocketState>,
 ) -> HandlerResult {
-    let lobby = Lobby::new_by_player(payload.max_players, payload.max_score, player)
-        .map_err(ActionError)?;
+    let lobby = Lobby::new_by_player(payload, player, state.developer_mode).map_err(ActionError)?;
     let mut lobbies = state.lobbies.lock().await;
     let id = Uuid::new_v4();
 
-    lobbies.insert(id.clone(), lobby.clone());
-    let timeout_handle = tokio::spawn(schedule_delete_lobby(
-        id.clone(),
-        broadcast_sender.clone(),
-        state.clone(),
-    ));
+    lobbies.insert(id, lobby.clone());
+    store_lobby(&id, &lobby, state.clone());
+    let timeout_handle = tokio::spawn(schedule_delete_lobby(id, state.clone()));
     {
         let mut lobby_timeouts = state.lobby_timeouts.lock().await;
-        lobby_timeouts.insert(id.clone(), timeout_handle);
+        lobby_timeouts.insert(id, timeout_handle);
     }
 
     let response = LobbyDetails(LobbyDetailsResponse {
         id,
         lobby: lobby.clone(),
     });
-    broadcast_text(&response.to_json(), broadcast_sender).map_err(SenderError)
+    broadcast_text(&response.to_json(), &state).map_err(SenderError)
 }
 
 pub(crate) async fn join_lobby(
     id: &Uuid,
     player: &String,
-    broadcast_sender: &mut BroadcastSender,
     state: Arc<WebSocketState>,
 ) -> HandlerResult {
     let mut lobbies = state.lobbies.lock().await;
@@ -98,74 +102,77 @@ pub(crate) async fn join_lobby(
         )));
     }
 
-    if let Some((game_id, game)) = add_player_to_lobby(lobby, player, state.clone()).await {
+    let games = add_player_to_lobby(lobby, player, state.clone()).await;
+    if !games.is_empty() {
         lobbies.remove(id);
-        cancel_lobby_timeout(&id, state.clone()).await;
+        forget_lobby(id, state.clone());
+        cancel_lobby_timeout(id, state.clone()).await;
 
-        broadcast_text(
-            &LobbyDeleted(IdResponse { id: id.clone() }).to_json(),
-            broadcast_sender,
-        )
-        .map_err(SenderError)?;
+        broadcast_text(&LobbyDeleted(IdResponse { id: *id }).to_json(), &state)
+            .map_err(SenderError)?;
 
-        broadcast_text(
-            &GameCreated(ListedGame {
-                id: game_id.clone(),
-                players: game.players.clone(),
-            })
-            .to_json(),
-            broadcast_sender,
-        )
-        .map_err(SenderError)?;
+        for (game_id, game) in games {
+            restart_game_clock(&game_id, &game, state.clone()).await;
+            broadcast_text(
+                &GameCreated(ListedGame {
+                    id: game_id,
+                    players: game.players.clone(),
+                })
+                .to_json(),
+                &state,
+            )
+            .map_err(SenderError)?;
 
-        return broadcast_game_to_players(&game_id, &game, state.clone())
-            .await
-            .map_err(SenderError);
+            broadcast_game_to_players(&game_id, &game, state.clone())
+                .await
+                .map_err(SenderError)?;
+        }
+        return Ok(());
     }
 
+    store_lobby(id, lobby, state.clone());
     let response = LobbyDetails(LobbyDetailsResponse {
-        id: id.clone(),
+        id: *id,
         lobby: lobby.clone(),
     });
-    broadcast_text(&response.to_json(), broadcast_sender).map_err(SenderError)
+    broadcast_text(&response.to_json(), &state).map_err(SenderError)
 }
 
 async fn add_player_to_lobby(
     lobby: &mut Lobby,
     player: &str,
     state: Arc<WebSocketState>,
-) -> Option<(Uuid, Game)> {
+) -> Vec<(Uuid, Game)> {
     lobby.players.push(player.to_string());
-    if lobby.players.len() == lobby.max_players {
-        let game_id = Uuid::new_v4();
-        let game = Game::from_players(
-            &lobby.players,
-            GameSettings {
-                max_score: lobby.max_score,
-            },
-        )
-        .unwrap();
+    if !lobby.is_full() {
+        return Vec::new();
+    }
 
-        state
-            .games
-            .lock()
-            .await
-            .insert(game_id.clone(), game.clone());
-        Some((game_id, game))
-    } else {
-        None
+    let mut games = match lobby.tables {
+        1 => vec![(
+            Uuid::new_v4(),
+            Game::from_players(&lobby.players, lobby.game_settings()).unwrap(),
+        )],
+        _ => start_tournament(lobby, state.clone()).await,
+    };
+
+    let mut state_games = state.games.lock().await;
+    for (game_id, game) in &mut games {
+        game.advance(&SystemClock, &SimpleBot);
+        store_game(game_id, game, state.clone());
+        state_games.insert(*game_id, game.clone());
     }
+    games
 }
 
 pub(crate) async fn quit_lobby(
     id: &Uuid,
     player: &str,
-    broadcast_sender: &mut BroadcastSender,
     state: Arc<WebSocketState>,
 ) -> HandlerResult {
     let player = player.to_string();
     let mut lobbies = state.lobbies.lock().await;
-    let mut lobby = lobbies
+    let lobby = lobbies
         .get_mut(id)
         .ok_or(ActionError(format!("Lobby with id {} not found", &id)))?;
 
@@ -176,20 +183,24 @@ pub(crate) async fn quit_lobby(
         )));
     }
 
-    let response = match remove_player_from_lobby(player, &mut lobby).await {
+    let response = match remove_player_from_lobby(player, lobby).await {
         Some(_) => {
             lobbies.remove(id);
-            cancel_lobby_timeout(&id, state.clone()).await;
-            LobbyDeleted(IdResponse { id: id.clone() }).to_json()
+            forget_lobby(id, state.clone());
+            cancel_lobby_timeout(id, state.clone()).await;
+            LobbyDeleted(IdResponse { id: *id }).to_json()
+        }
+        None => {
+            store_lobby(id, lobby, state.clone());
+            LobbyDetails(LobbyDetailsResponse {
+                id: *id,
+                lobby: lobby.clone(),
+            })
+            .to_json()
         }
-        None => LobbyDetails(LobbyDetailsResponse {
-            id: id.clone(),
-            lobby: lobby.clone(),
-        })
-        .to_json(),
     };
 
-    broadcast_text(&response, broadcast_sender).map_err(SenderError)
+    broadcast_text(&response, &state).map_err(SenderError)
 }
 
 async fn remove_player_from_lobby(player: String, lobby: &mut Lobby) -> Option<()> {
@@ -218,14 +229,10 @@ pub(crate) async fn get_game_details(
         .get(id)
         .ok_or(ActionError(format!("Game with id {} does not exist", id)))?;
 
-    check_player_in_game(id, &game, player)?;
+    check_player_in_game(id, game, player)?;
 
-    send_text(
-        &get_obfuscated_game_details_json(id, &game, &player),
-        sender,
-    )
-    .await
-    .map_err(SenderError)
+    let response = get_game_details_json(id, game, player).map_err(ActionError)?;
+    send_text(&response, sender).await.map_err(SenderError)
 }
 
 pub(crate) async fn card_exchange_move(
@@ -239,34 +246,30 @@ pub(crate) async fn card_exchange_move(
         &payload.id
     )))?;
 
-    check_player_in_game(&payload.id, &game, player)?;
-    check_game_finished(&game)?;
+    check_player_in_game(&payload.id, game, player)?;
+    // a player who already ran out of time cannot move anymore
+    game.advance(&SystemClock, &SimpleBot);
+    check_game_finished(game)?;
 
-    match &mut game.state {
-        CardExchange(step) => {
-            let mut cards = HashSet::new();
-            for card in &payload.cards_to_exchange {
-                cards.insert(get_validated_card(card)?);
-            }
-
-            let game_payload = game::CardExchangePayload {
-                cards_to_exchange: cards,
-            };
+    let mut cards = HashSet::new();
+    for card in &payload.cards_to_exchange {
+        cards.insert(get_validated_card(card)?);
+    }
 
-            step.handle_payload(&game_payload, player)
-                .map_err(|e| ActionError(e.to_string()))?;
+    let game_payload = game::CardExchangePayload {
+        cards_to_exchange: cards,
+    };
+    check_tutorial_move(&payload.id, state.clone(), |tutorial| {
+        tutorial.check_exchange(game, &game_payload.cards_to_exchange)
+    })
+    .await?;
 
-            if step.should_switch() {
-                game.state = RoundInProgress(step.clone().to_round_in_progress());
-            }
-            broadcast_game_to_players(&payload.id, &game, state.clone())
-                .await
-                .map_err(SenderError)
-        }
-        _ => Err(ActionError(
-            "Invalid game action, expected CardExchangeMove".to_string(),
-        )),
-    }
+    game.exchange_cards(player, &game_payload)
+        .map_err(|e| ActionError(e.to_string()))?;
+    finish_move(&payload.id, game, state.clone()).await;
+    broadcast_game_to_players(&payload.id, game, state.clone())
+        .await
+        .map_err(SenderError)
 }
 
 pub(crate) async fn place_card_move(
@@ -280,29 +283,25 @@ pub(crate) async fn place_card_move(
         &payload.id
     )))?;
 
-    check_player_in_game(&payload.id, &game, player)?;
-    check_game_finished(&game)?;
-
-    match &mut game.state {
-        RoundInProgress(step) => {
-            let game_payload = game::PlaceCardPayload {
-                card: get_validated_card(&payload.card)?.clone(),
-            };
+    check_player_in_game(&payload.id, game, player)?;
+    // a player who already ran out of time cannot move anymore
+    game.advance(&SystemClock, &SimpleBot);
+    check_game_finished(game)?;
 
-            step.handle_payload(&game_payload, player)
-                .map_err(|e| ActionError(e.to_string()))?;
+    let game_payload = game::PlaceCardPayload {
+        card: get_validated_card(&payload.card)?,
+    };
+    check_tutorial_move(&payload.id, state.clone(), |tutorial| {
+        tutorial.check_card(game, game_payload.card)
+    })
+    .await?;
 
-            if step.should_switch() {
-                game.state = RoundFinished(step.clone().to_round_finished());
-            }
-            broadcast_game_to_players(&payload.id, &game, state.clone())
-                .await
-                .map_err(SenderError)
-        }
-        _ => Err(ActionError(
-            "Invalid game action, expected RoundInProgress".to_string(),
-        )),
-    }
+    game.place_card(player, &game_payload)
+        .map_err(|e| ActionError(e.to_string()))?;
+    finish_move(&payload.id, game, state.clone()).await;
+    broadcast_game_to_players(&payload.id, game, state.clone())
+        .await
+        .map_err(SenderError)
 }
 
 pub(crate) async fn claim_readiness_move(
@@ -316,91 +315,164 @@ pub(crate) async fn claim_readiness_move(
         &payload.id
     )))?;
 
-    check_player_in_game(&payload.id, &game, player)?;
-    check_game_finished(&game)?;
-
-    match &mut game.state {
-        RoundFinished(step) => {
-            let game_payload = game::ClaimReadinessPayload {
-                ready: payload.ready,
-            };
+    check_player_in_game(&payload.id, game, player)?;
+    // a player who already ran out of time cannot move anymore
+    game.advance(&SystemClock, &SimpleBot);
+    check_game_finished(game)?;
 
-            step.handle_payload(&game_payload, player);
+    let game_payload = game::ClaimReadinessPayload {
+        ready: payload.ready,
+    };
 
-            if step.should_switch() {
-                game.state = CardExchange(step.clone().to_card_exchange());
-            }
-            broadcast_game_to_players(&payload.id, &game, state.clone())
-                .await
-                .map_err(SenderError)
-        }
-        _ => Err(ActionError(
-            "Invalid game action, expected RoundFinished".to_string(),
-        )),
-    }
+    game.claim_readiness(player, &game_payload)
+        .map_err(|e| ActionError(e.to_string()))?;
+    finish_move(&payload.id, game, state.clone()).await;
+    broadcast_game_to_players(&payload.id, game, state.clone())
+        .await
+        .map_err(SenderError)
 }
 
 pub(crate) async fn quit_game(
     id: &Uuid,
     player: String,
-    broadcast_sender: &mut BroadcastSender,
     state: Arc<WebSocketState>,
 ) -> HandlerResult {
     let mut games = state.games.lock().await;
-    let mut game = games
+    let game = games
         .get_mut(id)
         .ok_or(ActionError(format!("Game with id {} does not exist", id)))?;
 
-    if !game.players.contains(&player) {
-        return Err(ActionError(format!(
-            "You don't participate in game with id {}",
-            id
-        )));
-    }
+    check_player_in_game(id, game, &player)?;
 
-    // forcefully finish game if the player quits during it
-    match remove_player_from_game(player, &mut game).await {
-        Some(_) => {
-            cancel_game_finished_timeout(id, state.clone()).await;
-            games.remove(id);
+    // a bot takes over the seat, so the game can go on for the remaining players
+    let was_finished = game.is_finished;
+    game.replace_with_bot(&player)
+        .map_err(|e| ActionError(e.to_string()))?;
 
-            let response = GameDeleted(IdResponse { id: id.clone() }).to_json();
-            broadcast_text(&response, broadcast_sender).map_err(SenderError)
+    if game.active_players().is_empty() {
+        // bots play out the deals left at a tournament table, so that the tournament finishes
+        if state.tournament_tables.lock().await.contains_key(id) {
+            game.play_bots(&SimpleBot);
+            record_tournament_table(id, game, state.clone()).await;
         }
-        None => {
-            {
-                let mut game_timeouts = state.game_timeouts.lock().await;
-                if game_timeouts.get(id).is_none() {
-                    game_timeouts.insert(
-                        id.clone(),
-                        tokio::spawn(schedule_delete_finished_game(
-                            id.clone(),
-                            broadcast_sender.clone(),
-                            state.clone(),
-                        )),
-                    );
-                }
-            }
-            broadcast_game_to_players(id, &game, state.clone())
-                .await
-                .map_err(SenderError)
+        if was_finished {
+            cancel_game_finished_timeout(id, state.clone()).await;
         }
+        games.remove(id);
+        forget_game(id, state.clone());
+        record_challenge_forfeit(id, &player, state.clone()).await;
+        forget_tournament_table(id, state.clone()).await;
+        state.tutorials.lock().await.remove(id);
+
+        let response = GameDeleted(IdResponse { id: *id }).to_json();
+        return broadcast_text(&response, &state).map_err(SenderError);
+    }
+
+    finish_move(id, game, state.clone()).await;
+    if game.is_finished {
+        schedule_delete_game(id, state.clone()).await;
     }
+    broadcast_game_to_players(id, game, state.clone())
+        .await
+        .map_err(SenderError)
 }
 
-async fn remove_player_from_game(player: String, game: &mut Game) -> Option<()> {
-    let index = game.players.iter().position(|p| p == &player).unwrap();
-    game.players.remove(index);
-    game.is_finished = true;
+pub(crate) async fn vote_end_game(
+    id: &Uuid,
+    player: &String,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let mut games = state.games.lock().await;
+    let game = games
+        .get_mut(id)
+        .ok_or(ActionError(format!("Game with id {} does not exist", id)))?;
+
+    check_player_in_game(id, game, player)?;
 
-    match game.players.len() {
-        0 => Some(()),
-        _ => None,
+    game.vote_to_end(player)
+        .map_err(|e| ActionError(e.to_string()))?;
+    finish_move(id, game, state.clone()).await;
+
+    if game.is_finished {
+        schedule_delete_game(id, state.clone()).await;
+    }
+    broadcast_game_to_players(id, game, state.clone())
+        .await
+        .map_err(SenderError)
+}
+
+pub(crate) async fn request_take_back(
+    id: &Uuid,
+    player: &String,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let mut games = state.games.lock().await;
+    let game = games
+        .get_mut(id)
+        .ok_or(ActionError(format!("Game with id {} does not exist", id)))?;
+
+    check_player_in_game(id, game, player)?;
+    check_game_finished(game)?;
+
+    game.request_take_back(player)
+        .map_err(|e| ActionError(e.to_string()))?;
+    finish_move(id, game, state.clone()).await;
+
+    broadcast_game_to_players(id, game, state.clone())
+        .await
+        .map_err(SenderError)
+}
+
+pub(crate) async fn answer_take_back(
+    payload: &AnswerTakeBackPayload,
+    player: &String,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let mut games = state.games.lock().await;
+    let game = games.get_mut(&payload.id).ok_or(ActionError(format!(
+        "Game with id {} does not exist",
+        &payload.id
+    )))?;
+
+    check_player_in_game(&payload.id, game, player)?;
+    check_game_finished(game)?;
+
+    let result = game.answer_take_back(player, payload.accept);
+    finish_move(&payload.id, game, state.clone()).await;
+    // an outdated request is dropped as well, so everyone has to be notified either way
+    broadcast_game_to_players(&payload.id, game, state.clone())
+        .await
+        .map_err(SenderError)?;
+    result.map_err(|e| ActionError(e.to_string()))
+}
+
+// bot seats and players out of time move right away, then the clock waits for the next timeout
+pub(crate) async fn finish_move(id: &Uuid, game: &mut Game, state: Arc<WebSocketState>) {
+    play_tutorial_script(id, game, state.clone()).await;
+    game.advance(&SystemClock, &SimpleBot);
+    restart_game_clock(id, game, state.clone()).await;
+    if game.is_finished {
+        record_challenge_result(id, game, state.clone()).await;
+    }
+    record_tournament_table(id, game, state.clone()).await;
+    store_game(id, game, state.clone());
+    if game.is_finished {
+        schedule_delete_game(id, state).await;
+    }
+}
+
+pub(crate) async fn schedule_delete_game(id: &Uuid, state: Arc<WebSocketState>) {
+    let mut game_timeouts = state.game_timeouts.lock().await;
+    if game_timeouts.get(id).is_none() {
+        game_timeouts.insert(
+            *id,
+            tokio::spawn(schedule_delete_finished_game(*id, state.clone())),
+        );
     }
 }
 
 fn check_player_in_game(id: &Uuid, game: &Game, player: &String) -> HandlerResult {
-    if !game.players.contains(player) {
+    if !game.active_players().contains(player) {
         return Err(ActionError(format!(
             "You don't participate in game with id {}",
             id
@@ -420,4 +492,538 @@ fn get_validated_card(card: &InputCard) -> Result<Card, HandlerError> {
     Card::new(card.suit, card.value).map_err(ActionError)
 }
 
-// TODO: maybe redis for shared state if scaling instances
+pub(crate) async fn withdraw_exchange_cards(
+    id: &Uuid,
+    player: &String,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let mut games = state.games.lock().await;
+    let game = games
+        .get_mut(id)
+        .ok_or(ActionError(format!("Game with id {} does not exist", id)))?;
+
+    check_player_in_game(id, game, player)?;
+    game.advance(&SystemClock, &SimpleBot);
+    check_game_finished(game)?;
+
+    game.withdraw_exchange_cards(player)
+        .map_err(|e| ActionError(e.to_string()))?;
+    finish_move(id, game, state.clone()).await;
+    broadcast_game_to_players(id, game, state.clone())
+        .await
+        .map_err(SenderError)
+}
+
+pub(crate) async fn confirm_exchange_cards(
+    id: &Uuid,
+    player: &String,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let mut games = state.games.lock().await;
+    let game = games
+        .get_mut(id)
+        .ok_or(ActionError(format!("Game with id {} does not exist", id)))?;
+
+    check_player_in_game(id, game, player)?;
+    game.advance(&SystemClock, &SimpleBot);
+    check_game_finished(game)?;
+
+    game.confirm_exchange_cards(player)
+        .map_err(|e| ActionError(e.to_string()))?;
+    finish_move(id, game, state.clone()).await;
+    broadcast_game_to_players(id, game, state.clone())
+        .await
+        .map_err(SenderError)
+}
+
+// only the player asking sees the hint, everyone sees the count of hints used
+pub(crate) async fn get_hint(
+    id: &Uuid,
+    player: &String,
+    sender: &mut Sender,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let mut games = state.games.lock().await;
+    let game = games
+        .get_mut(id)
+        .ok_or(ActionError(format!("Game with id {} does not exist", id)))?;
+
+    check_player_in_game(id, game, player)?;
+    game.advance(&SystemClock, &SimpleBot);
+    check_game_finished(game)?;
+
+    let hint = game
+        .hint(player, &SimpleBot)
+        .map_err(|e| ActionError(e.to_string()))?;
+    store_game(id, game, state.clone());
+    send_text(&Hint(HintResponse { id: *id, hint }).to_json(), sender)
+        .await
+        .map_err(SenderError)?;
+    broadcast_game_to_players(id, game, state.clone())
+        .await
+        .map_err(SenderError)
+}
+
+// available until the finished game is deleted, also to players who left it
+pub(crate) async fn get_game_analysis(
+    id: &Uuid,
+    player: &String,
+    sender: &mut Sender,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let games = state.games.lock().await;
+    let game = games
+        .get(id)
+        .ok_or(ActionError(format!("Game with id {} does not exist", id)))?;
+
+    if !game.players.contains(player) {
+        return Err(ActionError(format!(
+            "You don't participate in game with id {}",
+            id
+        )));
+    }
+    if !game.is_finished {
+        return Err(ActionError("Game is not finished yet".to_string()));
+    }
+    // the history does not hold the script, so the replay would follow the standard rules
+    if game.settings.rule_script.is_some() {
+        return Err(ActionError("House rules are not analysed".to_string()));
+    }
+    let history = game.history().clone();
+    drop(games);
+
+    let analysis_player = player.clone();
+    let analysis = tokio::task::spawn_blocking(move || {
+        GameAnalysis::new(&history, &analysis_player, ANALYSIS_NODE_LIMIT)
+    })
+    .await
+    .map_err(|e| ActionError(e.to_string()))?
+    .map_err(|e| ActionError(e.to_string()))?;
+
+    let response = GameAnalysis(GameAnalysisResponse { id: *id, analysis });
+    send_text(&response.to_json(), sender)
+        .await
+        .map_err(SenderError)
+}
+
+pub(crate) async fn start_daily_challenge(
+    player: &str,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let challenge = DailyChallenge::today(&SystemClock);
+    let leaderboards = state.leaderboards.lock().await;
+    if leaderboards
+        .get(&challenge.date)
+        .is_some_and(|leaderboard| leaderboard.has_played(player))
+    {
+        return Err(ActionError(format!(
+            "You have already played the challenge of {}",
+            challenge.date
+        )));
+    }
+    drop(leaderboards);
+
+    let mut games = state.games.lock().await;
+    let mut challenge_games = state.challenge_games.lock().await;
+    let already_playing = challenge_games
+        .iter()
+        .any(|(id, (other_challenge, other_player))| {
+            other_challenge == &challenge
+                && other_player == player
+                && games.get(id).is_some_and(|game| !game.is_finished)
+        });
+    if already_playing {
+        return Err(ActionError(format!(
+            "You are already playing the challenge of {}",
+            challenge.date
+        )));
+    }
+
+    let id = Uuid::new_v4();
+    let mut game = challenge
+        .start(player)
+        .map_err(|e| ActionError(e.to_string()))?;
+    game.advance(&SystemClock, &SimpleBot);
+    challenge_games.insert(id, (challenge, player.to_string()));
+    drop(challenge_games);
+
+    store_game(&id, &game, state.clone());
+    let game = games.entry(id).or_insert(game);
+    broadcast_game_to_players(&id, game, state.clone())
+        .await
+        .map_err(SenderError)
+}
+
+pub(crate) async fn get_daily_leaderboard(
+    sender: &mut Sender,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let date = DailyChallenge::today(&SystemClock).date;
+    let leaderboard = state
+        .leaderboards
+        .lock()
+        .await
+        .get(&date)
+        .cloned()
+        .unwrap_or(Leaderboard::new(&date));
+
+    send_text(&DailyLeaderboard(leaderboard).to_json(), sender)
+        .await
+        .map_err(SenderError)
+}
+
+async fn record_challenge_result(id: &Uuid, game: &Game, state: Arc<WebSocketState>) {
+    let Some((challenge, player)) = state.challenge_games.lock().await.remove(id) else {
+        return;
+    };
+    let Some(result) = challenge.result(game, &player) else {
+        return;
+    };
+
+    add_challenge_result(&challenge, result, state).await;
+}
+
+// the deal of the challenge cannot be started over after quitting
+async fn record_challenge_forfeit(id: &Uuid, player: &str, state: Arc<WebSocketState>) {
+    let Some((challenge, _)) = state.challenge_games.lock().await.remove(id) else {
+        return;
+    };
+    add_challenge_result(&challenge, challenge.forfeit(player), state).await;
+}
+
+async fn add_challenge_result(
+    challenge: &DailyChallenge,
+    result: ChallengeResult,
+    state: Arc<WebSocketState>,
+) {
+    let mut leaderboards = state.leaderboards.lock().await;
+    let leaderboard = leaderboards
+        .entry(challenge.date.clone())
+        .or_insert(Leaderboard::new(&challenge.date));
+    if let Err(error) = leaderboard.add(result) {
+        tracing::warn!("{}", error);
+    }
+}
+
+pub(crate) async fn list_lessons(sender: &mut Sender, state: Arc<WebSocketState>) -> HandlerResult {
+    let mut lessons: Vec<ListedLesson> = state
+        .lessons
+        .iter()
+        .map(|(name, lesson)| ListedLesson {
+            name: name.clone(),
+            title: lesson.title.clone(),
+        })
+        .collect();
+    lessons.sort_by(|first, second| first.name.cmp(&second.name));
+
+    send_text(
+        &LessonList(LessonListResponse { lessons }).to_json(),
+        sender,
+    )
+    .await
+    .map_err(SenderError)
+}
+
+pub(crate) async fn start_tutorial(
+    payload: &StartTutorialPayload,
+    player: &str,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let lesson = state
+        .lessons
+        .get(&payload.lesson)
+        .ok_or(ActionError(format!("Lesson {} not found", payload.lesson)))?;
+    let tutorial = Tutorial::new(lesson, player).map_err(|e| ActionError(e.to_string()))?;
+    let game = tutorial
+        .start_game()
+        .map_err(|e| ActionError(e.to_string()))?;
+
+    let id = Uuid::new_v4();
+    store_game(&id, &game, state.clone());
+    let mut games = state.games.lock().await;
+    let game = games.entry(id).or_insert(game);
+    broadcast_game_to_players(&id, game, state.clone())
+        .await
+        .map_err(SenderError)?;
+
+    let mut tutorials = state.tutorials.lock().await;
+    let tutorial = tutorials.entry(id).or_insert(tutorial);
+    send_tutorial_to_player(&id, tutorial, game, state.clone()).await;
+    Ok(())
+}
+
+pub(crate) async fn continue_tutorial(
+    id: &Uuid,
+    player: &String,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let games = state.games.lock().await;
+    let game = games
+        .get(id)
+        .ok_or(ActionError(format!("Game with id {} does not exist", id)))?;
+    check_player_in_game(id, game, player)?;
+
+    let mut tutorials = state.tutorials.lock().await;
+    let tutorial = tutorials
+        .get_mut(id)
+        .ok_or(ActionError(format!("Game with id {} is not a lesson", id)))?;
+    tutorial
+        .read_explanation(game)
+        .map_err(|e| ActionError(e.to_string()))?;
+    send_tutorial_to_player(id, tutorial, game, state.clone()).await;
+    Ok(())
+}
+
+// moves in a lesson have to be the ones from its script
+async fn check_tutorial_move(
+    id: &Uuid,
+    state: Arc<WebSocketState>,
+    check: impl FnOnce(&Tutorial) -> GameResult<()>,
+) -> HandlerResult {
+    match state.tutorials.lock().await.get(id) {
+        Some(tutorial) => check(tutorial).map_err(|e| ActionError(e.to_string())),
+        None => Ok(()),
+    }
+}
+
+// bots of a lesson follow the script, then the player learns whether the lesson is paused
+async fn play_tutorial_script(id: &Uuid, game: &mut Game, state: Arc<WebSocketState>) {
+    let tutorials = state.tutorials.lock().await;
+    let Some(tutorial) = tutorials.get(id) else {
+        return;
+    };
+    if let Err(error) = tutorial.play_script(game) {
+        tracing::error!("Script of lesson in game with id {} failed: {}", id, error);
+    }
+    send_tutorial_to_player(id, tutorial, game, state.clone()).await;
+}
+
+async fn send_tutorial_to_player(
+    id: &Uuid,
+    tutorial: &Tutorial,
+    game: &Game,
+    state: Arc<WebSocketState>,
+) {
+    let response = Tutorial(TutorialResponse {
+        id: *id,
+        title: tutorial.lesson.title.clone(),
+        explanation: tutorial.explanation(game).map(String::from),
+        is_finished: tutorial.is_finished(game),
+    })
+    .to_json();
+
+    if let Err(error) = send_text_to_player(&response, tutorial.player(), state).await {
+        tracing::error!(error);
+    }
+}
+
+// every table starts with the same deal, players learn the tournament id from the standings
+async fn start_tournament(lobby: &Lobby, state: Arc<WebSocketState>) -> Vec<(Uuid, Game)> {
+    let EndCondition::MaxRounds { max_rounds } = lobby.end_condition else {
+        unreachable!("Tournament lobby without a fixed number of rounds")
+    };
+    let deal_seeds: Vec<u64> = (0..max_rounds).map(|_| get_random_seed()).collect();
+    let tournament = DuplicateTournament::new(
+        &lobby.players,
+        lobby.max_players,
+        lobby.game_settings(),
+        &deal_seeds,
+    )
+    .unwrap();
+
+    let tournament_id = Uuid::new_v4();
+    let mut tournament_tables = state.tournament_tables.lock().await;
+    let games = (0..tournament.tables.len())
+        .map(|table| {
+            let game_id = Uuid::new_v4();
+            tournament_tables.insert(game_id, (tournament_id, table));
+            (game_id, tournament.start_table(table).unwrap())
+        })
+        .collect();
+    drop(tournament_tables);
+
+    send_tournament_to_players(&tournament_id, &tournament, state.clone()).await;
+    state
+        .tournaments
+        .lock()
+        .await
+        .insert(tournament_id, tournament);
+    games
+}
+
+pub(crate) async fn get_tournament(
+    id: &Uuid,
+    player: &String,
+    sender: &mut Sender,
+    state: Arc<WebSocketState>,
+) -> HandlerResult {
+    let tournaments = state.tournaments.lock().await;
+    let tournament = tournaments.get(id).ok_or(ActionError(format!(
+        "Tournament with id {} does not exist",
+        id
+    )))?;
+
+    if !tournament.tables.iter().flatten().any(|p| p == player) {
+        return Err(ActionError(format!(
+            "You don't participate in tournament with id {}",
+            id
+        )));
+    }
+
+    send_text(&get_tournament_json(id, tournament), sender)
+        .await
+        .map_err(SenderError)
+}
+
+// standings are sent to all entrants whenever a table finishes a deal
+async fn record_tournament_table(id: &Uuid, game: &Game, state: Arc<WebSocketState>) {
+    let Some((tournament_id, table)) = state.tournament_tables.lock().await.get(id).copied() else {
+        return;
+    };
+
+    let mut tournaments = state.tournaments.lock().await;
+    let Some(tournament) = tournaments.get_mut(&tournament_id) else {
+        return;
+    };
+    let finished_deals = tournament.finished_deals(table);
+    tournament.record_table(table, game.history());
+    if tournament.finished_deals(table) == finished_deals {
+        return;
+    }
+
+    send_tournament_to_players(&tournament_id, tournament, state.clone()).await;
+    if tournament.is_finished() {
+        tournaments.remove(&tournament_id);
+    }
+}
+
+/// Drops the tournament once none of its tables is left.
+pub(crate) async fn forget_tournament_table(id: &Uuid, state: Arc<WebSocketState>) {
+    let mut tournament_tables = state.tournament_tables.lock().await;
+    let Some((tournament_id, _)) = tournament_tables.remove(id) else {
+        return;
+    };
+    if !tournament_tables
+        .values()
+        .any(|(other_id, _)| other_id == &tournament_id)
+    {
+        state.tournaments.lock().await.remove(&tournament_id);
+    }
+}
+
+async fn send_tournament_to_players(
+    id: &Uuid,
+    tournament: &DuplicateTournament,
+    state: Arc<WebSocketState>,
+) {
+    let response = get_tournament_json(id, tournament);
+    for player in tournament.tables.iter().flatten() {
+        if let Err(error) = send_text_to_player(&response, player, state.clone()).await {
+            tracing::error!(error);
+        }
+    }
+}
+
+fn get_tournament_json(id: &Uuid, tournament: &DuplicateTournament) -> String {
+    TournamentStandings(TournamentResponse {
+        id: *id,
+        tables: tournament.tables.clone(),
+        is_finished: tournament.is_finished(),
+        standings: tournament.standings(),
+    })
+    .to_json()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::step::ALL_POINTS;
+    use game::GameSettings;
+
+    #[tokio::test]
+    async fn quitting_daily_challenge_counts_as_forfeit() {
+        let state = Arc::new(WebSocketState::new());
+        assert!(start_daily_challenge("alice", state.clone()).await.is_ok());
+        let id = *state.games.lock().await.keys().next().unwrap();
+
+        assert!(quit_game(&id, "alice".to_string(), state.clone())
+            .await
+            .is_ok());
+
+        assert!(state.games.lock().await.is_empty());
+        assert!(state.challenge_games.lock().await.is_empty());
+        let date = DailyChallenge::today(&SystemClock).date;
+        let results = state.leaderboards.lock().await[&date].results.clone();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].score, ALL_POINTS);
+        assert!(start_daily_challenge("alice", state.clone()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn games_with_house_rules_are_not_analysed() {
+        let state = Arc::new(WebSocketState::new());
+        let players = ["1", "2", "3"].map(String::from);
+        let settings = GameSettings {
+            end_condition: EndCondition::MaxRounds { max_rounds: 1 },
+            rule_script: Some("fn trick_winner(state) { state.table[0].player }".to_string()),
+            ..GameSettings::default()
+        };
+        let mut game = Game::from_seed(&players, settings, 7).unwrap();
+        for player in &players {
+            game.replace_with_bot(player).unwrap();
+        }
+        game.play_bots(&SimpleBot);
+        assert!(game.is_finished);
+        let id = Uuid::new_v4();
+        state.games.lock().await.insert(id, game);
+
+        let (mut sender, _receiver) = tokio::sync::mpsc::channel(1);
+        let result = get_game_analysis(&id, &"1".to_string(), &mut sender, state).await;
+        assert!(matches!(
+            result,
+            Err(ActionError(error)) if error == "House rules are not analysed"
+        ));
+    }
+
+    #[tokio::test]
+    async fn tournament_table_left_by_everyone_is_played_out() {
+        let state = Arc::new(WebSocketState::new());
+        let entrants: Vec<String> = (1..=6).map(|player| player.to_string()).collect();
+        let tournament =
+            DuplicateTournament::new(&entrants, 3, Default::default(), &[1, 2]).unwrap();
+        let tournament_id = Uuid::new_v4();
+        let mut tables = Vec::new();
+        for table in 0..2 {
+            let id = Uuid::new_v4();
+            let game = tournament.start_table(table).unwrap();
+            state.games.lock().await.insert(id, game);
+            state
+                .tournament_tables
+                .lock()
+                .await
+                .insert(id, (tournament_id, table));
+            tables.push(id);
+        }
+        state
+            .tournaments
+            .lock()
+            .await
+            .insert(tournament_id, tournament.clone());
+
+        for (table, id) in tables.iter().enumerate() {
+            for player in &tournament.tables[table] {
+                assert!(quit_game(id, player.clone(), state.clone()).await.is_ok());
+            }
+            let tournaments = state.tournaments.lock().await;
+            match tournaments.get(&tournament_id) {
+                Some(tournament) => {
+                    assert_eq!(table, 0);
+                    assert_eq!(tournament.finished_deals(table), 2);
+                }
+                None => assert_eq!(table, 1),
+            }
+        }
+        assert!(state.games.lock().await.is_empty());
+        assert!(state.tournament_tables.lock().await.is_empty());
+    }
+}
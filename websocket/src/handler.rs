@@ -1,10 +1,15 @@
 use crate::error::HandlerError::{ActionError, SenderError};
 use crate::game_action::{
-    card_exchange_move, claim_readiness_move, create_lobby, get_game_details, get_lobby_details,
-    join_lobby, list_games, list_lobbies, place_card_move, quit_game, quit_lobby,
+    answer_take_back, card_exchange_move, claim_readiness_move, confirm_exchange_cards,
+    continue_tutorial, create_lobby, get_daily_leaderboard, get_game_analysis, get_game_details,
+    get_hint, get_lobby_details, get_tournament, join_lobby, list_games, list_lessons,
+    list_lobbies, place_card_move, quit_game, quit_lobby, request_take_back, start_daily_challenge,
+    start_tutorial, vote_end_game, withdraw_exchange_cards,
 };
 use crate::network::send_error;
 use crate::payload::{WebSocketPayload, WebSocketPayload::*};
+use crate::session::{hold_seats, resume_session};
+use crate::shared::lock_shared;
 use crate::WebSocketState;
 use axum::extract::ws::{Message, WebSocket};
 use axum::extract::{State, WebSocketUpgrade};
@@ -46,6 +51,9 @@ pub(crate) async fn handle_websocket(
         let _ = send_error(&text, &mut sender).await;
         return;
     }
+    if let Err(error) = resume_session(&user, &mut sender, state.clone()).await {
+        tracing::error!("Failed to resume session of player {}: {}", user, error);
+    }
 
     let mut receiver_task = tokio::spawn(read(stream, sender.clone(), user.clone(), state.clone()));
     let mut broadcast_receiver_task = tokio::spawn(read_broadcast(
@@ -58,8 +66,11 @@ pub(crate) async fn handle_websocket(
         _ = &mut broadcast_receiver_task => receiver_task.abort()
     }
 
-    let mut player_connections = state.player_connections.write().await;
-    player_connections.remove(&user);
+    state.player_connections.write().await.remove(&user);
+    if let Err(error) = state.shared.disconnect_player(&user, &state.instance) {
+        tracing::error!("Failed to disconnect player {}: {}", user, error);
+    }
+    hold_seats(&user, state).await;
 }
 
 fn get_user_from_header(user_cookie: Option<&Cookie>) -> Result<String, String> {
@@ -80,6 +91,7 @@ async fn add_player_to_connections(
     if player_connections.contains_key(player) {
         Err("This player is already connected")?
     }
+    state.shared.connect_player(player, &state.instance)?;
     player_connections.insert(player.to_string(), sender);
     Ok(())
 }
@@ -125,18 +137,14 @@ pub(crate) async fn handle_message(
     state: Arc<WebSocketState>,
     sender: &mut mpsc::Sender<Message>,
 ) -> ControlFlow<(), ()> {
-    let mut broadcast_sender = state.broadcast_sender.clone();
-
     match message {
-        Message::Text(text) => {
-            match handle_text_message(text, player, sender, &mut broadcast_sender, state).await {
-                Ok(_) => ControlFlow::Continue(()),
-                Err(text) => {
-                    tracing::error!(text);
-                    ControlFlow::Break(())
-                }
+        Message::Text(text) => match handle_text_message(text, player, sender, state).await {
+            Ok(_) => ControlFlow::Continue(()),
+            Err(text) => {
+                tracing::error!(text);
+                ControlFlow::Break(())
             }
-        }
+        },
         Message::Close(_) => ControlFlow::Break(()),
         _ => match sender
             .send(Message::Text("Invalid message type".to_string()))
@@ -155,7 +163,6 @@ pub(crate) async fn handle_text_message(
     text: String,
     player: &String,
     sender: &mut mpsc::Sender<Message>,
-    broadcast_sender: &mut broadcast::Sender<Message>,
     state: Arc<WebSocketState>,
 ) -> Result<(), String> {
     let payload_result = serde_json::from_str::<WebSocketPayload>(&text);
@@ -163,23 +170,47 @@ pub(crate) async fn handle_text_message(
         send_error(&error.to_string(), sender).await?;
         return Ok(());
     };
+    let payload = payload_result.unwrap();
 
-    let handler_result = match payload_result.unwrap() {
+    // other instances cannot change the game or lobby until the payload is handled
+    let _lock = match payload.target_id() {
+        Some(id) => match lock_shared(&id, state.clone()).await {
+            Ok(lock) => Some(lock),
+            Err(error) => {
+                tracing::error!("Failed to lock {}: {}", id, error);
+                return send_error("Server is unavailable, try again", sender).await;
+            }
+        },
+        None => None,
+    };
+
+    let handler_result = match payload {
         ListLobbies => list_lobbies(sender, state).await,
         GetLobbyDetails(payload) => get_lobby_details(&payload.id, sender, state).await,
         CreateLobby(create_lobby_payload) => {
-            create_lobby(&create_lobby_payload, player, broadcast_sender, state).await
+            create_lobby(&create_lobby_payload, player, state).await
         }
-        JoinLobby(payload) => join_lobby(&payload.id, player, broadcast_sender, state).await,
-        QuitLobby(payload) => quit_lobby(&payload.id, player, broadcast_sender, state).await,
+        JoinLobby(payload) => join_lobby(&payload.id, player, state).await,
+        QuitLobby(payload) => quit_lobby(&payload.id, player, state).await,
         ListGames => list_games(sender, state).await,
         GetGameDetails(payload) => get_game_details(&payload.id, player, sender, state).await,
         CardExchangeMove(payload) => card_exchange_move(&payload, player, state).await,
         PlaceCardMove(payload) => place_card_move(&payload, player, state).await,
         ClaimReadinessMove(payload) => claim_readiness_move(&payload, player, state).await,
-        QuitGame(payload) => {
-            quit_game(&payload.id, player.to_string(), broadcast_sender, state).await
-        }
+        QuitGame(payload) => quit_game(&payload.id, player.to_string(), state).await,
+        VoteEndGame(payload) => vote_end_game(&payload.id, player, state).await,
+        RequestTakeBack(payload) => request_take_back(&payload.id, player, state).await,
+        AnswerTakeBack(payload) => answer_take_back(&payload, player, state).await,
+        WithdrawExchangeCards(payload) => withdraw_exchange_cards(&payload.id, player, state).await,
+        ConfirmExchangeCards(payload) => confirm_exchange_cards(&payload.id, player, state).await,
+        StartDailyChallenge => start_daily_challenge(player, state).await,
+        GetDailyLeaderboard => get_daily_leaderboard(sender, state).await,
+        GetGameAnalysis(payload) => get_game_analysis(&payload.id, player, sender, state).await,
+        GetHint(payload) => get_hint(&payload.id, player, sender, state).await,
+        GetTournament(payload) => get_tournament(&payload.id, player, sender, state).await,
+        ListLessons => list_lessons(sender, state).await,
+        StartTutorial(payload) => start_tutorial(&payload, player, state).await,
+        ContinueTutorial(payload) => continue_tutorial(&payload.id, player, state).await,
     };
 
     if let Err(error) = handler_result {
@@ -0,0 +1,51 @@
+use crate::WebSocketState;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use render::{render_round, render_trick};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub(crate) struct ImageQuery {
+    round: Option<usize>,
+    trick: Option<usize>,
+}
+
+/// SVG of a round, or a single trick of it, of a finished game. Rounds and tricks are
+/// numbered from 1, the last round is rendered by default.
+pub(crate) async fn get_game_image(
+    Path(id): Path<Uuid>,
+    Query(query): Query<ImageQuery>,
+    State(state): State<Arc<WebSocketState>>,
+) -> Response {
+    let games = state.games.lock().await;
+    let Some(game) = games.get(&id).filter(|game| game.is_finished) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("Finished game with id {} not found", id),
+        )
+            .into_response();
+    };
+
+    let history = game.history();
+    let round = match query.round {
+        Some(round) => round.checked_sub(1),
+        None => history.rounds.len().checked_sub(1),
+    };
+    let svg = match (round, query.trick) {
+        (Some(round), Some(trick)) => history
+            .rounds
+            .get(round)
+            .and_then(|round| round.tricks.get(trick.checked_sub(1)?))
+            .map(render_trick),
+        (Some(round), None) => render_round(history, round),
+        (None, _) => None,
+    };
+
+    match svg {
+        Some(svg) => ([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response(),
+        None => (StatusCode::NOT_FOUND, "Round or trick not found").into_response(),
+    }
+}
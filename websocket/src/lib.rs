@@ -1,20 +1,29 @@
 mod error;
 mod game_action;
 mod handler;
-mod helper;
+mod image;
 mod lobby;
 mod network;
 pub mod payload;
+mod redis_state;
 pub mod response;
+mod session;
+mod shared;
+mod storage;
 mod timeout;
 
 use crate::handler::handle;
+use crate::image::get_game_image;
 use crate::lobby::Lobby;
+use crate::redis_state::RedisState;
+use crate::session::ReconnectingPlayer;
+use crate::shared::{keep_players_connected, listen_to_instances, LocalState, SharedState};
+use crate::storage::{restore_from_storage, NoStorage, SqliteStorage, Storage};
 use axum::extract::ws::Message;
 use axum::routing::get;
 use axum::Router;
-use game::Game;
-use std::collections::HashMap;
+use game::{DailyChallenge, DuplicateTournament, Game, Leaderboard, Lesson, Tutorial};
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
@@ -31,8 +40,38 @@ pub async fn start_game_server() {
         .init();
 
     let port = std::env::var("PORT").unwrap_or("6379".to_string());
-    let state = Arc::new(WebSocketState::new());
-    let app = Router::new().route("/ws", get(handle)).with_state(state);
+    let lessons_directory = std::env::var("LESSONS_DIR").unwrap_or("lessons".to_string());
+    let mut state = WebSocketState::new();
+    state.lessons = load_lessons(&lessons_directory);
+    // lets lobbies preset the deal, e.g. to reproduce bugs, never enable it in production
+    state.developer_mode = matches!(
+        std::env::var("DEVELOPER_MODE").as_deref(),
+        Ok("1") | Ok("true")
+    );
+    if state.developer_mode {
+        tracing::warn!("Running in developer mode");
+    }
+    // without a database everything is lost on restart
+    if let Ok(path) = std::env::var("DATABASE_PATH") {
+        state.storage = Arc::new(SqliteStorage::open(&path).unwrap());
+        tracing::info!("Storing lobbies and games in {}", path);
+    }
+    // instances sharing the Redis share games, lobbies and players, Redis stores them as well
+    if let Ok(url) = std::env::var("REDIS_URL") {
+        let redis = Arc::new(RedisState::connect(&url).unwrap());
+        state.storage = redis.clone();
+        state.shared = redis;
+        tracing::info!("Sharing state through Redis at {}", url);
+    }
+    let receiver = state.shared.subscribe().unwrap();
+    let state = Arc::new(state);
+    tokio::spawn(listen_to_instances(receiver, state.clone()));
+    tokio::spawn(keep_players_connected(state.clone()));
+    restore_from_storage(state.clone()).await;
+    let app = Router::new()
+        .route("/ws", get(handle))
+        .route("/games/:id/svg", get(get_game_image))
+        .with_state(state);
 
     tracing::info!("Starting server on port {}", port);
     let listener = TcpListener::bind(format!("0.0.0.0:{}", port))
@@ -47,24 +86,92 @@ pub async fn start_game_server() {
     .unwrap();
 }
 
+// lessons by file name without the extension, files which are not valid lessons are skipped
+fn load_lessons(directory: &str) -> HashMap<String, Lesson> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            tracing::warn!("Cannot read lessons from {}: {}", directory, error);
+            return HashMap::new();
+        }
+    };
+
+    let mut lessons = HashMap::new();
+    for path in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let lesson = std::fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|text| text.parse::<Lesson>().map_err(|error| error.to_string()));
+        match lesson {
+            Ok(lesson) => {
+                lessons.insert(name.to_string(), lesson);
+            }
+            Err(error) => tracing::warn!("Skipping lesson {}: {}", path.display(), error),
+        }
+    }
+    tracing::info!("Loaded {} lessons from {}", lessons.len(), directory);
+    lessons
+}
+
+// to avoid deadlocks, maps locked together are always locked in this order: lobbies before
+// games, games before the maps of timeouts, challenges, tournaments and tutorials
 struct WebSocketState {
+    // tells apart the instances sharing the state
+    instance: Uuid,
     games: Mutex<HashMap<Uuid, Game>>,
     lobbies: Mutex<HashMap<Uuid, Lobby>>,
+    // games changed by other instances since they were last loaded, see `reload_changed_game`
+    changed_games: Mutex<HashSet<Uuid>>,
     player_connections: RwLock<HashMap<String, mpsc::Sender<Message>>>,
+    // players whose connection dropped, their seats are held for a while
+    reconnecting_players: Mutex<HashMap<String, ReconnectingPlayer>>,
+    // messages for everyone connected to this instance, see `listen_to_instances`
     broadcast_sender: broadcast::Sender<Message>,
     lobby_timeouts: Mutex<HashMap<Uuid, JoinHandle<()>>>,
     game_timeouts: Mutex<HashMap<Uuid, JoinHandle<()>>>,
+    clock_timeouts: Mutex<HashMap<Uuid, JoinHandle<()>>>,
+    // games of the daily challenge with the player taking part
+    challenge_games: Mutex<HashMap<Uuid, (DailyChallenge, String)>>,
+    // by date
+    leaderboards: Mutex<HashMap<String, Leaderboard>>,
+    tournaments: Mutex<HashMap<Uuid, DuplicateTournament>>,
+    // tournament id and table index of the games played as a part of a tournament
+    tournament_tables: Mutex<HashMap<Uuid, (Uuid, usize)>>,
+    lessons: HashMap<String, Lesson>,
+    // games played as a lesson
+    tutorials: Mutex<HashMap<Uuid, Tutorial>>,
+    developer_mode: bool,
+    storage: Arc<dyn Storage>,
+    shared: Arc<dyn SharedState>,
 }
 
 impl WebSocketState {
     pub fn new() -> WebSocketState {
         WebSocketState {
+            instance: Uuid::new_v4(),
             games: Mutex::new(HashMap::new()),
             lobbies: Mutex::new(HashMap::new()),
+            changed_games: Mutex::new(HashSet::new()),
             player_connections: RwLock::new(HashMap::new()),
+            reconnecting_players: Mutex::new(HashMap::new()),
             broadcast_sender: broadcast::channel::<Message>(128).0,
             lobby_timeouts: Mutex::new(HashMap::new()),
             game_timeouts: Mutex::new(HashMap::new()),
+            clock_timeouts: Mutex::new(HashMap::new()),
+            challenge_games: Mutex::new(HashMap::new()),
+            leaderboards: Mutex::new(HashMap::new()),
+            tournaments: Mutex::new(HashMap::new()),
+            tournament_tables: Mutex::new(HashMap::new()),
+            lessons: HashMap::new(),
+            tutorials: Mutex::new(HashMap::new()),
+            developer_mode: false,
+            storage: Arc::new(NoStorage),
+            shared: Arc::new(LocalState::new()),
         }
     }
 }
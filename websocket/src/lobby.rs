@@ -1,30 +1,109 @@
-use serde::Serialize;
+use crate::payload::CreateLobbyPayload;
+use game::{Card, EndCondition, GameSettings, HouseRules, TimeControl, TimeoutPolicy};
+use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-#[derive(Serialize, TS, Clone)]
+#[derive(Serialize, Deserialize, TS, Clone)]
 #[ts(export)]
 pub struct Lobby {
     #[serde(rename = "maxPlayers")]
     pub max_players: usize,
-    #[serde(rename = "maxScore")]
-    pub max_score: usize,
+    #[serde(rename = "endCondition")]
+    pub end_condition: EndCondition,
+    #[serde(rename = "takeBackCompletedTricks")]
+    pub take_back_completed_tricks: bool,
+    #[serde(rename = "timeControl")]
+    pub time_control: Option<TimeControl>,
+    #[serde(rename = "timeoutPolicy")]
+    pub timeout_policy: TimeoutPolicy,
+    #[serde(rename = "confirmCardExchange")]
+    pub confirm_card_exchange: bool,
+    #[serde(rename = "ruleScript")]
+    pub rule_script: Option<String>,
+    pub hints: bool,
+    /// More than one table makes a duplicate tournament.
+    pub tables: usize,
     pub players: Vec<String>,
+    /// Kept from players, as it gives away all hands. Stored apart in `StoredLobby`.
+    #[serde(skip)]
+    #[ts(skip)]
+    pub deal: Option<Vec<Vec<Card>>>,
 }
 
 impl Lobby {
     pub(crate) fn new_by_player(
-        max_players: usize,
-        max_score: usize,
+        payload: &CreateLobbyPayload,
         player: &str,
+        developer_mode: bool,
     ) -> Result<Lobby, String> {
-        if max_players < 3 || max_players > 4 {
+        if !(3..=4).contains(&payload.max_players) {
             Err("Invalid lobby max players".to_string())?
         }
+        let tables = payload.tables.unwrap_or(1);
+        if tables == 0 {
+            Err("Invalid lobby tables".to_string())?
+        }
+        let fixed_rounds = matches!(payload.end_condition, EndCondition::MaxRounds { .. });
+        if tables > 1 && !fixed_rounds {
+            Err("Tournaments need a fixed number of rounds".to_string())?
+        }
+        if let Some(script) = &payload.rule_script {
+            HouseRules::compile(script).map_err(|error| error.to_string())?;
+        }
+        let deal = match &payload.debug_deal {
+            None => None,
+            Some(_) if !developer_mode => {
+                Err("Preset deals are only allowed in developer mode".to_string())?
+            }
+            Some(_) if tables > 1 => Err("Tournaments cannot have a preset deal".to_string())?,
+            Some(hands) => Some(
+                hands
+                    .iter()
+                    .map(|hand| {
+                        hand.iter()
+                            .map(|card| Card::new(card.suit, card.value))
+                            .collect::<Result<Vec<Card>, String>>()
+                    })
+                    .collect::<Result<Vec<Vec<Card>>, String>>()?,
+            ),
+        };
 
-        Ok(Lobby {
-            max_players,
-            max_score,
+        let lobby = Lobby {
+            max_players: payload.max_players,
+            end_condition: payload.end_condition,
+            take_back_completed_tricks: payload.take_back_completed_tricks,
+            time_control: payload.time_control,
+            timeout_policy: payload.timeout_policy,
+            confirm_card_exchange: payload.confirm_card_exchange,
+            rule_script: payload.rule_script.clone(),
+            hints: payload.hints,
+            tables,
             players: vec![player.to_string()],
-        })
+            deal,
+        };
+        lobby
+            .game_settings()
+            .validate_deal(lobby.max_players)
+            .map_err(|error| error.to_string())?;
+        Ok(lobby)
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.players.len() == self.max_players * self.tables
+    }
+
+    pub(crate) fn game_settings(&self) -> GameSettings {
+        GameSettings {
+            end_condition: self.end_condition,
+            take_backs: true,
+            take_back_completed_tricks: self.take_back_completed_tricks,
+            time_control: self.time_control,
+            timeout_policy: self.timeout_policy,
+            confirm_card_exchange: self.confirm_card_exchange,
+            rule_script: self.rule_script.clone(),
+            hints: self.hints,
+            deal_seeds: Vec::new(),
+            deal: self.deal.clone(),
+        }
     }
 }
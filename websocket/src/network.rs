@@ -1,14 +1,15 @@
 use crate::response::WebSocketResponse::Error;
-use crate::response::{get_obfuscated_game_details_json, ErrorResponse, ToJson};
+use crate::response::{get_game_details_json, ErrorResponse, ToJson};
+use crate::session::remember_missed_message;
+use crate::shared::{publish, SharedEvent};
 use crate::WebSocketState;
 use axum::extract::ws::Message;
 use game::Game;
 use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 pub(crate) type Sender = mpsc::Sender<Message>;
-pub(crate) type BroadcastSender = broadcast::Sender<Message>;
 
 pub(crate) async fn send_text(text: &str, sender: &mut Sender) -> Result<(), String> {
     sender
@@ -17,14 +18,41 @@ pub(crate) async fn send_text(text: &str, sender: &mut Sender) -> Result<(), Str
         .map_err(|e| e.to_string())
 }
 
-pub(crate) fn broadcast_text(
+/// Sends the text to players connected to any of the instances.
+pub(crate) fn broadcast_text(text: &str, state: &WebSocketState) -> Result<(), String> {
+    let event = SharedEvent::Broadcast {
+        text: text.to_string(),
+    };
+    publish(event, state)
+}
+
+/// Players connected to other instances get the text through them, reconnecting players
+/// once they are back.
+pub(crate) async fn send_text_to_player(
     text: &str,
-    broadcast_sender: &mut BroadcastSender,
+    player: &str,
+    state: Arc<WebSocketState>,
 ) -> Result<(), String> {
-    broadcast_sender
-        .send(Message::Text(text.to_string()))
-        .map(|_| ())
-        .map_err(|e| e.to_string())
+    let sender = state.player_connections.read().await.get(player).cloned();
+    if let Some(mut sender) = sender {
+        return send_text(text, &mut sender).await;
+    }
+    if remember_missed_message(text, player, &state).await {
+        return Ok(());
+    }
+    match state.shared.is_distributed() {
+        true => {
+            let event = SharedEvent::PlayerMessage {
+                player: player.to_string(),
+                text: text.to_string(),
+            };
+            publish(event, &state)
+        }
+        false => {
+            tracing::warn!("Tried to send a message to disconnected player {}", player);
+            Ok(())
+        }
+    }
 }
 
 pub(crate) async fn broadcast_game_to_players(
@@ -32,22 +60,9 @@ pub(crate) async fn broadcast_game_to_players(
     game: &Game,
     state: Arc<WebSocketState>,
 ) -> Result<(), String> {
-    let player_connections = state.player_connections.read().await;
-    for player in &game.players {
-        match player_connections.get(player).cloned() {
-            Some(mut sender) => {
-                send_text(
-                    &get_obfuscated_game_details_json(id, game, player),
-                    &mut sender,
-                )
-                .await?
-            }
-            None => tracing::warn!(
-                "Tried to send game with id {} to disconnected player {}",
-                id,
-                player
-            ),
-        }
+    for player in &game.active_players() {
+        let response = get_game_details_json(id, game, player)?;
+        send_text_to_player(&response, player, state.clone()).await?;
     }
     Ok(())
 }
@@ -1,4 +1,4 @@
-use game::CardSuit;
+use game::{CardSuit, EndCondition, TimeControl, TimeoutPolicy};
 use serde::Deserialize;
 use std::collections::HashSet;
 use ts_rs::TS;
@@ -30,6 +30,65 @@ pub enum WebSocketPayload {
     ClaimReadinessMove(ClaimReadinessPayload),
     #[serde(rename = "quitGame")]
     QuitGame(IdPayload),
+    #[serde(rename = "voteEndGame")]
+    VoteEndGame(IdPayload),
+    #[serde(rename = "requestTakeBack")]
+    RequestTakeBack(IdPayload),
+    #[serde(rename = "answerTakeBack")]
+    AnswerTakeBack(AnswerTakeBackPayload),
+    #[serde(rename = "withdrawExchangeCards")]
+    WithdrawExchangeCards(IdPayload),
+    #[serde(rename = "confirmExchangeCards")]
+    ConfirmExchangeCards(IdPayload),
+    #[serde(rename = "startDailyChallenge")]
+    StartDailyChallenge,
+    #[serde(rename = "getDailyLeaderboard")]
+    GetDailyLeaderboard,
+    #[serde(rename = "getTournament")]
+    GetTournament(IdPayload),
+    #[serde(rename = "getHint")]
+    GetHint(IdPayload),
+    #[serde(rename = "getGameAnalysis")]
+    GetGameAnalysis(IdPayload),
+    #[serde(rename = "listLessons")]
+    ListLessons,
+    #[serde(rename = "startTutorial")]
+    StartTutorial(StartTutorialPayload),
+    /// Marks the explanation shown in the tutorial game as read.
+    #[serde(rename = "continueTutorial")]
+    ContinueTutorial(IdPayload),
+}
+
+impl WebSocketPayload {
+    /// Game or lobby the payload acts on.
+    pub(crate) fn target_id(&self) -> Option<Uuid> {
+        match self {
+            WebSocketPayload::GetLobbyDetails(payload)
+            | WebSocketPayload::JoinLobby(payload)
+            | WebSocketPayload::QuitLobby(payload)
+            | WebSocketPayload::GetGameDetails(payload)
+            | WebSocketPayload::QuitGame(payload)
+            | WebSocketPayload::VoteEndGame(payload)
+            | WebSocketPayload::RequestTakeBack(payload)
+            | WebSocketPayload::WithdrawExchangeCards(payload)
+            | WebSocketPayload::ConfirmExchangeCards(payload)
+            | WebSocketPayload::GetHint(payload)
+            | WebSocketPayload::GetGameAnalysis(payload)
+            | WebSocketPayload::ContinueTutorial(payload) => Some(payload.id),
+            WebSocketPayload::CardExchangeMove(payload) => Some(payload.id),
+            WebSocketPayload::PlaceCardMove(payload) => Some(payload.id),
+            WebSocketPayload::ClaimReadinessMove(payload) => Some(payload.id),
+            WebSocketPayload::AnswerTakeBack(payload) => Some(payload.id),
+            WebSocketPayload::ListLobbies
+            | WebSocketPayload::CreateLobby(_)
+            | WebSocketPayload::ListGames
+            | WebSocketPayload::StartDailyChallenge
+            | WebSocketPayload::GetDailyLeaderboard
+            | WebSocketPayload::GetTournament(_)
+            | WebSocketPayload::ListLessons
+            | WebSocketPayload::StartTutorial(_) => None,
+        }
+    }
 }
 
 #[derive(Deserialize, TS)]
@@ -43,8 +102,33 @@ pub struct IdPayload {
 pub struct CreateLobbyPayload {
     #[serde(rename = "maxPlayers")]
     pub(crate) max_players: usize,
-    #[serde(rename = "maxScore")]
-    pub(crate) max_score: usize,
+    #[serde(rename = "endCondition")]
+    pub(crate) end_condition: EndCondition,
+    #[serde(rename = "takeBackCompletedTricks", default)]
+    pub(crate) take_back_completed_tricks: bool,
+    #[serde(rename = "timeControl", default)]
+    pub(crate) time_control: Option<TimeControl>,
+    #[serde(rename = "timeoutPolicy", default)]
+    pub(crate) timeout_policy: TimeoutPolicy,
+    #[serde(rename = "confirmCardExchange", default)]
+    pub(crate) confirm_card_exchange: bool,
+    #[serde(rename = "ruleScript", default)]
+    pub(crate) rule_script: Option<String>,
+    #[serde(default)]
+    pub(crate) hints: bool,
+    /// One table if not given.
+    #[serde(default)]
+    pub(crate) tables: Option<usize>,
+    /// Hands of the first round by seat, in the order players join.
+    /// Only accepted by a server running in developer mode.
+    #[serde(rename = "debugDeal", default)]
+    pub(crate) debug_deal: Option<Vec<Vec<InputCard>>>,
+}
+
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct StartTutorialPayload {
+    pub(crate) lesson: String,
 }
 
 #[derive(Deserialize, TS)]
@@ -69,6 +153,13 @@ pub struct ClaimReadinessPayload {
     pub(crate) ready: bool,
 }
 
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct AnswerTakeBackPayload {
+    pub(crate) id: Uuid,
+    pub(crate) accept: bool,
+}
+
 #[derive(PartialEq, Eq, Hash, Deserialize, TS)]
 #[ts(export)]
 pub struct InputCard {
@@ -0,0 +1,293 @@
+//! Redis backend of the state shared between instances. Lobbies, games and results are kept
+//! in hashes by id, messages go through a pub/sub channel.
+
+use crate::shared::{SharedMessage, SharedResult, SharedState};
+use crate::storage::{GameRecord, Storage, StorageResult, StoredLobby};
+use game::GameSnapshot;
+use redis::{Client, Commands, Connection, RedisResult, Script};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::sync::mpsc;
+use tokio::task::block_in_place;
+use uuid::Uuid;
+
+const CHANNEL: &str = "black-widow:messages";
+const LOBBIES_KEY: &str = "black-widow:lobbies";
+const GAMES_KEY: &str = "black-widow:games";
+const RESULTS_KEY: &str = "black-widow:results";
+const CONNECTION_SECONDS: u64 = 30;
+const LOCK_MILLIS: u64 = 10_000;
+
+// keys of locks and connections are only deleted by their owner
+const DELETE_OWNED_KEY: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+pub(crate) struct RedisState {
+    client: Client,
+    connection: Mutex<Connection>,
+}
+
+impl RedisState {
+    pub(crate) fn connect(url: &str) -> SharedResult<RedisState> {
+        let client = Client::open(url).map_err(|e| e.to_string())?;
+        let connection = client.get_connection().map_err(|e| e.to_string())?;
+        Ok(RedisState {
+            client,
+            connection: Mutex::new(connection),
+        })
+    }
+
+    // commands block on the network, meanwhile the other tasks of the async worker are moved
+    // to other workers
+    fn run<T>(&self, command: impl FnOnce(&mut Connection) -> RedisResult<T>) -> SharedResult<T> {
+        let run_command =
+            || command(&mut self.connection.lock().unwrap()).map_err(|e| e.to_string());
+        match Handle::try_current().map(|handle| handle.runtime_flavor()) {
+            Ok(RuntimeFlavor::MultiThread) => block_in_place(run_command),
+            _ => run_command(),
+        }
+    }
+
+    fn save<T: Serialize>(&self, key: &str, id: &Uuid, value: &T) -> StorageResult<()> {
+        let data = serde_json::to_string(value).map_err(|e| e.to_string())?;
+        self.run(|connection| connection.hset(key, id.to_string(), data))
+    }
+
+    fn delete(&self, key: &str, id: &Uuid) -> StorageResult<()> {
+        self.run(|connection| connection.hdel(key, id.to_string()))
+    }
+
+    fn load_one<T: DeserializeOwned>(&self, key: &str, id: &Uuid) -> StorageResult<Option<T>> {
+        let data: Option<String> = self.run(|connection| connection.hget(key, id.to_string()))?;
+        data.map(|data| {
+            serde_json::from_str(&data).map_err(|e| format!("Invalid {} entry {}: {}", key, id, e))
+        })
+        .transpose()
+    }
+
+    fn load<T: DeserializeOwned>(&self, key: &str) -> StorageResult<Vec<(Uuid, T)>> {
+        let entries: HashMap<String, String> = self.run(|connection| connection.hgetall(key))?;
+        entries
+            .into_iter()
+            .map(|(id, data)| {
+                let id = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+                let value = serde_json::from_str(&data)
+                    .map_err(|e| format!("Invalid {} entry {}: {}", key, id, e))?;
+                Ok((id, value))
+            })
+            .collect()
+    }
+
+    fn delete_owned_key(&self, key: &str, owner: &str) -> SharedResult<()> {
+        let script = Script::new(DELETE_OWNED_KEY);
+        self.run(|connection| script.key(key).arg(owner).invoke::<i32>(connection))
+            .map(|_| ())
+    }
+}
+
+fn get_connection_key(player: &str) -> String {
+    format!("black-widow:connection:{}", player)
+}
+
+fn get_lock_key(id: &Uuid) -> String {
+    format!("black-widow:lock:{}", id)
+}
+
+impl Storage for RedisState {
+    fn save_lobby(&self, id: &Uuid, lobby: &StoredLobby) -> StorageResult<()> {
+        self.save(LOBBIES_KEY, id, lobby)
+    }
+
+    fn delete_lobby(&self, id: &Uuid) -> StorageResult<()> {
+        self.delete(LOBBIES_KEY, id)
+    }
+
+    fn load_lobby(&self, id: &Uuid) -> StorageResult<Option<StoredLobby>> {
+        self.load_one(LOBBIES_KEY, id)
+    }
+
+    fn load_lobbies(&self) -> StorageResult<Vec<(Uuid, StoredLobby)>> {
+        self.load(LOBBIES_KEY)
+    }
+
+    fn save_game(&self, id: &Uuid, snapshot: &GameSnapshot) -> StorageResult<()> {
+        self.save(GAMES_KEY, id, snapshot)
+    }
+
+    fn delete_game(&self, id: &Uuid) -> StorageResult<()> {
+        self.delete(GAMES_KEY, id)
+    }
+
+    fn load_game(&self, id: &Uuid) -> StorageResult<Option<GameSnapshot>> {
+        self.load_one(GAMES_KEY, id)
+    }
+
+    fn load_games(&self) -> StorageResult<Vec<(Uuid, GameSnapshot)>> {
+        self.load(GAMES_KEY)
+    }
+
+    fn save_result(&self, id: &Uuid, result: &GameRecord) -> StorageResult<()> {
+        self.save(RESULTS_KEY, id, result)
+    }
+}
+
+impl SharedState for RedisState {
+    fn is_distributed(&self) -> bool {
+        true
+    }
+
+    fn publish(&self, message: &SharedMessage) -> SharedResult<()> {
+        let data = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        self.run(|connection| connection.publish(CHANNEL, data))
+    }
+
+    // pub/sub needs a connection of its own, which blocks while waiting for messages
+    fn subscribe(&self) -> SharedResult<mpsc::UnboundedReceiver<SharedMessage>> {
+        let mut connection = self.client.get_connection().map_err(|e| e.to_string())?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (subscribed_sender, subscribed_receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut pubsub = connection.as_pubsub();
+            let subscribed = pubsub.subscribe(CHANNEL).map_err(|e| e.to_string());
+            let failed = subscribed.is_err();
+            let _ = subscribed_sender.send(subscribed);
+            if failed {
+                return;
+            }
+
+            loop {
+                let message = pubsub
+                    .get_message()
+                    .and_then(|message| message.get_payload::<String>());
+                let data = match message {
+                    Ok(data) => data,
+                    Err(error) => {
+                        tracing::error!("Redis subscription failed: {}", error);
+                        return;
+                    }
+                };
+                match serde_json::from_str(&data) {
+                    Ok(message) => {
+                        if sender.send(message).is_err() {
+                            return;
+                        }
+                    }
+                    Err(error) => tracing::warn!("Skipping invalid message {}: {}", data, error),
+                }
+            }
+        });
+
+        subscribed_receiver.recv().map_err(|e| e.to_string())??;
+        Ok(receiver)
+    }
+
+    fn connect_player(&self, player: &str, instance: &Uuid) -> SharedResult<()> {
+        let connected: Option<String> = self.run(|connection| {
+            redis::cmd("SET")
+                .arg(get_connection_key(player))
+                .arg(instance.to_string())
+                .arg("NX")
+                .arg("EX")
+                .arg(CONNECTION_SECONDS)
+                .query(connection)
+        })?;
+        match connected {
+            Some(_) => Ok(()),
+            None => Err("This player is already connected".to_string()),
+        }
+    }
+
+    fn disconnect_player(&self, player: &str, instance: &Uuid) -> SharedResult<()> {
+        self.delete_owned_key(&get_connection_key(player), &instance.to_string())
+    }
+
+    fn refresh_players(&self, players: &[String]) -> SharedResult<()> {
+        let mut pipeline = redis::pipe();
+        for player in players {
+            pipeline
+                .expire(get_connection_key(player), CONNECTION_SECONDS as i64)
+                .ignore();
+        }
+        self.run(|connection| pipeline.query::<()>(connection))
+    }
+
+    fn try_lock(&self, id: &Uuid) -> SharedResult<Option<String>> {
+        let token = Uuid::new_v4().to_string();
+        let locked: Option<String> = self.run(|connection| {
+            redis::cmd("SET")
+                .arg(get_lock_key(id))
+                .arg(&token)
+                .arg("NX")
+                .arg("PX")
+                .arg(LOCK_MILLIS)
+                .query(connection)
+        })?;
+        Ok(locked.map(|_| token))
+    }
+
+    fn unlock(&self, id: &Uuid, token: &str) -> SharedResult<()> {
+        self.delete_owned_key(&get_lock_key(id), token)
+    }
+}
+
+// run against a local Redis, whose database is cleared first:
+// TEST_REDIS_URL=redis://localhost:6380/1 cargo test -p websocket -- --ignored --test-threads=1
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::SharedEvent;
+    use crate::storage::tests::check_storage;
+
+    fn connect() -> RedisState {
+        let url = std::env::var("TEST_REDIS_URL").expect("TEST_REDIS_URL is not set");
+        let state = RedisState::connect(&url).unwrap();
+        redis::cmd("FLUSHDB")
+            .query::<()>(&mut state.connection.lock().unwrap())
+            .unwrap();
+        state
+    }
+
+    #[test]
+    #[ignore]
+    fn redis_storage_keeps_lobbies_and_games() {
+        check_storage(&connect());
+    }
+
+    #[test]
+    #[ignore]
+    fn redis_state_is_shared_by_instances() {
+        let first = connect();
+        let second = connect();
+        let (instance, other_instance) = (Uuid::new_v4(), Uuid::new_v4());
+
+        first.connect_player("1", &instance).unwrap();
+        assert!(second.connect_player("1", &other_instance).is_err());
+        second.disconnect_player("1", &other_instance).unwrap();
+        assert!(second.connect_player("1", &other_instance).is_err());
+        first.disconnect_player("1", &instance).unwrap();
+        second.connect_player("1", &other_instance).unwrap();
+
+        let id = Uuid::new_v4();
+        let token = first.try_lock(&id).unwrap().unwrap();
+        assert_eq!(second.try_lock(&id).unwrap(), None);
+        second.unlock(&id, "other").unwrap();
+        assert_eq!(second.try_lock(&id).unwrap(), None);
+        first.unlock(&id, &token).unwrap();
+        assert!(second.try_lock(&id).unwrap().is_some());
+
+        let mut receiver = second.subscribe().unwrap();
+        let message = SharedMessage {
+            instance,
+            event: SharedEvent::GameChanged { id },
+        };
+        first.publish(&message).unwrap();
+        assert_eq!(receiver.blocking_recv(), Some(message));
+    }
+}
@@ -1,11 +1,11 @@
-use crate::helper::{get_obfuscated_exchange_cards, get_obfuscated_player_cards};
 use crate::lobby::Lobby;
 use crate::response::WebSocketResponse::{
     GameDetailsCardExchange, GameDetailsRoundFinished, GameDetailsRoundInProgress,
 };
-use game::step::GameStep;
 use game::{
-    self, Card, CardExchange, CardSuit, Game, GameSettings, RoundFinished, RoundInProgress,
+    Card, CardExchangeView, Game, GameAnalysis, GameSettings, Hint, Leaderboard, PlayerClocks,
+    PlayerView, RoundFinishedView, RoundInProgressView, StateView, TakeBackRequest,
+    TournamentStanding,
 };
 use serde::Serialize;
 use std::collections::{HashMap, HashSet};
@@ -27,13 +27,29 @@ pub enum WebSocketResponse {
     #[serde(rename = "gameCreated")]
     GameCreated(ListedGame),
     #[serde(rename = "gameDetailsCardExchange")]
-    GameDetailsCardExchange(GameDetailsResponse<CardExchangeState>),
+    GameDetailsCardExchange(GameDetailsResponse<CardExchangeView>),
     #[serde(rename = "gameDetailsRoundInProgress")]
-    GameDetailsRoundInProgress(GameDetailsResponse<RoundInProgressState>),
+    GameDetailsRoundInProgress(GameDetailsResponse<RoundInProgressView>),
     #[serde(rename = "gameDetailsCardExchange")]
-    GameDetailsRoundFinished(GameDetailsResponse<RoundFinishedState>),
+    GameDetailsRoundFinished(GameDetailsResponse<RoundFinishedView>),
     #[serde(rename = "gameDeleted")]
     GameDeleted(IdResponse),
+    #[serde(rename = "dailyLeaderboard")]
+    DailyLeaderboard(Leaderboard),
+    #[serde(rename = "tournamentStandings")]
+    TournamentStandings(TournamentResponse),
+    #[serde(rename = "hint")]
+    Hint(HintResponse),
+    #[serde(rename = "gameAnalysis")]
+    GameAnalysis(GameAnalysisResponse),
+    #[serde(rename = "lessonList")]
+    LessonList(LessonListResponse),
+    #[serde(rename = "tutorial")]
+    Tutorial(TutorialResponse),
+    #[serde(rename = "playerReconnecting")]
+    PlayerReconnecting(PlayerConnectionResponse),
+    #[serde(rename = "playerReconnected")]
+    PlayerReconnected(PlayerConnectionResponse),
     #[serde(rename = "error")]
     Error(ErrorResponse),
 }
@@ -68,7 +84,7 @@ impl GameListResponse {
         let games: Vec<ListedGame> = games
             .iter()
             .map(|(id, game)| ListedGame {
-                id: id.clone(),
+                id: *id,
                 players: game.players.to_vec(),
             })
             .collect();
@@ -91,87 +107,32 @@ pub struct GameDetailsResponse<S: Serialize> {
     pub game: ObfuscatedGame<S>,
 }
 
-impl GameDetailsResponse<CardExchangeState> {
-    pub(crate) fn new(
-        id: &Uuid,
-        game: &Game,
-        player: &str,
-        step: &GameStep<game::CardExchangeState>,
-    ) -> GameDetailsResponse<CardExchangeState> {
-        let state = CardExchangeState {
-            player_exchange_cards: get_obfuscated_exchange_cards(
-                &step.state.cards_to_exchange,
-                player,
-            ),
-            your_exchange_cards: step
-                .state
-                .cards_to_exchange
-                .get(player)
-                .unwrap_or(&HashSet::with_capacity(3))
-                .clone(),
-        };
-        let obfuscated_game = ObfuscatedGame::new(game, &step, state, player);
-        GameDetailsResponse {
-            id: id.clone(),
-            game: obfuscated_game,
+pub(crate) fn get_game_details_json(
+    id: &Uuid,
+    game: &Game,
+    player: &str,
+) -> Result<String, String> {
+    let view = game.view_for(player).map_err(|e| e.to_string())?;
+    let response = match view.state.clone() {
+        StateView::CardExchange(state) => {
+            GameDetailsCardExchange(GameDetailsResponse::new(id, view, state))
         }
-    }
-}
-
-impl GameDetailsResponse<RoundInProgressState> {
-    pub(crate) fn new(
-        id: &Uuid,
-        game: &Game,
-        player: &str,
-        step: &GameStep<game::RoundInProgressState>,
-    ) -> GameDetailsResponse<RoundInProgressState> {
-        let state = RoundInProgressState {
-            current_player: step.state.current_player.clone(),
-            table_suit: step.state.table_suit,
-            cards_on_table: step.state.cards_on_table.clone(),
-        };
-        let obfuscated_game = ObfuscatedGame::new(game, &step, state, player);
-        GameDetailsResponse {
-            id: id.clone(),
-            game: obfuscated_game,
+        StateView::RoundInProgress(state) => {
+            GameDetailsRoundInProgress(GameDetailsResponse::new(id, view, state))
         }
-    }
-}
-
-impl GameDetailsResponse<RoundFinishedState> {
-    pub(crate) fn new(
-        id: &Uuid,
-        game: &Game,
-        player: &str,
-        step: &GameStep<game::RoundFinishedState>,
-    ) -> GameDetailsResponse<RoundFinishedState> {
-        let state = RoundFinishedState {
-            players_ready: step.state.players_ready.clone(),
-        };
-        let obfuscated_game = ObfuscatedGame::new(game, &step, state, player);
-        GameDetailsResponse {
-            id: id.clone(),
-            game: obfuscated_game,
+        StateView::RoundFinished(state) => {
+            GameDetailsRoundFinished(GameDetailsResponse::new(id, view, state))
         }
-    }
+    };
+    Ok(response.to_json())
 }
 
-pub(crate) fn get_obfuscated_game_details_json(id: &Uuid, game: &Game, player: &str) -> String {
-    match &game.state {
-        CardExchange(step) => GameDetailsCardExchange(
-            GameDetailsResponse::<CardExchangeState>::new(id, &game, player, &step),
-        )
-        .to_json(),
-        RoundInProgress(step) => {
-            GameDetailsRoundInProgress(GameDetailsResponse::<RoundInProgressState>::new(
-                id, &game, player, &step,
-            ))
-            .to_json()
+impl<S: Serialize> GameDetailsResponse<S> {
+    fn new(id: &Uuid, view: PlayerView, state: S) -> GameDetailsResponse<S> {
+        GameDetailsResponse {
+            id: *id,
+            game: ObfuscatedGame::new(view, state),
         }
-        RoundFinished(step) => GameDetailsRoundFinished(
-            GameDetailsResponse::<RoundFinishedState>::new(id, &game, player, &step),
-        )
-        .to_json(),
     }
 }
 
@@ -180,6 +141,8 @@ pub(crate) fn get_obfuscated_game_details_json(id: &Uuid, game: &Game, player: &
 pub struct ObfuscatedGame<S: Serialize> {
     pub settings: GameSettings,
     pub players: Vec<String>,
+    pub round: usize,
+    pub dealer: String,
     pub scores: HashMap<String, usize>,
     #[serde(rename = "isFinished")]
     pub is_finished: bool,
@@ -187,23 +150,34 @@ pub struct ObfuscatedGame<S: Serialize> {
     pub player_decks: HashMap<String, usize>,
     #[serde(rename = "yourCards")]
     pub your_cards: HashSet<Card>,
+    #[serde(rename = "leftPlayers")]
+    pub left_players: Vec<String>,
+    #[serde(rename = "endVotes")]
+    pub end_votes: HashSet<String>,
+    #[serde(rename = "takeBackRequest")]
+    pub take_back_request: Option<TakeBackRequest>,
+    pub clocks: Option<PlayerClocks>,
+    #[serde(rename = "hintsUsed")]
+    pub hints_used: HashMap<String, usize>,
     pub state: S,
 }
 
 impl<S: Serialize> ObfuscatedGame<S> {
-    pub(crate) fn new<T>(
-        game: &Game,
-        step: &GameStep<T>,
-        state: S,
-        player: &str,
-    ) -> ObfuscatedGame<S> {
+    fn new(view: PlayerView, state: S) -> ObfuscatedGame<S> {
         ObfuscatedGame {
-            settings: game.settings.clone(),
-            players: game.players.to_vec(),
-            scores: step.scores.clone(),
-            is_finished: game.is_finished,
-            player_decks: get_obfuscated_player_cards(&step.player_decks, player),
-            your_cards: step.player_decks[player].clone(),
+            settings: view.settings,
+            players: view.players,
+            round: view.round,
+            dealer: view.dealer,
+            scores: view.scores,
+            is_finished: view.is_finished,
+            player_decks: view.player_decks,
+            your_cards: view.your_cards,
+            left_players: view.left_players,
+            end_votes: view.end_votes,
+            take_back_request: view.take_back_request,
+            clocks: view.clocks,
+            hints_used: view.hints_used,
             state,
         }
     }
@@ -211,29 +185,61 @@ impl<S: Serialize> ObfuscatedGame<S> {
 
 #[derive(Serialize, TS)]
 #[ts(export)]
-pub struct CardExchangeState {
-    #[serde(rename = "playerExchangeCards")]
-    pub player_exchange_cards: HashMap<String, bool>,
-    #[serde(rename = "yourExchangeCards")]
-    pub your_exchange_cards: HashSet<Card>,
+pub struct TournamentResponse {
+    pub id: Uuid,
+    /// Players of every table in seating order.
+    pub tables: Vec<Vec<String>>,
+    #[serde(rename = "isFinished")]
+    pub is_finished: bool,
+    pub standings: Vec<TournamentStanding>,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct HintResponse {
+    pub id: Uuid,
+    pub hint: Hint,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct GameAnalysisResponse {
+    pub id: Uuid,
+    pub analysis: GameAnalysis,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct LessonListResponse {
+    pub lessons: Vec<ListedLesson>,
 }
 
 #[derive(Serialize, TS)]
 #[ts(export)]
-pub struct RoundInProgressState {
-    #[serde(rename = "currentPlayer")]
-    pub current_player: String,
-    #[serde(rename = "tableSuit")]
-    pub table_suit: Option<CardSuit>,
-    #[serde(rename = "cardsOnTable")]
-    pub cards_on_table: HashMap<String, Card>,
+pub struct ListedLesson {
+    /// Used to start the lesson.
+    pub name: String,
+    pub title: String,
 }
 
 #[derive(Serialize, TS)]
 #[ts(export)]
-pub struct RoundFinishedState {
-    #[serde(rename = "playersReady")]
-    pub players_ready: HashMap<String, bool>,
+pub struct TutorialResponse {
+    /// Id of the game.
+    pub id: Uuid,
+    pub title: String,
+    /// Shown until the player continues, moves are rejected in the meantime.
+    pub explanation: Option<String>,
+    #[serde(rename = "isFinished")]
+    pub is_finished: bool,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct PlayerConnectionResponse {
+    /// Id of the game or lobby.
+    pub id: Uuid,
+    pub player: String,
 }
 
 #[derive(Serialize, TS)]
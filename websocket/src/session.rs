@@ -0,0 +1,318 @@
+//! Seats of players whose connection dropped are held for a while, so that they can connect
+//! again and pick up where they left off.
+//!
+//! Messages sent to such a player are kept and replayed once they are back, followed by the
+//! current state of their games and lobbies. Other players of those games and lobbies are told
+//! that the player is reconnecting. When the player does not come back in time, they quit their
+//! lobbies and bots take over their games.
+
+use crate::error::HandlerError::{ActionError, SenderError};
+use crate::game_action::{quit_game, quit_lobby};
+use crate::network::{send_text, send_text_to_player, Sender};
+use crate::response::WebSocketResponse::{LobbyDetails, PlayerReconnected, PlayerReconnecting};
+use crate::response::{
+    get_game_details_json, LobbyDetailsResponse, PlayerConnectionResponse, ToJson,
+};
+use crate::shared::{lock_shared, publish, reload_changed_game, SharedEvent};
+use crate::WebSocketState;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+static RECONNECT_GRACE_SECONDS: u64 = 60;
+// older messages are dropped, the current state is sent on reconnect anyway
+const MISSED_MESSAGES_LIMIT: usize = 128;
+
+/// Player whose connection dropped, with the messages sent to them since.
+pub(crate) struct ReconnectingPlayer {
+    timeout: JoinHandle<()>,
+    missed_messages: VecDeque<String>,
+}
+
+impl ReconnectingPlayer {
+    fn remember(&mut self, text: &str) {
+        if self.missed_messages.len() == MISSED_MESSAGES_LIMIT {
+            self.missed_messages.pop_front();
+        }
+        self.missed_messages.push_back(text.to_string());
+    }
+}
+
+/// Holds the seats of the player whose connection dropped until the grace period is over.
+pub(crate) async fn hold_seats(player: &str, state: Arc<WebSocketState>) {
+    let mut reconnecting_players = state.reconnecting_players.lock().await;
+    // connected again already
+    if state.player_connections.read().await.contains_key(player) {
+        return;
+    }
+    let reconnecting = ReconnectingPlayer {
+        timeout: tokio::spawn(schedule_release_seats(player.to_string(), state.clone())),
+        missed_messages: VecDeque::new(),
+    };
+    if let Some(previous) = reconnecting_players.insert(player.to_string(), reconnecting) {
+        previous.timeout.abort();
+    }
+    drop(reconnecting_players);
+
+    announce_to_other_players(player, false, state).await;
+}
+
+/// Replays the messages the player missed while reconnecting, then sends the current state
+/// of their games and lobbies.
+pub(crate) async fn resume_session(
+    player: &str,
+    sender: &mut Sender,
+    state: Arc<WebSocketState>,
+) -> Result<(), String> {
+    // the player could have been connected to another instance
+    if state.shared.is_distributed() {
+        let event = SharedEvent::PlayerReconnected {
+            player: player.to_string(),
+        };
+        publish(event, &state)?;
+    }
+
+    if let Some(missed_messages) = stop_holding_seats(player, state.clone()).await {
+        for text in missed_messages {
+            send_text(&text, sender).await?;
+        }
+    }
+
+    // games changed by other instances in the meantime are loaded again
+    let games: Vec<Uuid> = state
+        .games
+        .lock()
+        .await
+        .iter()
+        .filter(|(_, game)| game.active_players().iter().any(|p| p == player))
+        .map(|(id, _)| *id)
+        .collect();
+    for id in &games {
+        reload_changed_game(id, state.clone()).await?;
+    }
+
+    let mut responses = vec![];
+    for (id, game) in state.games.lock().await.iter() {
+        if game.active_players().iter().any(|p| p == player) {
+            responses.push(get_game_details_json(id, game, player)?);
+        }
+    }
+    for (id, lobby) in state.lobbies.lock().await.iter() {
+        if lobby.players.iter().any(|p| p == player) {
+            let response = LobbyDetails(LobbyDetailsResponse {
+                id: *id,
+                lobby: lobby.clone(),
+            });
+            responses.push(response.to_json());
+        }
+    }
+    for response in responses {
+        send_text(&response, sender).await?;
+    }
+    Ok(())
+}
+
+/// Cancels giving up the seats of the player, who is connected again. Returns the messages
+/// they missed, `None` when their seats were not held by this instance.
+pub(crate) async fn stop_holding_seats(
+    player: &str,
+    state: Arc<WebSocketState>,
+) -> Option<VecDeque<String>> {
+    let reconnecting = state.reconnecting_players.lock().await.remove(player)?;
+    reconnecting.timeout.abort();
+    announce_to_other_players(player, true, state).await;
+    Some(reconnecting.missed_messages)
+}
+
+/// Keeps the text for the player if they are reconnecting, returns whether they are.
+pub(crate) async fn remember_missed_message(
+    text: &str,
+    player: &str,
+    state: &WebSocketState,
+) -> bool {
+    match state.reconnecting_players.lock().await.get_mut(player) {
+        Some(reconnecting) => {
+            reconnecting.remember(text);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Keeps the text sent to everyone for all reconnecting players.
+pub(crate) async fn remember_missed_broadcast(text: &str, state: &WebSocketState) {
+    for reconnecting in state.reconnecting_players.lock().await.values_mut() {
+        reconnecting.remember(text);
+    }
+}
+
+async fn schedule_release_seats(player: String, state: Arc<WebSocketState>) {
+    sleep(Duration::from_secs(RECONNECT_GRACE_SECONDS)).await;
+    if state
+        .reconnecting_players
+        .lock()
+        .await
+        .remove(&player)
+        .is_none()
+    {
+        return;
+    }
+
+    tracing::info!("Player {} did not reconnect in time", &player);
+    release_seats(&player, state).await;
+}
+
+async fn release_seats(player: &str, state: Arc<WebSocketState>) {
+    let games: Vec<Uuid> = state
+        .games
+        .lock()
+        .await
+        .iter()
+        .filter(|(_, game)| game.active_players().iter().any(|p| p == player))
+        .map(|(id, _)| *id)
+        .collect();
+    for id in games {
+        // the game could have changed on another instance
+        let _lock = match lock_shared(&id, state.clone()).await {
+            Ok(lock) => lock,
+            Err(error) => {
+                tracing::error!("Failed to lock game with id {}: {}", id, error);
+                continue;
+            }
+        };
+        if let Err(ActionError(error) | SenderError(error)) =
+            quit_game(&id, player.to_string(), state.clone()).await
+        {
+            tracing::error!("Failed to give up seat in game with id {}: {}", id, error);
+        }
+    }
+
+    let lobbies: Vec<Uuid> = state
+        .lobbies
+        .lock()
+        .await
+        .iter()
+        .filter(|(_, lobby)| lobby.players.iter().any(|p| p == player))
+        .map(|(id, _)| *id)
+        .collect();
+    for id in lobbies {
+        let _lock = match lock_shared(&id, state.clone()).await {
+            Ok(lock) => lock,
+            Err(error) => {
+                tracing::error!("Failed to lock lobby with id {}: {}", id, error);
+                continue;
+            }
+        };
+        if let Err(ActionError(error) | SenderError(error)) =
+            quit_lobby(&id, player, state.clone()).await
+        {
+            tracing::error!("Failed to give up seat in lobby with id {}: {}", id, error);
+        }
+    }
+}
+
+async fn announce_to_other_players(player: &str, reconnected: bool, state: Arc<WebSocketState>) {
+    let mut seats: Vec<(Uuid, Vec<String>)> = state
+        .games
+        .lock()
+        .await
+        .iter()
+        .map(|(id, game)| (*id, game.active_players()))
+        .filter(|(_, players)| players.iter().any(|p| p == player))
+        .collect();
+    seats.extend(
+        state
+            .lobbies
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, lobby)| lobby.players.iter().any(|p| p == player))
+            .map(|(id, lobby)| (*id, lobby.players.clone())),
+    );
+
+    for (id, players) in seats {
+        let response = PlayerConnectionResponse {
+            id,
+            player: player.to_string(),
+        };
+        let response = match reconnected {
+            true => PlayerReconnected(response),
+            false => PlayerReconnecting(response),
+        }
+        .to_json();
+        for other_player in players.iter().filter(|p| *p != player) {
+            if let Err(error) = send_text_to_player(&response, other_player, state.clone()).await {
+                tracing::error!(error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::ws::Message;
+    use game::{Game, GameSettings};
+    use tokio::sync::mpsc;
+
+    fn get_state() -> (Arc<WebSocketState>, Uuid) {
+        let players = ["player1", "player2", "player3"].map(String::from);
+        let game = Game::from_seed(&players, GameSettings::default(), 7).unwrap();
+        let state = WebSocketState::new();
+        let id = Uuid::new_v4();
+        state.games.try_lock().unwrap().insert(id, game);
+        (Arc::new(state), id)
+    }
+
+    async fn connect(player: &str, state: &WebSocketState) -> mpsc::Receiver<Message> {
+        let (sender, receiver) = mpsc::channel(128);
+        state
+            .player_connections
+            .write()
+            .await
+            .insert(player.to_string(), sender);
+        receiver
+    }
+
+    fn receive_type(receiver: &mut mpsc::Receiver<Message>) -> String {
+        let Ok(Message::Text(text)) = receiver.try_recv() else {
+            panic!("No message received");
+        };
+        let response: serde_json::Value = serde_json::from_str(&text).unwrap();
+        response["type"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn missed_messages_and_game_are_sent_on_reconnect() {
+        let (state, id) = get_state();
+        let mut other_receiver = connect("player2", &state).await;
+
+        hold_seats("player1", state.clone()).await;
+        assert_eq!(receive_type(&mut other_receiver), "playerReconnecting");
+        send_text_to_player(r#"{"type":"missed"}"#, "player1", state.clone())
+            .await
+            .unwrap();
+
+        let mut receiver = connect("player1", &state).await;
+        let mut sender = state.player_connections.read().await["player1"].clone();
+        resume_session("player1", &mut sender, state.clone())
+            .await
+            .unwrap();
+        assert_eq!(receive_type(&mut receiver), "missed");
+        assert_eq!(receive_type(&mut receiver), "gameDetailsCardExchange");
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(receive_type(&mut other_receiver), "playerReconnected");
+        assert!(state.reconnecting_players.lock().await.is_empty());
+        assert!(state.games.lock().await[&id].left_players.is_empty());
+    }
+
+    #[tokio::test]
+    async fn seats_are_given_up_after_grace_period() {
+        let (state, id) = get_state();
+        release_seats("player1", state.clone()).await;
+        assert_eq!(state.games.lock().await[&id].left_players, vec!["player1"]);
+    }
+}
@@ -0,0 +1,368 @@
+//! State shared between instances of the server, so that several of them can run behind
+//! a load balancer.
+//!
+//! Every instance keeps games and lobbies in memory. Changes are stored (see the `storage`
+//! module) and announced to the other instances, which load the changed lobby again right
+//! away. Replaying a game is costly, so a changed game is only loaded again once it is needed.
+//! Messages for all players and for players connected to another instance are passed through
+//! the instances as well. Daily challenges, tournaments and lessons stay with the instance
+//! which started them.
+
+use crate::network::send_text;
+use crate::session::{remember_missed_broadcast, remember_missed_message, stop_holding_seats};
+use crate::WebSocketState;
+use axum::extract::ws::Message;
+use game::Game;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+const LOCK_RETRY_MILLIS: u64 = 10;
+const CONNECTION_REFRESH_SECONDS: u64 = 10;
+
+pub(crate) type SharedResult<T> = Result<T, String>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum SharedEvent {
+    /// Sent to every connected player.
+    Broadcast {
+        text: String,
+    },
+    /// Sent to the player by the instance they are connected to.
+    PlayerMessage {
+        player: String,
+        text: String,
+    },
+    /// The player connected again, their seats are no longer held.
+    PlayerReconnected {
+        player: String,
+    },
+    GameChanged {
+        id: Uuid,
+    },
+    GameDeleted {
+        id: Uuid,
+    },
+    LobbyChanged {
+        id: Uuid,
+    },
+    LobbyDeleted {
+        id: Uuid,
+    },
+}
+
+/// Event with the instance it was published by.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SharedMessage {
+    pub instance: Uuid,
+    pub event: SharedEvent,
+}
+
+pub(crate) trait SharedState: Send + Sync {
+    /// Whether other instances change games and lobbies too.
+    fn is_distributed(&self) -> bool;
+    /// Delivers the message to every instance, this one included.
+    fn publish(&self, message: &SharedMessage) -> SharedResult<()>;
+    /// Messages published by all instances in order, can only be taken once.
+    fn subscribe(&self) -> SharedResult<mpsc::UnboundedReceiver<SharedMessage>>;
+    /// Fails when the player is already connected to any of the instances.
+    fn connect_player(&self, player: &str, instance: &Uuid) -> SharedResult<()>;
+    fn disconnect_player(&self, player: &str, instance: &Uuid) -> SharedResult<()>;
+    /// Connections which are not refreshed expire, in case their instance stopped.
+    fn refresh_players(&self, players: &[String]) -> SharedResult<()>;
+    /// Locks the game or lobby for changes by other instances, `None` while it is locked.
+    /// The returned token unlocks it, the lock expires on its own as well.
+    fn try_lock(&self, id: &Uuid) -> SharedResult<Option<String>>;
+    fn unlock(&self, id: &Uuid, token: &str) -> SharedResult<()>;
+}
+
+/// Single instance, messages only go around in memory.
+pub(crate) struct LocalState {
+    sender: mpsc::UnboundedSender<SharedMessage>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<SharedMessage>>>,
+}
+
+impl LocalState {
+    pub(crate) fn new() -> LocalState {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        LocalState {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+}
+
+impl SharedState for LocalState {
+    fn is_distributed(&self) -> bool {
+        false
+    }
+
+    fn publish(&self, message: &SharedMessage) -> SharedResult<()> {
+        self.sender.send(message.clone()).map_err(|e| e.to_string())
+    }
+
+    fn subscribe(&self) -> SharedResult<mpsc::UnboundedReceiver<SharedMessage>> {
+        self.receiver
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or("Already subscribed".to_string())
+    }
+
+    // connections of the single instance are checked by the instance itself
+    fn connect_player(&self, _player: &str, _instance: &Uuid) -> SharedResult<()> {
+        Ok(())
+    }
+
+    fn disconnect_player(&self, _player: &str, _instance: &Uuid) -> SharedResult<()> {
+        Ok(())
+    }
+
+    fn refresh_players(&self, _players: &[String]) -> SharedResult<()> {
+        Ok(())
+    }
+
+    // the maps of the instance are locked anyway
+    fn try_lock(&self, _id: &Uuid) -> SharedResult<Option<String>> {
+        Ok(Some(String::new()))
+    }
+
+    fn unlock(&self, _id: &Uuid, _token: &str) -> SharedResult<()> {
+        Ok(())
+    }
+}
+
+pub(crate) fn publish(event: SharedEvent, state: &WebSocketState) -> SharedResult<()> {
+    state.shared.publish(&SharedMessage {
+        instance: state.instance,
+        event,
+    })
+}
+
+/// Holds the lock of a game or lobby across instances until dropped.
+pub(crate) struct SharedLock {
+    id: Uuid,
+    token: String,
+    state: Arc<WebSocketState>,
+}
+
+impl Drop for SharedLock {
+    fn drop(&mut self) {
+        if let Err(error) = self.state.shared.unlock(&self.id, &self.token) {
+            tracing::error!("Failed to unlock {}: {}", self.id, error);
+        }
+    }
+}
+
+/// Waits for the lock of the game or lobby, then loads the game again if other instances
+/// changed it.
+pub(crate) async fn lock_shared(id: &Uuid, state: Arc<WebSocketState>) -> SharedResult<SharedLock> {
+    let token = loop {
+        match state.shared.try_lock(id)? {
+            Some(token) => break token,
+            None => sleep(Duration::from_millis(LOCK_RETRY_MILLIS)).await,
+        }
+    };
+    let lock = SharedLock {
+        id: *id,
+        token,
+        state: state.clone(),
+    };
+
+    reload_changed_game(id, state).await?;
+    Ok(lock)
+}
+
+/// Loads the game again if other instances changed it since it was last loaded.
+pub(crate) async fn reload_changed_game(id: &Uuid, state: Arc<WebSocketState>) -> SharedResult<()> {
+    if state.changed_games.lock().await.remove(id) {
+        reload_game(id, state).await?;
+    }
+    Ok(())
+}
+
+async fn reload_game(id: &Uuid, state: Arc<WebSocketState>) -> SharedResult<()> {
+    let snapshot = state.storage.load_game(id)?;
+    let mut games = state.games.lock().await;
+    match snapshot {
+        Some(snapshot) => {
+            games.insert(*id, Game::from_snapshot(&snapshot).map_err(|e| e.0)?);
+        }
+        None => {
+            games.remove(id);
+        }
+    }
+    Ok(())
+}
+
+async fn reload_lobby(id: &Uuid, state: Arc<WebSocketState>) -> SharedResult<()> {
+    let lobby = state.storage.load_lobby(id)?;
+    let mut lobbies = state.lobbies.lock().await;
+    match lobby {
+        Some(lobby) => {
+            lobbies.insert(*id, lobby.into_lobby());
+        }
+        None => {
+            lobbies.remove(id);
+        }
+    }
+    Ok(())
+}
+
+/// Passes messages of all instances to the players connected to this one, and keeps games
+/// and lobbies changed by other instances up to date.
+pub(crate) async fn listen_to_instances(
+    mut receiver: mpsc::UnboundedReceiver<SharedMessage>,
+    state: Arc<WebSocketState>,
+) {
+    while let Some(message) = receiver.recv().await {
+        if let Err(error) = handle_shared_message(message, state.clone()).await {
+            tracing::error!(error);
+        }
+    }
+    tracing::error!("Stopped listening to other instances");
+}
+
+async fn handle_shared_message(
+    message: SharedMessage,
+    state: Arc<WebSocketState>,
+) -> SharedResult<()> {
+    match message.event {
+        // nobody to send it to is fine
+        SharedEvent::Broadcast { text } => {
+            remember_missed_broadcast(&text, &state).await;
+            let _ = state.broadcast_sender.send(Message::Text(text));
+        }
+        SharedEvent::PlayerMessage { player, text } => {
+            let sender = state.player_connections.read().await.get(&player).cloned();
+            match sender {
+                Some(mut sender) => send_text(&text, &mut sender).await?,
+                None => {
+                    remember_missed_message(&text, &player, &state).await;
+                }
+            }
+        }
+        _ if message.instance == state.instance => {}
+        // messages missed in the meantime are not passed on, the player gets the current state
+        SharedEvent::PlayerReconnected { player } => {
+            stop_holding_seats(&player, state.clone()).await;
+        }
+        // the instance which changed the game runs its clock and timeouts from now on
+        SharedEvent::GameChanged { id } => {
+            abort_timeouts(&id, state.clone()).await;
+            // new games are loaded right away, so that they are listed
+            let is_known = state.games.lock().await.contains_key(&id);
+            match is_known {
+                true => {
+                    state.changed_games.lock().await.insert(id);
+                }
+                false => reload_game(&id, state.clone()).await?,
+            }
+        }
+        SharedEvent::GameDeleted { id } => {
+            abort_timeouts(&id, state.clone()).await;
+            state.games.lock().await.remove(&id);
+            state.changed_games.lock().await.remove(&id);
+        }
+        SharedEvent::LobbyChanged { id } => reload_lobby(&id, state.clone()).await?,
+        SharedEvent::LobbyDeleted { id } => {
+            if let Some(timeout_handle) = state.lobby_timeouts.lock().await.remove(&id) {
+                timeout_handle.abort();
+            }
+            state.lobbies.lock().await.remove(&id);
+        }
+    }
+    Ok(())
+}
+
+/// Refreshes the connections of players connected to this instance.
+pub(crate) async fn keep_players_connected(state: Arc<WebSocketState>) {
+    if !state.shared.is_distributed() {
+        return;
+    }
+    loop {
+        sleep(Duration::from_secs(CONNECTION_REFRESH_SECONDS)).await;
+        let players: Vec<String> = state
+            .player_connections
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect();
+        if let Err(error) = state.shared.refresh_players(&players) {
+            tracing::error!("Failed to refresh connected players: {}", error);
+        }
+    }
+}
+
+async fn abort_timeouts(id: &Uuid, state: Arc<WebSocketState>) {
+    if let Some(timeout_handle) = state.clock_timeouts.lock().await.remove(id) {
+        timeout_handle.abort();
+    }
+    if let Some(timeout_handle) = state.game_timeouts.lock().await.remove(id) {
+        timeout_handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use game::GameSettings;
+
+    #[test]
+    fn local_state_delivers_messages_in_order() {
+        let state = LocalState::new();
+        let mut receiver = state.subscribe().unwrap();
+        assert!(state.subscribe().is_err());
+
+        let instance = Uuid::new_v4();
+        let messages = [
+            SharedEvent::Broadcast {
+                text: "first".to_string(),
+            },
+            SharedEvent::GameChanged { id: Uuid::new_v4() },
+        ]
+        .map(|event| SharedMessage { instance, event });
+        for message in &messages {
+            state.publish(message).unwrap();
+        }
+        for message in messages {
+            assert_eq!(receiver.try_recv().unwrap(), message);
+        }
+    }
+
+    #[tokio::test]
+    async fn games_changed_by_other_instances_are_loaded_once_needed() {
+        let mut state = WebSocketState::new();
+        state.storage = Arc::new(MemoryStorage::default());
+        let state = Arc::new(state);
+        let players = ["1", "2", "3"].map(String::from);
+        let game = Game::from_seed(&players, GameSettings::default(), 7).unwrap();
+        let (id, new_id) = (Uuid::new_v4(), Uuid::new_v4());
+        state.games.lock().await.insert(id, game.clone());
+        let mut changed_game = game.clone();
+        changed_game.left_players = vec!["1".to_string()];
+        for id in [id, new_id] {
+            state
+                .storage
+                .save_game(&id, &changed_game.snapshot())
+                .unwrap();
+            let message = SharedMessage {
+                instance: Uuid::new_v4(),
+                event: SharedEvent::GameChanged { id },
+            };
+            handle_shared_message(message, state.clone()).await.unwrap();
+        }
+        assert!(state.games.lock().await[&id].left_players.is_empty());
+        assert_eq!(state.games.lock().await[&new_id].left_players, vec!["1"]);
+
+        let _lock = lock_shared(&id, state.clone()).await.unwrap();
+        assert_eq!(state.games.lock().await[&id].left_players, vec!["1"]);
+        assert!(state.changed_games.lock().await.is_empty());
+    }
+}
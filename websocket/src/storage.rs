@@ -0,0 +1,557 @@
+//! Storage of lobbies, running games and results of finished games, so that they outlive
+//! a restart of the server. Games are stored as snapshots, which are restored by replaying
+//! their history.
+
+use crate::game_action::schedule_delete_game;
+use crate::lobby::Lobby;
+use crate::shared::{publish, SharedEvent};
+use crate::timeout::{restart_game_clock, schedule_delete_lobby};
+use crate::WebSocketState;
+use game::{Card, Game, GameSnapshot, SimpleBot, SystemClock};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::sync::Arc;
+#[cfg(test)]
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub(crate) type StorageResult<T> = Result<T, String>;
+
+/// Saving replaces whatever was stored under the id before.
+pub(crate) trait Storage: Send + Sync {
+    fn save_lobby(&self, id: &Uuid, lobby: &StoredLobby) -> StorageResult<()>;
+    fn delete_lobby(&self, id: &Uuid) -> StorageResult<()>;
+    fn load_lobby(&self, id: &Uuid) -> StorageResult<Option<StoredLobby>>;
+    fn load_lobbies(&self) -> StorageResult<Vec<(Uuid, StoredLobby)>>;
+    fn save_game(&self, id: &Uuid, snapshot: &GameSnapshot) -> StorageResult<()>;
+    fn delete_game(&self, id: &Uuid) -> StorageResult<()>;
+    fn load_game(&self, id: &Uuid) -> StorageResult<Option<GameSnapshot>>;
+    fn load_games(&self) -> StorageResult<Vec<(Uuid, GameSnapshot)>>;
+    /// Results are kept after the finished game is deleted.
+    fn save_result(&self, id: &Uuid, result: &GameRecord) -> StorageResult<()>;
+}
+
+/// Lobby together with its preset deal, which is not serialized with the lobby sent to players.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct StoredLobby {
+    // flattened, so lobbies stored before the deal was kept still load
+    #[serde(flatten)]
+    pub lobby: Lobby,
+    pub deal: Option<Vec<Vec<Card>>>,
+}
+
+impl StoredLobby {
+    pub(crate) fn new(lobby: &Lobby) -> StoredLobby {
+        StoredLobby {
+            lobby: lobby.clone(),
+            deal: lobby.deal.clone(),
+        }
+    }
+
+    pub(crate) fn into_lobby(self) -> Lobby {
+        Lobby {
+            deal: self.deal,
+            ..self.lobby
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct GameRecord {
+    pub players: Vec<String>,
+    pub scores: HashMap<String, usize>,
+    /// In the text format of the hand history.
+    pub history: String,
+}
+
+impl GameRecord {
+    pub(crate) fn new(game: &Game) -> GameRecord {
+        GameRecord {
+            players: game.players.clone(),
+            scores: game.scores().clone(),
+            history: game.history().to_string(),
+        }
+    }
+}
+
+/// Brings back the stored lobbies and games, their timeouts start over. Daily challenges,
+/// tournaments and lessons are not stored, so their games go on as regular ones.
+pub(crate) async fn restore_from_storage(state: Arc<WebSocketState>) {
+    let (lobbies, snapshots) = match (state.storage.load_lobbies(), state.storage.load_games()) {
+        (Ok(lobbies), Ok(snapshots)) => (lobbies, snapshots),
+        (Err(error), _) | (_, Err(error)) => {
+            tracing::error!("Failed to load stored lobbies and games: {}", error);
+            return;
+        }
+    };
+
+    let number_of_lobbies = lobbies.len();
+    for (id, lobby) in lobbies {
+        state.lobbies.lock().await.insert(id, lobby.into_lobby());
+        let timeout_handle = tokio::spawn(schedule_delete_lobby(id, state.clone()));
+        state.lobby_timeouts.lock().await.insert(id, timeout_handle);
+    }
+
+    let mut number_of_games = 0;
+    for (id, snapshot) in snapshots {
+        let mut game = match Game::from_snapshot(&snapshot) {
+            Ok(game) => game,
+            Err(error) => {
+                tracing::warn!("Skipping stored game with id {}: {}", id, error);
+                continue;
+            }
+        };
+        // the time the server was down is not charged to anyone, unless other instances
+        // kept the game running
+        if let Some(clocks) = game
+            .clocks
+            .as_mut()
+            .filter(|_| !state.shared.is_distributed())
+        {
+            clocks.updated_at = None;
+        }
+        game.advance(&SystemClock, &SimpleBot);
+        restart_game_clock(&id, &game, state.clone()).await;
+        if game.is_finished {
+            schedule_delete_game(&id, state.clone()).await;
+        }
+        state.games.lock().await.insert(id, game);
+        number_of_games += 1;
+    }
+    tracing::info!(
+        "Restored {} lobbies and {} games",
+        number_of_lobbies,
+        number_of_games
+    );
+}
+
+/// Checkpoints the game, and records its result once it is finished. Other instances are
+/// told about every change.
+pub(crate) fn store_game(id: &Uuid, game: &Game, state: Arc<WebSocketState>) {
+    let mut result = state.storage.save_game(id, &game.snapshot());
+    if game.is_finished {
+        result = result.and_then(|_| state.storage.save_result(id, &GameRecord::new(game)));
+    }
+    let result = result.and_then(|_| publish(SharedEvent::GameChanged { id: *id }, &state));
+    if let Err(error) = result {
+        tracing::error!("Failed to store game with id {}: {}", id, error);
+    }
+}
+
+pub(crate) fn forget_game(id: &Uuid, state: Arc<WebSocketState>) {
+    let result = state
+        .storage
+        .delete_game(id)
+        .and_then(|_| publish(SharedEvent::GameDeleted { id: *id }, &state));
+    if let Err(error) = result {
+        tracing::error!("Failed to delete stored game with id {}: {}", id, error);
+    }
+}
+
+pub(crate) fn store_lobby(id: &Uuid, lobby: &Lobby, state: Arc<WebSocketState>) {
+    let result = state
+        .storage
+        .save_lobby(id, &StoredLobby::new(lobby))
+        .and_then(|_| publish(SharedEvent::LobbyChanged { id: *id }, &state));
+    if let Err(error) = result {
+        tracing::error!("Failed to store lobby with id {}: {}", id, error);
+    }
+}
+
+pub(crate) fn forget_lobby(id: &Uuid, state: Arc<WebSocketState>) {
+    let result = state
+        .storage
+        .delete_lobby(id)
+        .and_then(|_| publish(SharedEvent::LobbyDeleted { id: *id }, &state));
+    if let Err(error) = result {
+        tracing::error!("Failed to delete stored lobby with id {}: {}", id, error);
+    }
+}
+
+/// Keeps nothing, used when no database is configured.
+pub(crate) struct NoStorage;
+
+impl Storage for NoStorage {
+    fn save_lobby(&self, _id: &Uuid, _lobby: &StoredLobby) -> StorageResult<()> {
+        Ok(())
+    }
+
+    fn delete_lobby(&self, _id: &Uuid) -> StorageResult<()> {
+        Ok(())
+    }
+
+    fn load_lobby(&self, _id: &Uuid) -> StorageResult<Option<StoredLobby>> {
+        Ok(None)
+    }
+
+    fn load_lobbies(&self) -> StorageResult<Vec<(Uuid, StoredLobby)>> {
+        Ok(Vec::new())
+    }
+
+    fn save_game(&self, _id: &Uuid, _snapshot: &GameSnapshot) -> StorageResult<()> {
+        Ok(())
+    }
+
+    fn delete_game(&self, _id: &Uuid) -> StorageResult<()> {
+        Ok(())
+    }
+
+    fn load_game(&self, _id: &Uuid) -> StorageResult<Option<GameSnapshot>> {
+        Ok(None)
+    }
+
+    fn load_games(&self) -> StorageResult<Vec<(Uuid, GameSnapshot)>> {
+        Ok(Vec::new())
+    }
+
+    fn save_result(&self, _id: &Uuid, _result: &GameRecord) -> StorageResult<()> {
+        Ok(())
+    }
+}
+
+/// Keeps everything for as long as it lives, results are never evicted.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemoryStorage {
+    lobbies: Mutex<HashMap<Uuid, StoredLobby>>,
+    games: Mutex<HashMap<Uuid, GameSnapshot>>,
+    results: Mutex<HashMap<Uuid, GameRecord>>,
+}
+
+#[cfg(test)]
+impl Storage for MemoryStorage {
+    fn save_lobby(&self, id: &Uuid, lobby: &StoredLobby) -> StorageResult<()> {
+        self.lobbies.lock().unwrap().insert(*id, lobby.clone());
+        Ok(())
+    }
+
+    fn delete_lobby(&self, id: &Uuid) -> StorageResult<()> {
+        self.lobbies.lock().unwrap().remove(id);
+        Ok(())
+    }
+
+    fn load_lobby(&self, id: &Uuid) -> StorageResult<Option<StoredLobby>> {
+        Ok(self.lobbies.lock().unwrap().get(id).cloned())
+    }
+
+    fn load_lobbies(&self) -> StorageResult<Vec<(Uuid, StoredLobby)>> {
+        Ok(clone_entries(&self.lobbies.lock().unwrap()))
+    }
+
+    fn save_game(&self, id: &Uuid, snapshot: &GameSnapshot) -> StorageResult<()> {
+        self.games.lock().unwrap().insert(*id, snapshot.clone());
+        Ok(())
+    }
+
+    fn delete_game(&self, id: &Uuid) -> StorageResult<()> {
+        self.games.lock().unwrap().remove(id);
+        Ok(())
+    }
+
+    fn load_game(&self, id: &Uuid) -> StorageResult<Option<GameSnapshot>> {
+        Ok(self.games.lock().unwrap().get(id).cloned())
+    }
+
+    fn load_games(&self) -> StorageResult<Vec<(Uuid, GameSnapshot)>> {
+        Ok(clone_entries(&self.games.lock().unwrap()))
+    }
+
+    fn save_result(&self, id: &Uuid, result: &GameRecord) -> StorageResult<()> {
+        self.results.lock().unwrap().insert(*id, result.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn clone_entries<T: Clone>(entries: &HashMap<Uuid, T>) -> Vec<(Uuid, T)> {
+    entries
+        .iter()
+        .map(|(id, value)| (*id, value.clone()))
+        .collect()
+}
+
+const LOBBIES_TABLE: &str = "lobbies";
+const GAMES_TABLE: &str = "games";
+const RESULTS_TABLE: &str = "results";
+
+type Job = Box<dyn FnOnce(&Connection) + Send>;
+
+/// Embedded SQLite database, every table holds JSON documents by id. The connection belongs
+/// to a thread of its own, which runs the queued statements in order, so that saving does
+/// not wait for the disk while the games are locked.
+pub(crate) struct SqliteStorage {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl SqliteStorage {
+    /// Creates the database file and its tables when they do not exist yet.
+    pub(crate) fn open(path: &str) -> StorageResult<SqliteStorage> {
+        let connection = Connection::open(path).map_err(|e| e.to_string())?;
+        for table in [LOBBIES_TABLE, GAMES_TABLE, RESULTS_TABLE] {
+            connection
+                .execute(
+                    &format!(
+                        "CREATE TABLE IF NOT EXISTS {} (id TEXT PRIMARY KEY, data TEXT NOT NULL)",
+                        table
+                    ),
+                    (),
+                )
+                .map_err(|e| e.to_string())?;
+        }
+
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        std::thread::spawn(move || {
+            for job in receiver {
+                job(&connection);
+            }
+        });
+        Ok(SqliteStorage { jobs })
+    }
+
+    fn queue(&self, job: Job) -> StorageResult<()> {
+        self.jobs
+            .send(job)
+            .map_err(|_| "Storage thread stopped".to_string())
+    }
+
+    /// Waits for the statements queued before.
+    fn query<T: Send + 'static>(
+        &self,
+        query: impl FnOnce(&Connection) -> StorageResult<T> + Send + 'static,
+    ) -> StorageResult<T> {
+        let (sender, receiver) = mpsc::channel();
+        self.queue(Box::new(move |connection| {
+            let _ = sender.send(query(connection));
+        }))?;
+        receiver.recv().map_err(|e| e.to_string())?
+    }
+
+    /// Failures are only logged, as nobody waits for them.
+    fn execute(
+        &self,
+        table: &'static str,
+        id: &Uuid,
+        statement: String,
+        data: Option<String>,
+    ) -> StorageResult<()> {
+        let id = *id;
+        self.queue(Box::new(move |connection| {
+            let result = match data {
+                Some(data) => connection.execute(&statement, params![id.to_string(), data]),
+                None => connection.execute(&statement, params![id.to_string()]),
+            };
+            if let Err(error) = result {
+                tracing::error!("Failed to write {} entry {}: {}", table, id, error);
+            }
+        }))
+    }
+
+    fn save<T: Serialize>(&self, table: &'static str, id: &Uuid, value: &T) -> StorageResult<()> {
+        let data = serde_json::to_string(value).map_err(|e| e.to_string())?;
+        self.execute(
+            table,
+            id,
+            format!(
+                "INSERT OR REPLACE INTO {} (id, data) VALUES (?1, ?2)",
+                table
+            ),
+            Some(data),
+        )
+    }
+
+    fn delete(&self, table: &'static str, id: &Uuid) -> StorageResult<()> {
+        self.execute(
+            table,
+            id,
+            format!("DELETE FROM {} WHERE id = ?1", table),
+            None,
+        )
+    }
+
+    fn load_one<T: DeserializeOwned + Send + 'static>(
+        &self,
+        table: &'static str,
+        id: &Uuid,
+    ) -> StorageResult<Option<T>> {
+        let id = *id;
+        self.query(move |connection| {
+            let data: Option<String> = connection
+                .query_row(
+                    &format!("SELECT data FROM {} WHERE id = ?1", table),
+                    params![id.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+            data.map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| format!("Invalid {} entry {}: {}", table, id, e))
+            })
+            .transpose()
+        })
+    }
+
+    fn load<T: DeserializeOwned + Send + 'static>(
+        &self,
+        table: &'static str,
+    ) -> StorageResult<Vec<(Uuid, T)>> {
+        self.query(move |connection| {
+            let mut statement = connection
+                .prepare(&format!("SELECT id, data FROM {}", table))
+                .map_err(|e| e.to_string())?;
+            let rows = statement
+                .query_map((), |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })
+                .map_err(|e| e.to_string())?;
+
+            let mut entries = Vec::new();
+            for row in rows {
+                let (id, data) = row.map_err(|e| e.to_string())?;
+                let id = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+                let value = serde_json::from_str(&data)
+                    .map_err(|e| format!("Invalid {} entry {}: {}", table, id, e))?;
+                entries.push((id, value));
+            }
+            Ok(entries)
+        })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn save_lobby(&self, id: &Uuid, lobby: &StoredLobby) -> StorageResult<()> {
+        self.save(LOBBIES_TABLE, id, lobby)
+    }
+
+    fn delete_lobby(&self, id: &Uuid) -> StorageResult<()> {
+        self.delete(LOBBIES_TABLE, id)
+    }
+
+    fn load_lobby(&self, id: &Uuid) -> StorageResult<Option<StoredLobby>> {
+        self.load_one(LOBBIES_TABLE, id)
+    }
+
+    fn load_lobbies(&self) -> StorageResult<Vec<(Uuid, StoredLobby)>> {
+        self.load(LOBBIES_TABLE)
+    }
+
+    fn save_game(&self, id: &Uuid, snapshot: &GameSnapshot) -> StorageResult<()> {
+        self.save(GAMES_TABLE, id, snapshot)
+    }
+
+    fn delete_game(&self, id: &Uuid) -> StorageResult<()> {
+        self.delete(GAMES_TABLE, id)
+    }
+
+    fn load_game(&self, id: &Uuid) -> StorageResult<Option<GameSnapshot>> {
+        self.load_one(GAMES_TABLE, id)
+    }
+
+    fn load_games(&self) -> StorageResult<Vec<(Uuid, GameSnapshot)>> {
+        self.load(GAMES_TABLE)
+    }
+
+    fn save_result(&self, id: &Uuid, result: &GameRecord) -> StorageResult<()> {
+        self.save(RESULTS_TABLE, id, result)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use game::{EndCondition, GameSettings, TimeoutPolicy};
+
+    fn get_lobby() -> Lobby {
+        Lobby {
+            max_players: 3,
+            end_condition: EndCondition::MaxScore { max_score: 100 },
+            take_back_completed_tricks: false,
+            time_control: None,
+            timeout_policy: TimeoutPolicy::default(),
+            confirm_card_exchange: false,
+            rule_script: None,
+            hints: true,
+            tables: 1,
+            players: vec!["1".to_string()],
+            deal: None,
+        }
+    }
+
+    fn get_game() -> Game {
+        let players = vec!["1".to_string(), "2".to_string(), "3".to_string()];
+        let mut game = Game::from_seed(&players, GameSettings::default(), 11).unwrap();
+        game.left_players = players[1..].to_vec();
+        game.play_bots(&SimpleBot);
+        game
+    }
+
+    pub(crate) fn check_storage(storage: &dyn Storage) {
+        let lobby_id = Uuid::new_v4();
+        let mut lobby = get_lobby();
+        storage
+            .save_lobby(&lobby_id, &StoredLobby::new(&lobby))
+            .unwrap();
+        lobby.players.push("2".to_string());
+        let deal = ["2C", "3C", "4C"].map(|card| vec![card.parse().unwrap()]);
+        lobby.deal = Some(deal.to_vec());
+        storage
+            .save_lobby(&lobby_id, &StoredLobby::new(&lobby))
+            .unwrap();
+        let lobbies = storage.load_lobbies().unwrap();
+        assert_eq!(lobbies.len(), 1);
+        assert_eq!(lobbies[0].0, lobby_id);
+        let restored = lobbies[0].1.clone().into_lobby();
+        assert_eq!(restored.players, lobby.players);
+        assert_eq!(restored.deal, lobby.deal);
+
+        let game_id = Uuid::new_v4();
+        let game = get_game();
+        storage.save_game(&game_id, &game.snapshot()).unwrap();
+        let games = storage.load_games().unwrap();
+        assert_eq!(games, vec![(game_id, game.snapshot())]);
+        assert_eq!(storage.load_game(&game_id).unwrap(), Some(game.snapshot()));
+        let restored = Game::from_snapshot(&games[0].1).unwrap();
+        assert_eq!(restored.view_for("1"), game.view_for("1"));
+
+        storage.delete_lobby(&lobby_id).unwrap();
+        storage.delete_game(&game_id).unwrap();
+        assert!(storage.load_lobbies().unwrap().is_empty());
+        assert!(storage.load_games().unwrap().is_empty());
+        assert!(storage.load_lobby(&lobby_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn memory_storage_keeps_lobbies_and_games() {
+        check_storage(&MemoryStorage::default());
+    }
+
+    #[test]
+    fn no_storage_keeps_nothing() {
+        let storage = NoStorage;
+        let id = Uuid::new_v4();
+        storage.save_game(&id, &get_game().snapshot()).unwrap();
+        assert!(storage.load_game(&id).unwrap().is_none());
+        assert!(storage.load_games().unwrap().is_empty());
+    }
+
+    #[test]
+    fn sqlite_storage_keeps_lobbies_and_games() {
+        check_storage(&SqliteStorage::open(":memory:").unwrap());
+    }
+
+    #[test]
+    fn results_outlive_deleted_games() {
+        let storage = SqliteStorage::open(":memory:").unwrap();
+        let id = Uuid::new_v4();
+        let game = get_game();
+        storage.save_game(&id, &game.snapshot()).unwrap();
+        storage.save_result(&id, &GameRecord::new(&game)).unwrap();
+        storage.delete_game(&id).unwrap();
+
+        let results: Vec<(Uuid, GameRecord)> = storage.load(RESULTS_TABLE).unwrap();
+        assert_eq!(results, vec![(id, GameRecord::new(&game))]);
+    }
+}